@@ -0,0 +1,78 @@
+//! Altitude constraint (2D navigation), toggled at runtime
+use gnss_rtk::prelude::Config;
+
+/// Altitude the solver is constrained to, once toggled: the configured altitude,
+/// then that of the latest unconstrained fix
+#[derive(Debug, Default)]
+pub struct AltitudeConstraint {
+    /// Altitude to constrain to (m)
+    altitude: Option<f64>,
+}
+
+impl AltitudeConstraint {
+    pub fn new(altitude: Option<f64>) -> Self {
+        Self { altitude }
+    }
+
+    /// Constrains, or releases, the altitude of this solver [Config]
+    pub fn toggle(&self, cfg: &mut Config) {
+        cfg.fixed_altitude = match cfg.fixed_altitude {
+            Some(_) => {
+                info!("altitude constraint released");
+                None
+            },
+            None => {
+                match self.altitude {
+                    Some(alt) => info!("altitude constrained to {:.3}m", alt),
+                    None => warn!("no altitude to constrain to yet"),
+                }
+                self.altitude
+            },
+        };
+    }
+
+    /// New fix at `altitude` (m), retained unless the solver is constrained
+    pub fn on_fix(&mut self, cfg: &Config, altitude: f64) {
+        if cfg.fixed_altitude.is_none() {
+            self.altitude = Some(altitude);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Method;
+
+    #[test]
+    fn constrained_to_configured_altitude() {
+        let mut cfg = Config::static_preset(Method::SPP);
+        let constraint = AltitudeConstraint::new(Some(102.5));
+
+        constraint.toggle(&mut cfg);
+        assert_eq!(cfg.fixed_altitude, Some(102.5));
+        constraint.toggle(&mut cfg);
+        assert_eq!(cfg.fixed_altitude, None);
+    }
+
+    #[test]
+    fn constrained_to_latest_free_altitude() {
+        let mut cfg = Config::static_preset(Method::SPP);
+        let mut constraint = AltitudeConstraint::default();
+
+        // nothing to constrain to yet
+        constraint.toggle(&mut cfg);
+        assert_eq!(cfg.fixed_altitude, None);
+
+        constraint.on_fix(&cfg, 48.0);
+        constraint.on_fix(&cfg, 51.2);
+        constraint.toggle(&mut cfg);
+        assert_eq!(cfg.fixed_altitude, Some(51.2));
+
+        // constrained fixes do not move the constraint
+        constraint.on_fix(&cfg, 51.2001);
+        constraint.toggle(&mut cfg);
+        constraint.toggle(&mut cfg);
+        assert_eq!(cfg.fixed_altitude, Some(51.2));
+    }
+}
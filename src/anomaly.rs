@@ -0,0 +1,169 @@
+//! Spoofing / jamming / multipath indicators
+use std::collections::{HashMap, HashSet};
+
+use gnss_rtk::prelude::{Epoch, SV};
+
+/// Detection thresholds
+#[derive(Debug, Clone, Copy)]
+pub struct AnomalyOpts {
+    /// C/N0 variation (dB-Hz) between two epochs, considered abrupt
+    pub cno_jump: f64,
+    /// MON-HW CW jamming indicator (0-255) considered significant
+    pub jam_ind: u8,
+}
+
+/// Risk indicator, aggregating all signals
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Risk {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+/// MON-HW jamming state, as reported by the receiver
+const JAMMING_WARNING: u8 = 2;
+const JAMMING_CRITICAL: u8 = 3;
+
+/// C/N0 jumps on this fraction of tracked SV, within one epoch, is suspicious:
+/// multipath affects a few SV, while a spoofer replaces all signals at once.
+const SIMULTANEOUS_JUMPS_RATIO: f64 = 0.5;
+
+/// Aggregates receiver and measurement hints into a [Risk] indicator
+#[derive(Debug)]
+pub struct AnomalyDetector {
+    opts: AnomalyOpts,
+    /// Previous C/N0, per SV
+    cno: HashMap<SV, f64>,
+    /// SV whose C/N0 jumped during current epoch
+    jumps: Vec<SV>,
+    /// SV tracked during current epoch
+    tracked: HashSet<SV>,
+    /// Latest jamming state and indicator
+    jamming: (u8, u8),
+    /// Latest [Risk]
+    risk: Risk,
+}
+
+impl AnomalyDetector {
+    pub fn new(opts: AnomalyOpts) -> Self {
+        Self {
+            opts,
+            cno: HashMap::new(),
+            jumps: Vec::new(),
+            tracked: HashSet::new(),
+            jamming: (0, 0),
+            risk: Risk::default(),
+        }
+    }
+
+    /// New C/N0 measurement (dB-Hz). Only the first signal of each SV is considered.
+    pub fn on_cno(&mut self, sv: SV, cno: f64) {
+        if !self.tracked.insert(sv) {
+            return;
+        }
+        if let Some(prev) = self.cno.insert(sv, cno) {
+            if (cno - prev).abs() > self.opts.cno_jump {
+                debug!("{} C/N0 jump: {:.1} -> {:.1} dB-Hz", sv, prev, cno);
+                self.jumps.push(sv);
+            }
+        }
+    }
+
+    /// New MON-HW jamming state and CW jamming indicator
+    pub fn on_jamming(&mut self, state: u8, jam_ind: u8) {
+        self.jamming = (state, jam_ind);
+    }
+
+    /// Evaluates the [Risk] at the end of the epoch, logging any change
+    pub fn on_epoch(&mut self, t: Epoch) {
+        let (state, jam_ind) = self.jamming;
+
+        let mut risk = Risk::Low;
+        if state == JAMMING_WARNING || jam_ind > self.opts.jam_ind || !self.jumps.is_empty() {
+            risk = Risk::Medium;
+        }
+        let ratio = if !self.tracked.is_empty() {
+            self.jumps.len() as f64 / self.tracked.len() as f64
+        } else {
+            0.0
+        };
+        if state == JAMMING_CRITICAL || ratio >= SIMULTANEOUS_JUMPS_RATIO {
+            risk = Risk::High;
+        }
+
+        if risk != self.risk {
+            let details = format!(
+                "C/N0 jumps: {}/{}, jamming state: {}, indicator: {}",
+                self.jumps.len(),
+                self.tracked.len(),
+                state,
+                jam_ind
+            );
+            match risk {
+                Risk::Low => info!("{} anomaly risk cleared ({})", t, details),
+                Risk::Medium | Risk::High => warn!("{} anomaly risk {:?} ({})", t, risk, details),
+            }
+            self.risk = risk;
+        }
+
+        self.jumps.clear();
+        self.tracked.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Constellation, Duration};
+
+    fn detector() -> AnomalyDetector {
+        AnomalyDetector::new(AnomalyOpts {
+            cno_jump: 6.0,
+            jam_ind: 100,
+        })
+    }
+
+    /// Runs one epoch of 8 SV: the `spiked` first ones gain 10 dB-Hz
+    fn epoch(detector: &mut AnomalyDetector, t: Epoch, spiked: u8) -> Risk {
+        for prn in 1..=8 {
+            let cno = if prn <= spiked { 52.0 } else { 42.0 };
+            detector.on_cno(SV::new(Constellation::GPS, prn), cno);
+        }
+        detector.on_epoch(t);
+        detector.risk
+    }
+
+    #[test]
+    fn cno_spikes_raise_risk() {
+        let t0 = Epoch::from_gpst_seconds(1_000_000.0);
+        let t = |i: u32| t0 + Duration::from_seconds(i as f64);
+        let mut detector = detector();
+
+        assert_eq!(epoch(&mut detector, t(0), 0), Risk::Low);
+        assert_eq!(epoch(&mut detector, t(1), 0), Risk::Low);
+        // multipath like: one SV
+        assert_eq!(epoch(&mut detector, t(2), 1), Risk::Medium);
+        // the spiked SV drops back
+        assert_eq!(epoch(&mut detector, t(3), 0), Risk::Medium);
+        assert_eq!(epoch(&mut detector, t(4), 0), Risk::Low);
+        // spoofer like: all signals at once
+        assert_eq!(epoch(&mut detector, t(5), 4), Risk::High);
+        assert_eq!(epoch(&mut detector, t(6), 4), Risk::Low);
+    }
+
+    #[test]
+    fn jamming_raises_risk() {
+        let t = Epoch::from_gpst_seconds(1_000_000.0);
+        let mut detector = detector();
+
+        detector.on_jamming(JAMMING_WARNING, 10);
+        assert_eq!(epoch(&mut detector, t, 0), Risk::Medium);
+        detector.on_jamming(1, 150);
+        assert_eq!(epoch(&mut detector, t, 0), Risk::Medium);
+        detector.on_jamming(JAMMING_CRITICAL, 10);
+        assert_eq!(epoch(&mut detector, t, 0), Risk::High);
+        detector.on_jamming(1, 10);
+        assert_eq!(epoch(&mut detector, t, 0), Risk::Low);
+    }
+}
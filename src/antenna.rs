@@ -0,0 +1,78 @@
+//! Active antenna power control (CFG-ANT) and status (MON-HW)
+use std::str::FromStr;
+
+use ublox::{AntFlags, AntennaPower as UbxAntennaPower, AntennaStatus as UbxAntennaStatus};
+
+use crate::Error;
+
+/// Active antenna supply
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntennaPower {
+    /// Supply voltage control, with short and open circuit detection
+    On,
+    /// Supply voltage control and detections released
+    Off,
+}
+
+impl FromStr for AntennaPower {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "on" => Ok(Self::On),
+            "off" => Ok(Self::Off),
+            _ => Err(Error::InvalidAntennaPower(s.to_string())),
+        }
+    }
+}
+
+impl AntennaPower {
+    /// CFG-ANT flags. The supply is powered down on short circuit,
+    /// and recovers automatically once the short circuit is removed.
+    pub fn flags(&self) -> AntFlags {
+        match self {
+            Self::On => {
+                AntFlags::SVCS
+                    | AntFlags::SCD
+                    | AntFlags::OCD
+                    | AntFlags::PDWN_ON_SCD
+                    | AntFlags::RECOVERY
+            },
+            Self::Off => AntFlags::empty(),
+        }
+    }
+}
+
+/// Antenna indicator, from MON-HW antenna status and power
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AntennaState {
+    /// Powered and connected
+    Ok,
+    /// Supply is off
+    Off,
+    /// Short circuit detected
+    Short,
+    /// Open circuit: antenna disconnected
+    Open,
+    /// Detection is disabled or still initializing
+    #[default]
+    Unknown,
+}
+
+impl AntennaState {
+    /// Indicator state from MON-HW `status` and `power`.
+    /// A detected fault takes precedence over the supply state.
+    pub fn new(status: UbxAntennaStatus, power: UbxAntennaPower) -> Self {
+        match (status, power) {
+            (UbxAntennaStatus::Short, _) => Self::Short,
+            (UbxAntennaStatus::Open, _) => Self::Open,
+            (_, UbxAntennaPower::Off) => Self::Off,
+            (UbxAntennaStatus::Ok, _) => Self::Ok,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns true when the antenna needs attention
+    pub fn is_fault(&self) -> bool {
+        matches!(self, Self::Short | Self::Open)
+    }
+}
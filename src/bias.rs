@@ -0,0 +1,118 @@
+//! Receiver code biases
+use std::{collections::HashMap, str::FromStr};
+
+use gnss_rtk::prelude::Carrier;
+
+use crate::{carrier::SPEED_OF_LIGHT_M_S, Error};
+
+/// Parses [Carrier] from its usual name
+fn carrier_from_str(s: &str) -> Result<Carrier, Error> {
+    match s.trim().to_uppercase().as_str() {
+        "L1" => Ok(Carrier::L1),
+        "L2" => Ok(Carrier::L2),
+        "L5" => Ok(Carrier::L5),
+        "L6" => Ok(Carrier::L6),
+        "E1" => Ok(Carrier::E1),
+        "E5" => Ok(Carrier::E5),
+        "E5A" => Ok(Carrier::E5A),
+        "E5B" => Ok(Carrier::E5B),
+        "E6" => Ok(Carrier::E6),
+        "B1I" => Ok(Carrier::B1I),
+        "B1C" | "B1A/B1C" => Ok(Carrier::B1aB1c),
+        "B2" => Ok(Carrier::B2),
+        "B2A" => Ok(Carrier::B2A),
+        "B2I" | "B2I/B2B" => Ok(Carrier::B2iB2b),
+        "B3" => Ok(Carrier::B3),
+        _ => Err(Error::InvalidBias(s.to_string())),
+    }
+}
+
+/// Receiver code bias of one signal, as described by user: "CARRIER:NS"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodeBias {
+    /// [Carrier] signal
+    pub carrier: Carrier,
+    /// Delay in nanoseconds
+    pub delay_ns: f64,
+}
+
+impl FromStr for CodeBias {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (carrier, delay) = s
+            .split_once(':')
+            .ok_or_else(|| Error::InvalidBias(s.to_string()))?;
+        Ok(Self {
+            carrier: carrier_from_str(carrier)?,
+            delay_ns: delay
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| Error::InvalidBias(s.to_string()))?,
+        })
+    }
+}
+
+/// Receiver code biases (DCB), consulted when building candidates.
+/// A positive bias is a delay, that lengthens the measured pseudo range.
+#[derive(Debug, Clone, Default)]
+pub struct BiasTable {
+    /// Delay in seconds, per signal
+    biases: HashMap<Carrier, f64>,
+}
+
+impl BiasTable {
+    /// Builds [BiasTable] from user specifications
+    pub fn new(biases: &[CodeBias]) -> Self {
+        Self {
+            biases: biases
+                .iter()
+                .map(|bias| (bias.carrier, bias.delay_ns * 1.0E-9))
+                .collect(),
+        }
+    }
+
+    /// Returns pseudo range (m) corrected for receiver code bias on this [Carrier]
+    pub fn correct_pseudo_range(&self, carrier: Carrier, pr: f64) -> f64 {
+        match self.biases.get(&carrier) {
+            Some(bias) => pr - bias * SPEED_OF_LIGHT_M_S,
+            None => pr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            CodeBias::from_str("e5b:-2.5").unwrap(),
+            CodeBias {
+                carrier: Carrier::E5B,
+                delay_ns: -2.5,
+            }
+        );
+        assert_eq!(
+            CodeBias::from_str(" B1A/B1C : 1").unwrap().carrier,
+            Carrier::B1aB1c
+        );
+        for invalid in ["L2", "L7:1.0", "L2:1ns", ""] {
+            assert!(CodeBias::from_str(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn known_bias_shifts_pseudo_range() {
+        let table = BiasTable::new(&[
+            CodeBias::from_str("L2:10").unwrap(),
+            CodeBias::from_str("L5:-1").unwrap(),
+        ]);
+        let pr = 22_000_000.0;
+        // 10 ns delay: 2.998 m longer than the geometric range
+        assert!((table.correct_pseudo_range(Carrier::L2, pr) - (pr - 2.99792458)).abs() < 1.0E-6);
+        assert!((table.correct_pseudo_range(Carrier::L5, pr) - (pr + 0.299792458)).abs() < 1.0E-6);
+        // no bias on L1
+        assert_eq!(table.correct_pseudo_range(Carrier::L1, pr), pr);
+    }
+}
@@ -0,0 +1,92 @@
+//! Carrier frequencies and wavelengths
+use gnss_rtk::prelude::Carrier;
+
+/// Speed of light in vacuum (m/s)
+pub const SPEED_OF_LIGHT_M_S: f64 = 299_792_458.0;
+
+/// Returns the frequency (Hz) of this [Carrier].
+/// Every frequency dependent computation (ionosphere delays, doppler and phase scaling,
+/// dual frequency combinations) goes through the actual signal carrier,
+/// so a signal tracked on L5 is never scaled as L1 or L2.
+pub fn carrier_frequency_hz(carrier: Carrier) -> f64 {
+    carrier.frequency()
+}
+
+/// Returns the wavelength (m) of this [Carrier]
+pub fn wavelength(carrier: Carrier) -> f64 {
+    carrier.wavelength()
+}
+
+/// Converts a carrier phase from cycles to meters
+pub fn cycles_to_meters(cycles: f64, carrier: Carrier) -> f64 {
+    cycles * wavelength(carrier)
+}
+
+/// Ionosphere free combination coefficients (a, b) of two carriers:
+/// a * obs(c1) + b * obs(c2) cancels the first order ionosphere delay.
+/// Returns None when both carriers share the same frequency.
+pub fn iono_free_coefficients(c1: Carrier, c2: Carrier) -> Option<(f64, f64)> {
+    let (f1, f2) = (carrier_frequency_hz(c1), carrier_frequency_hz(c2));
+    let den = f1.powi(2) - f2.powi(2);
+    if den == 0.0 {
+        return None;
+    }
+    Some((f1.powi(2) / den, -f2.powi(2) / den))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carrier_frequencies() {
+        for (carrier, mhz) in [
+            (Carrier::L1, 1575.42),
+            (Carrier::E1, 1575.42),
+            (Carrier::B1aB1c, 1575.42),
+            (Carrier::L2, 1227.60),
+            (Carrier::L5, 1176.45),
+            (Carrier::E5A, 1176.45),
+            (Carrier::B2A, 1176.45),
+            (Carrier::E5B, 1207.14),
+            (Carrier::B2iB2b, 1207.14),
+            (Carrier::E5, 1191.795),
+            (Carrier::L6, 1278.75),
+            (Carrier::E6, 1278.75),
+            (Carrier::B1I, 1561.098),
+            (Carrier::B3, 1268.52),
+        ] {
+            let hz = carrier_frequency_hz(carrier);
+            assert!((hz - mhz * 1.0E6).abs() < 1.0E-3, "{:?}: {}", carrier, hz);
+        }
+        assert!((wavelength(Carrier::L1) - 0.190293672798).abs() < 1.0E-12);
+    }
+
+    #[test]
+    fn iono_free_combinations() {
+        for (c1, c2, a, b) in [
+            (Carrier::L1, Carrier::L2, 2.545727780163, -1.545727780163),
+            (Carrier::L1, Carrier::L5, 2.260604327519, -1.260604327519),
+            (Carrier::E1, Carrier::E5A, 2.260604327519, -1.260604327519),
+        ] {
+            let (coef_a, coef_b) = iono_free_coefficients(c1, c2).unwrap();
+            assert!(
+                (coef_a - a).abs() < 1.0E-12,
+                "{:?}/{:?}: {}",
+                c1,
+                c2,
+                coef_a
+            );
+            assert!(
+                (coef_b - b).abs() < 1.0E-12,
+                "{:?}/{:?}: {}",
+                c1,
+                c2,
+                coef_b
+            );
+            // the combination preserves geometry
+            assert!((coef_a + coef_b - 1.0).abs() < 1.0E-12);
+        }
+        assert!(iono_free_coefficients(Carrier::L5, Carrier::E5A).is_none());
+    }
+}
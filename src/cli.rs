@@ -1,10 +1,31 @@
 use std::{
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration as StdDuration,
+};
+
+#[cfg(feature = "mqtt")]
+use crate::mqtt::MqttOpts;
+use crate::{
+    anomaly::AnomalyOpts,
+    antenna::AntennaPower,
+    bias::{BiasTable, CodeBias},
+    config::{SolverConfig, UserProfile},
+    datum::{Datum, Helmert},
+    output::{Destination, Format, SinkOpts},
+    pvt::EnuOrigin,
+    rtcm::RtcmOpts,
+    selection::Selection,
+    survey::SurveyOpts,
+    time::TimestampFormat,
+    timing::KnownPosition,
+    tropo::TropoModel,
+    ublox::{DynModel, ExtendedSignal, MessagePorts, Opts as UbloxOpts, SerialOpts, StartType},
+    Error,
 };
 
-use crate::ublox::SerialOpts;
 use clap::{value_parser, Arg, ArgAction, ArgMatches, ColorChoice, Command};
+use gnss_rtk::prelude::{Constellation, Duration, TimeScale, SV};
 
 pub struct Cli {
     /// Arguments passed by user
@@ -22,7 +43,7 @@ impl Cli {
     pub fn new() -> Self {
         Self {
             matches: {
-                Command::new("rt-navi")
+                let cmd = Command::new("rt-navi")
                     .author("Guillaume W. Bres, <guillaume.bressaix@gmail.com>")
                     .version(env!("CARGO_PKG_VERSION"))
                     .about("High precision Navigation, in real time")
@@ -33,11 +54,562 @@ impl Cli {
                         Arg::new("ublox")
                             .short('u')
                             .long("ublox")
-                            .required(true)
+                            .action(ArgAction::Append)
+                            .required_unless_present("replay")
                             .value_name("PORT")
-                            .help("Specify serial port to Ublox device"),
+                            .help("Specify serial port to Ublox device. Can be repeated: the first device navigates,
+the fixes of the others (NAV-PVT) are displayed next to the solution, for redundancy checks."),
+                    )
+                    .arg(
+                        Arg::new("replay")
+                            .long("replay")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .conflicts_with("ublox")
+                            .help("Replay captured UBX stream (offline), instead of a real device.
+The capture should contain RXM-RAWX and NAV-EOE messages."),
+                    )
+                    .arg(
+                        Arg::new("record")
+                            .long("record")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .conflicts_with("replay")
+                            .help("Record received UBX frames (.ubx), to be replayed later or analyzed with u-center.
+Only complete and valid frames are recorded."),
+                    )
+                    .arg(
+                        Arg::new("sbas")
+                            .long("sbas")
+                            .action(ArgAction::SetTrue)
+                            .help("Apply SBAS (WAAS, EGNOS..) fast corrections to GPS L1 C/A pseudo ranges"),
+                    )
+                    .arg(
+                        Arg::new("signal")
+                            .long("signal")
+                            .action(ArgAction::Append)
+                            .value_parser(value_parser!(ExtendedSignal))
+                            .value_name("SIGNAL")
+                            .help("Enable extended signal, for receivers that support it. Can be repeated.
+SIGNAL is either \"L5\", \"E5a\", \"E6\", \"B1C\" or \"B2a\".
+Signals the receiver does not support are skipped."),
+                    )
+                    .arg(
+                        Arg::new("warmup-ms")
+                            .long("warmup-ms")
+                            .value_name("MS")
+                            .value_parser(value_parser!(u64))
+                            .help("Wait MS milliseconds after opening the port, and discard whatever the receiver sent
+meanwhile, before configuring it. For receivers that are slow to accept configuration
+after power up."),
+                    )
+                    .arg(
+                        Arg::new("ack-timeout")
+                            .long("ack-timeout")
+                            .value_name("DURATION")
+                            .value_parser(value_parser!(Duration))
+                            .default_value("3 s")
+                            .help("Receiver configuration: ACK timeout, per attempt"),
+                    )
+                    .arg(
+                        Arg::new("ack-retries")
+                            .long("ack-retries")
+                            .value_name("N")
+                            .value_parser(value_parser!(u8))
+                            .default_value("3")
+                            .help("Receiver configuration: number of retries when ACK times out"),
+                    )
+                    .arg(
+                        Arg::new("verify-config")
+                            .long("verify-config")
+                            .action(ArgAction::SetTrue)
+                            .help("Read back the signal configuration, once applied, and report any discrepancy"),
+                    )
+                    .arg(
+                        Arg::new("persist")
+                            .long("persist")
+                            .action(ArgAction::SetTrue)
+                            .help("Also store the signal configuration in battery backed RAM and flash,
+so it survives receiver restarts and power cycles"),
+                    )
+                    .arg(
+                        Arg::new("max-pr-stdev")
+                            .long("max-pr-stdev")
+                            .value_name("METERS")
+                            .value_parser(value_parser!(f64))
+                            .help("Reject measurements whose pseudo range standard deviation (RAWX prStdev) exceeds this value"),
+                    )
+                    .arg(
+                        Arg::new("max-cp-stdev")
+                            .long("max-cp-stdev")
+                            .value_name("CYCLES")
+                            .value_parser(value_parser!(f64))
+                            .help("Drop carrier phases whose standard deviation (RAWX cpStdev) exceeds this value"),
+                    )
+                    .arg(
+                        Arg::new("min-pr")
+                            .long("min-pr")
+                            .value_name("KM")
+                            .value_parser(value_parser!(f64))
+                            .default_value("18000")
+                            .help("Reject pseudo ranges shorter than this value, which indicate a tracking or decoding error"),
+                    )
+                    .arg(
+                        Arg::new("max-pr")
+                            .long("max-pr")
+                            .value_name("KM")
+                            .value_parser(value_parser!(f64))
+                            .default_value("42000")
+                            .help("Reject pseudo ranges longer than this value, which indicate a tracking or decoding error.
+The default preserves geostationary and inclined orbits (QZSS)."),
+                    )
+                    .arg(
+                        Arg::new("msg-ports")
+                            .long("msg-ports")
+                            .value_name("PORTS")
+                            .value_parser(value_parser!(MessagePorts))
+                            .help("Receiver ports that messages are enabled on, comma separated, among
+\"i2c\", \"uart1\", \"uart2\", \"usb\" and \"spi\". Default: \"uart1,uart2,usb\".
+Example: --msg-ports usb, to spare the UART bandwidth."),
+                    )
+                    .arg(
+                        Arg::new("dyn-model")
+                            .long("dyn-model")
+                            .value_name("MODEL")
+                            .value_parser(value_parser!(DynModel))
+                            .help("Receiver dynamic platform model (CFG-NAV5): \"portable\", \"stationary\", \"pedestrian\",
+\"automotive\", \"sea\", \"airborne1g\", \"airborne2g\", \"airborne4g\", \"wrist\" or \"bike\".
+Only affects the receiver navigation filter, not the solver profile."),
+                    )
+                    .arg(
+                        Arg::new("ant-power")
+                            .long("ant-power")
+                            .value_name("on|off")
+                            .value_parser(value_parser!(AntennaPower))
+                            .help("Active antenna supply (CFG-ANT): \"on\" controls the supply voltage and detects
+short and open circuits, \"off\" releases the control. The antenna status (MON-HW)
+is then reported on every change, and in the status snapshot."),
+                    )
+                    .arg(
+                        Arg::new("anomalies")
+                            .long("anomalies")
+                            .action(ArgAction::SetTrue)
+                            .help("Detect spoofing / jamming / multipath indicators (abrupt C/N0 variations, MON-HW jamming),
+and report the aggregated risk."),
+                    )
+                    .arg(
+                        Arg::new("cno-jump")
+                            .long("cno-jump")
+                            .value_name("DBHZ")
+                            .value_parser(value_parser!(f64))
+                            .default_value("10")
+                            .help("C/N0 variation between two epochs considered abrupt (--anomalies)"),
+                    )
+                    .arg(
+                        Arg::new("jam-indicator")
+                            .long("jam-indicator")
+                            .value_name("N")
+                            .value_parser(value_parser!(u8))
+                            .default_value("100")
+                            .help("MON-HW CW jamming indicator (0-255) considered significant (--anomalies)"),
+                    )
+                    .arg(
+                        Arg::new("heading")
+                            .long("heading")
+                            .action(ArgAction::SetTrue)
+                            .help("Report moving baseline heading (dual antenna setup, NAV-RELPOSNED)"),
+                    )
+                    .arg(
+                        Arg::new("use-phase")
+                            .long("use-phase")
+                            .action(ArgAction::SetTrue)
+                            .help("Propose carrier phases to the solver, converted from cycles to meters.
+Phases are withheld while invalid, half cycle ambiguous, too noisy (--max-cp-stdev)
+or locked for less than a second. Required by PPP."),
                     )
-                    .get_matches()
+                    .arg(
+                        Arg::new("max-gap")
+                            .long("max-gap")
+                            .value_name("DURATION")
+                            .value_parser(value_parser!(Duration))
+                            .default_value("5 s")
+                            .help("Signals missing for longer than this are given a new phase ambiguity when reacquired,
+even if the receiver did not report a loss of lock. Their phases are withheld for a second,
+so phase smoothing does not run across the reacquisition."),
+                    )
+                    .arg(
+                        Arg::new("combinations")
+                            .long("combinations")
+                            .action(ArgAction::SetTrue)
+                            .help("Report widelane, narrowlane, Melbourne-Wübbena, ionosphere free and multipath (MP1/MP2)
+combinations of SV tracked on two frequencies (see --signal), for ambiguity resolution experiments
+and to identify SV affected by multipath."),
+                    )
+                    .arg(
+                        Arg::new("esf")
+                            .long("esf")
+                            .action(ArgAction::SetTrue)
+                            .help("Bridge GNSS outages by dead reckoning, for receivers with sensor fusion (ESF-INS).
+The last fix is extrapolated from the vehicle yaw rate and forward acceleration, for up to 30s."),
+                    )
+                    .arg(
+                        Arg::new("start")
+                            .long("start")
+                            .value_name("TYPE")
+                            .value_parser(value_parser!(StartType))
+                            .help("Restart the receiver before processing: \"cold\" (full acquisition),
+\"warm\" (clears ephemeris) or \"hot\" (fast restart)."),
+                    )
+                    .arg(
+                        Arg::new("timepulse")
+                            .long("timepulse")
+                            .value_name("HZ")
+                            .value_parser(value_parser!(u32).range(1..))
+                            .help("Configure TIMEPULSE output at HZ (50% duty cycle, aligned to top of second)
+and report the time pulse quantization error."),
+                    )
+                    .arg(
+                        Arg::new("cable-delay-ns")
+                            .long("cable-delay-ns")
+                            .value_name("NS")
+                            .value_parser(value_parser!(f64))
+                            .help("Antenna cable delay, in nanoseconds. It is removed from the reported clock offset
+(a positive delay makes the receiver clock appear late), and compensated by the receiver time pulse."),
+                    )
+                    .next_help_heading("Solver")
+                    .arg(
+                        Arg::new("sp3")
+                            .long("sp3")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .help("Precise orbits (SP3 file), interpolated instead of broadcast orbits."),
+                    )
+                    .arg(
+                        Arg::new("clk")
+                            .long("clk")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .help("Precise clocks (RINEX clock file), interpolated instead of broadcast clock corrections."),
+                    )
+                    .arg(
+                        Arg::new("strict-clocks")
+                            .long("strict-clocks")
+                            .action(ArgAction::SetTrue)
+                            .requires("clk")
+                            .help("Withhold SV that the precise clocks do not cover at that epoch,
+instead of proposing them without clock correction."),
+                    )
+                    .arg(
+                        Arg::new("ionex")
+                            .long("ionex")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .help("Ionosphere maps (IONEX file). The slant ionosphere delay is removed from pseudo ranges,
+from the vertical TEC at the pierce point of each SV that contributed to the latest fix.
+Out of the maps' coverage, the broadcast GPS (Klobuchar) model applies once received.
+SV that did not contribute to the latest fix are not corrected."),
+                    )
+                    .arg(
+                        Arg::new("clock-filter")
+                            .long("clock-filter")
+                            .action(ArgAction::SetTrue)
+                            .help("Filter the reported clock offset with a clock model (random walk offset, white noise drift),
+which also estimates the clock drift."),
+                    )
+                    .arg(
+                        Arg::new("config")
+                            .short('c')
+                            .long("config")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .help("Solver configuration file (JSON). Unspecified parameters
+preserve the static SPP preset. Example: {\"method\": \"CPP\", \"min_sv_elev\": 15.0}"),
+                    )
+                    .arg(
+                        Arg::new("profile")
+                            .long("profile")
+                            .value_name("PROFILE")
+                            .value_parser(value_parser!(UserProfile))
+                            .help("User dynamics: \"static\", \"walking\", \"cycling\", \"driving\" or \"flying\".
+Overrides the configuration file. Static by default."),
+                    )
+                    .arg(
+                        Arg::new("fix-altitude")
+                            .long("fix-altitude")
+                            .value_name("METERS")
+                            .value_parser(value_parser!(f64))
+                            .help("Constrain altitude (2D navigation), which stabilizes the horizontal fix under poor geometry.
+Overrides the configuration file. The constraint may also be toggled at runtime, holding the latest altitude."),
+                    )
+                    .arg(
+                        Arg::new("tropo")
+                            .long("tropo")
+                            .value_name("MODEL")
+                            .value_parser(value_parser!(TropoModel))
+                            .help("Troposphere model: \"niell\" (default) or \"unb3m\".
+UNB3m zenith delays are derived from the latest fix latitude, altitude and day of year."),
+                    )
+                    .arg(
+                        Arg::new("min-sv")
+                            .long("min-sv")
+                            .value_name("N")
+                            .value_parser(value_parser!(usize).range(1..))
+                            .help("Do not attempt a fix with less than N satellites (after selection).
+The solver always requires one satellite per unknown: 4, 3 with fixed altitude, 1 with --known-position.
+Raising the threshold trades availability for more robust fixes."),
+                    )
+                    .arg(
+                        Arg::new("max-sv")
+                            .long("max-sv")
+                            .value_name("N")
+                            .value_parser(value_parser!(usize).range(4..))
+                            .help("Propose the measurements of at most N satellites to the solver, when more are available.
+Preferred constellations (--gnss-priority) are kept first, then highest elevation first."),
+                    )
+                    .arg(
+                        Arg::new("gnss-priority")
+                            .long("gnss-priority")
+                            .value_name("GNSS")
+                            .value_delimiter(',')
+                            .action(ArgAction::Append)
+                            .value_parser(value_parser!(Constellation))
+                            .requires("max-sv")
+                            .help("Constellations to keep first when selecting satellites (--max-sv),
+most preferred first. Example: --gnss-priority GPS,Galileo"),
+                    )
+                    .arg(
+                        Arg::new("exclude-sv")
+                            .long("exclude-sv")
+                            .value_name("SV")
+                            .value_delimiter(',')
+                            .action(ArgAction::Append)
+                            .value_parser(value_parser!(SV))
+                            .help("Satellites never proposed to the solver, for example a known bad one.
+Example: --exclude-sv G07,E11. Satellites can also be toggled while running."),
+                    )
+                    .arg(
+                        Arg::new("include-sv")
+                            .long("include-sv")
+                            .value_name("SV")
+                            .value_delimiter(',')
+                            .action(ArgAction::Append)
+                            .value_parser(value_parser!(SV))
+                            .help("Only propose these satellites to the solver (whitelist).
+Example: --include-sv G01,G03,G07,G08"),
+                    )
+                    .next_help_heading("Receiver biases")
+                    .arg(
+                        Arg::new("dcb")
+                            .long("dcb")
+                            .action(ArgAction::Append)
+                            .value_parser(value_parser!(CodeBias))
+                            .value_name("CARRIER:NS")
+                            .help("Receiver code bias on given signal, in nanoseconds. Can be repeated.
+A positive bias is a delay: it is removed from the measured pseudo range.
+Example: --dcb L2:1.5"),
+                    )
+                    .next_help_heading("RTCM corrections")
+                    .arg(
+                        Arg::new("rtcm")
+                            .long("rtcm")
+                            .value_name("ADDR:PORT")
+                            .help("Connect to RTCM3 corrections stream (TCP)"),
+                    )
+                    .arg(
+                        Arg::new("rtcm-forward")
+                            .long("rtcm-forward")
+                            .action(ArgAction::SetTrue)
+                            .requires("rtcm")
+                            .help("Forward valid RTCM frames to the GNSS receiver, as is"),
+                    )
+                    .arg(
+                        Arg::new("rtcm-log")
+                            .long("rtcm-log")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .requires("rtcm")
+                            .help("Log valid RTCM frames to FILE, as is"),
+                    )
+                    .next_help_heading("Static survey")
+                    .arg(
+                        Arg::new("survey")
+                            .long("survey")
+                            .value_name("DURATION")
+                            .value_parser(value_parser!(Duration))
+                            .help("Average all solutions over DURATION (for example \"30 min\")
+and report the mean coordinates, their standard deviation and the number of solutions."),
+                    )
+                    .arg(
+                        Arg::new("survey-marker")
+                            .long("survey-marker")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .requires("survey")
+                            .help("Write the surveyed coordinates to FILE"),
+                    )
+                    .next_help_heading("Output")
+                    .arg(
+                        Arg::new("output")
+                            .short('o')
+                            .long("output")
+                            .action(ArgAction::Append)
+                            .value_parser(value_parser!(SinkOpts))
+                            .value_name("SINK")
+                            .help("Output solutions to SINK. Can be repeated to use several outputs at once.
+SINK is described as [FORMAT[@TIMESTAMP]:]DESTINATION, where DESTINATION is either \"stdout\", \"file:PATH\", \"tcp:ADDR:PORT\", \"udp:ADDR:PORT\" or \"http:ADDR:PORT\",
+and FORMAT is either \"text\" (default), \"json\", \"csv\", \"nmea\", \"bin\", \"gpx\" or \"kml\".
+JSON and CSV epochs are rendered as TIMESTAMP: \"epoch\" (default, in --timescale), \"iso\" (ISO8601 UTC),
+\"week\" (GPS week and time of week) or \"unix\" (nanoseconds since 1970, UTC). Example: csv@unix:file:fixes.csv
+\"bin\" is a compact binary frame (64 byte payload and CRC-16) for embedded consumers, see src/output/binary.rs.
+GPX and KML tracks (requires the related features) are written to file on exit.
+KML tracks may also be served live over http (kml:http:ADDR:PORT), for example to a Google Earth network link.
+Solutions are printed to stdout by default."),
+                    )
+                    .arg(
+                        Arg::new("output-rate")
+                            .long("output-rate")
+                            .value_name("HZ")
+                            .value_parser(value_parser!(f64))
+                            .help("Maximal rate of solutions handed to the outputs (including survey and SHM),
+independently of the navigation rate. Example: --output-rate 1 outputs one solution per second
+while navigating at 10 Hz."),
+                    )
+                    .arg(
+                        Arg::new("timescale")
+                            .long("timescale")
+                            .value_name("TIMESCALE")
+                            .value_parser(["gpst", "utc", "gst"])
+                            .default_value("gpst")
+                            .help("Timescale solution epochs are expressed in, in text, JSON and CSV outputs.
+NMEA sentences are always expressed in UTC."),
+                    )
+                    .arg(
+                        Arg::new("shm")
+                            .long("shm")
+                            .value_name("UNIT")
+                            .value_parser(value_parser!(u8))
+                            .help("Publish GNSS time on this NTP shared memory unit (chrony / ntpd SHM refclock),
+so the system clock may be disciplined. Example (chrony.conf): \"refclock SHM 0 offset 0.0\"."),
+                    )
+                    .arg(
+                        Arg::new("status-file")
+                            .long("status-file")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .default_value("rt-navi-status.json")
+                            .help("JSON status snapshot (current fix, tracked satellites, active configuration),
+written on key press."),
+                    )
+                    .arg(
+                        Arg::new("history")
+                            .long("history")
+                            .value_name("K")
+                            .value_parser(value_parser!(usize))
+                            .default_value("10")
+                            .help("Number of recent epochs (candidates and solution) kept for look-back analysis,
+browsed at runtime. 0 disables the history."),
+                    )
+                    .arg(
+                        Arg::new("sky-log")
+                            .long("sky-log")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .help("Log azimuth, elevation, C/N0 and usage of each tracked SV, at every epoch, to this CSV file.
+For obstruction and multipath maps of the site."),
+                    )
+                    .arg(
+                        Arg::new("summary")
+                            .long("summary")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .help("Also write the session summary, printed on exit, to this file: duration, epochs,
+fixes and fix rate, TTFF, mean GDOP, constellations, position spread (and mean position
+when static) and parser losses."),
+                    )
+                    .arg(
+                        Arg::new("known-position")
+                            .long("known-position")
+                            .value_name("LAT,LON,ALT")
+                            .value_parser(value_parser!(KnownPosition))
+                            .help("Time only navigation, for timing receivers at a surveyed position
+(ddeg, ddeg, meters above ellipsoid): the position is held and only the clock is resolved,
+which a single satellite is enough for."),
+                    )
+                    .arg(
+                        Arg::new("datum")
+                            .long("datum")
+                            .value_name("DATUM")
+                            .value_parser(value_parser!(Datum))
+                            .conflicts_with("datum-file")
+                            .help("Express solutions in a regional datum, at the epoch of the solution:
+\"etrs89\" (ETRF2000), \"nad83\" (NAD83(2011)) or \"gda2020\".
+Solutions are considered aligned to ITRF, which WGS84 is at the centimeter level."),
+                    )
+                    .arg(
+                        Arg::new("datum-file")
+                            .long("datum-file")
+                            .value_name("FILE")
+                            .value_parser(value_parser!(PathBuf))
+                            .help("Express solutions in a custom datum: 14 parameter Helmert transformation from ITRF (JSON),
+in the IERS (position vector) convention. Example:
+{\"t\": [54.7, 52.2, -74.1], \"d\": 2.12, \"r\": [1.701, 10.29, -16.632],
+ \"t_rate\": [0.1, 0.1, -1.9], \"d_rate\": 0.11, \"r_rate\": [0.081, 0.49, -0.792], \"epoch\": 2010.0}
+Translations in mm, scale in ppb, rotations in mas, rates per year, epoch as decimal year."),
+                    )
+                    .arg(
+                        Arg::new("enu-origin")
+                            .long("enu-origin")
+                            .value_name("LAT,LON,ALT")
+                            .value_parser(value_parser!(EnuOrigin))
+                            .help("Also express solutions as local East, North and Up coordinates (m),
+with respect to this origin (ddeg, ddeg, meters above ellipsoid)."),
+                    )
+                    .arg(
+                        Arg::new("compare-receiver")
+                            .long("compare-receiver")
+                            .action(ArgAction::SetTrue)
+                            .help("Compare each epoch to the receiver's own fix (NAV-PVT): horizontal and vertical
+differences are logged and reported in the status snapshot, along with epochs where
+only one of them has a fix."),
+                    )
+                    .arg(
+                        Arg::new("utm")
+                            .long("utm")
+                            .action(ArgAction::SetTrue)
+                            .help("Also express solutions in UTM coordinates (zone, easting and northing).
+The zone is selected from the solution, unless forced with --utm-zone."),
+                    )
+                    .arg(
+                        Arg::new("utm-zone")
+                            .long("utm-zone")
+                            .value_name("ZONE")
+                            .value_parser(value_parser!(u8).range(1..=60))
+                            .help("Project UTM coordinates in this zone (1-60). Implies --utm."),
+                    );
+                // feature dependent options are not even accepted when not compiled in
+                #[cfg(feature = "mqtt")]
+                let cmd = cmd.arg(
+                    Arg::new("mqtt")
+                        .long("mqtt")
+                        .value_name("FILE")
+                        .value_parser(value_parser!(PathBuf))
+                        .help_heading("MQTT corrections")
+                        .help("Subscribe to correction data (SPARTN, RTCM..) on an MQTT broker, and forward it
+to the GNSS receiver as is. FILE (JSON) describes the broker and credentials.
+Example: {\"host\": \"pp.services.u-blox.com\", \"client_id\": \"ID\",
+\"ca\": \"ca.crt\", \"cert\": \"ID.crt\", \"key\": \"ID.pem\", \"topics\": [\"/pp/ip/eu\"]}"),
+                );
+                #[cfg(feature = "osnma")]
+                let cmd = cmd.arg(
+                    Arg::new("osnma")
+                        .long("osnma")
+                        .action(ArgAction::SetTrue)
+                        .help_heading("GNSS Receiver (Hardware)")
+                        .help("Report the Galileo OSNMA authentication status, per satellite and globally,
+from E1-B I/NAV pages (RXM-SFRBX).
+Only the broadcast status is reported: authentication codes are not verified."),
+                );
+                cmd.get_matches()
             },
         }
     }
@@ -47,4 +619,286 @@ impl Cli {
             baud: 9600,
         }
     }
+    /// Returns serial options of the secondary devices
+    pub fn secondary_serial_opts(&self) -> Vec<SerialOpts> {
+        match self.matches.get_many::<String>("ublox") {
+            Some(ports) => ports
+                .skip(1)
+                .map(|port| SerialOpts {
+                    port: port.to_string(),
+                    baud: 9600,
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+    /// Returns captured UBX stream to replay, if any
+    pub fn replay(&self) -> Option<&Path> {
+        self.matches
+            .get_one::<PathBuf>("replay")
+            .map(|path| path.as_path())
+    }
+    /// Returns UBX record file path, if any
+    pub fn record(&self) -> Option<&Path> {
+        self.matches
+            .get_one::<PathBuf>("record")
+            .map(|path| path.as_path())
+    }
+    /// Returns all requested outputs
+    pub fn outputs(&self) -> Vec<SinkOpts> {
+        match self.matches.get_many::<SinkOpts>("output") {
+            Some(outputs) => outputs.cloned().collect(),
+            None => vec![SinkOpts {
+                format: Format::default(),
+                timestamp: TimestampFormat::default(),
+                destination: Destination::Stdout,
+            }],
+        }
+    }
+    /// Returns minimal interval between output solutions, if throttled
+    pub fn output_interval(&self) -> Option<Duration> {
+        self.matches
+            .get_one::<f64>("output-rate")
+            .filter(|rate| **rate > 0.0)
+            .map(|rate| Duration::from_seconds(1.0 / rate))
+    }
+    /// Returns [TimeScale] solutions are expressed in
+    pub fn timescale(&self) -> TimeScale {
+        match self
+            .matches
+            .get_one::<String>("timescale")
+            .unwrap()
+            .as_str()
+        {
+            "utc" => TimeScale::UTC,
+            "gst" => TimeScale::GST,
+            _ => TimeScale::GPST,
+        }
+    }
+    /// Returns NTP SHM unit, if requested
+    pub fn shm(&self) -> Option<u8> {
+        self.matches.get_one::<u8>("shm").copied()
+    }
+    /// Returns status snapshot file path
+    pub fn status_file(&self) -> PathBuf {
+        self.matches
+            .get_one::<PathBuf>("status-file")
+            .unwrap()
+            .clone()
+    }
+    /// Returns number of epochs kept in history
+    pub fn history(&self) -> usize {
+        *self.matches.get_one::<usize>("history").unwrap()
+    }
+    /// Returns sky log file path, if requested
+    pub fn sky_log(&self) -> Option<&PathBuf> {
+        self.matches.get_one::<PathBuf>("sky-log")
+    }
+    /// Returns session summary file, if requested
+    pub fn summary(&self) -> Option<&PathBuf> {
+        self.matches.get_one::<PathBuf>("summary")
+    }
+    /// Returns known antenna position, for time only navigation
+    pub fn known_position(&self) -> Option<KnownPosition> {
+        self.matches
+            .get_one::<KnownPosition>("known-position")
+            .copied()
+    }
+    /// Returns regional datum transformation, either built-in or loaded from file, if any
+    pub fn datum(&self) -> Result<Option<Helmert>, Error> {
+        if let Some(path) = self.matches.get_one::<PathBuf>("datum-file") {
+            return Ok(Some(Helmert::from_file(path)?));
+        }
+        Ok(self
+            .matches
+            .get_one::<Datum>("datum")
+            .map(|datum| datum.helmert()))
+    }
+    /// Returns local ENU frame origin, if any
+    pub fn enu_origin(&self) -> Option<EnuOrigin> {
+        self.matches.get_one::<EnuOrigin>("enu-origin").copied()
+    }
+    /// Returns true if solutions should be compared to the receiver fix
+    pub fn compare_receiver(&self) -> bool {
+        self.matches.get_flag("compare-receiver")
+    }
+    /// Returns UTM projection, if requested: Some(None) selects the zone automatically
+    pub fn utm(&self) -> Option<Option<u8>> {
+        match self.matches.get_one::<u8>("utm-zone") {
+            Some(zone) => Some(Some(*zone)),
+            None if self.matches.get_flag("utm") => Some(None),
+            None => None,
+        }
+    }
+    /// Returns solver configuration, either loaded from file or the default preset
+    pub fn solver_config(&self) -> Result<SolverConfig, Error> {
+        let mut cfg = match self.matches.get_one::<PathBuf>("config") {
+            Some(path) => SolverConfig::from_file(path)?,
+            None => SolverConfig::default(),
+        };
+        if let Some(profile) = self.matches.get_one::<UserProfile>("profile") {
+            cfg.profile = Some(*profile);
+        }
+        if let Some(altitude) = self.matches.get_one::<f64>("fix-altitude") {
+            cfg.fixed_altitude = Some(*altitude);
+        }
+        Ok(cfg)
+    }
+    /// Returns minimal number of satellites, prior attempting a fix, if specified
+    pub fn min_sv(&self) -> Option<usize> {
+        self.matches.get_one::<usize>("min-sv").copied()
+    }
+    /// Returns candidate [Selection], if requested
+    pub fn selection(&self) -> Option<Selection> {
+        let max_sv = *self.matches.get_one::<usize>("max-sv")?;
+        Some(Selection {
+            max_sv,
+            priority: match self.matches.get_many::<Constellation>("gnss-priority") {
+                Some(priority) => priority.copied().collect(),
+                None => Vec::new(),
+            },
+        })
+    }
+    /// Returns satellites excluded from navigation
+    pub fn excluded_sv(&self) -> Vec<SV> {
+        match self.matches.get_many::<SV>("exclude-sv") {
+            Some(excluded) => excluded.copied().collect(),
+            None => Vec::new(),
+        }
+    }
+    /// Returns the only satellites allowed to navigate, if restricted
+    pub fn included_sv(&self) -> Option<Vec<SV>> {
+        self.matches
+            .get_many::<SV>("include-sv")
+            .map(|included| included.copied().collect())
+    }
+    /// Returns RTCM relay options, if requested
+    pub fn rtcm_opts(&self) -> Option<RtcmOpts> {
+        let addr = self.matches.get_one::<String>("rtcm")?;
+        Some(RtcmOpts {
+            addr: addr.to_string(),
+            forward: self.matches.get_flag("rtcm-forward"),
+            log: self.matches.get_one::<PathBuf>("rtcm-log").cloned(),
+        })
+    }
+    /// Returns MQTT corrections options, if requested
+    #[cfg(feature = "mqtt")]
+    pub fn mqtt_opts(&self) -> Result<Option<MqttOpts>, Error> {
+        match self.matches.get_one::<PathBuf>("mqtt") {
+            Some(path) => Ok(Some(MqttOpts::from_file(path)?)),
+            None => Ok(None),
+        }
+    }
+    /// Returns receiver code biases
+    fn bias_table(&self) -> BiasTable {
+        match self.matches.get_many::<CodeBias>("dcb") {
+            Some(biases) => BiasTable::new(&biases.copied().collect::<Vec<_>>()),
+            None => BiasTable::default(),
+        }
+    }
+    /// Returns measurements processing options
+    pub fn ublox_opts(&self) -> UbloxOpts {
+        UbloxOpts {
+            biases: self.bias_table(),
+            sbas: self.matches.get_flag("sbas"),
+            signals: match self.matches.get_many::<ExtendedSignal>("signal") {
+                Some(signals) => signals.copied().collect(),
+                None => Vec::new(),
+            },
+            start: self.matches.get_one::<StartType>("start").copied(),
+            timepulse: self.matches.get_one::<u32>("timepulse").copied(),
+            cable_delay_ns: self.cable_delay_ns(),
+            heading: self.matches.get_flag("heading"),
+            use_phase: self.matches.get_flag("use-phase"),
+            max_gap: *self.matches.get_one::<Duration>("max-gap").unwrap(),
+            combinations: self.matches.get_flag("combinations"),
+            esf: self.matches.get_flag("esf"),
+            osnma: self.osnma(),
+            ack_timeout: StdDuration::from_secs_f64(
+                self.matches
+                    .get_one::<Duration>("ack-timeout")
+                    .unwrap()
+                    .to_seconds(),
+            ),
+            ack_retries: *self.matches.get_one::<u8>("ack-retries").unwrap(),
+            verify_config: self.matches.get_flag("verify-config"),
+            persist: self.matches.get_flag("persist"),
+            clocks: None,
+            strict_clocks: self.matches.get_flag("strict-clocks"),
+            ionex: None,
+            max_pr_stdev: self.matches.get_one::<f64>("max-pr-stdev").copied(),
+            max_cp_stdev: self.matches.get_one::<f64>("max-cp-stdev").copied(),
+            pr_window: (
+                *self.matches.get_one::<f64>("min-pr").unwrap() * 1.0E3,
+                *self.matches.get_one::<f64>("max-pr").unwrap() * 1.0E3,
+            ),
+            dyn_model: self.matches.get_one::<DynModel>("dyn-model").copied(),
+            ports: self
+                .matches
+                .get_one::<MessagePorts>("msg-ports")
+                .copied()
+                .unwrap_or_default(),
+            ant_power: self.matches.get_one::<AntennaPower>("ant-power").copied(),
+            warmup: self
+                .matches
+                .get_one::<u64>("warmup-ms")
+                .map(|ms| StdDuration::from_millis(*ms)),
+            anomalies: if self.matches.get_flag("anomalies") {
+                Some(AnomalyOpts {
+                    cno_jump: *self.matches.get_one::<f64>("cno-jump").unwrap(),
+                    jam_ind: *self.matches.get_one::<u8>("jam-indicator").unwrap(),
+                })
+            } else {
+                None
+            },
+            fix_only: false,
+        }
+    }
+    /// Returns true if Galileo OSNMA status is requested
+    #[cfg(feature = "osnma")]
+    fn osnma(&self) -> bool {
+        self.matches.get_flag("osnma")
+    }
+    /// Returns true if Galileo OSNMA status is requested
+    #[cfg(not(feature = "osnma"))]
+    fn osnma(&self) -> bool {
+        false
+    }
+    /// Returns antenna cable delay (ns), if specified
+    pub fn cable_delay_ns(&self) -> Option<f64> {
+        self.matches.get_one::<f64>("cable-delay-ns").copied()
+    }
+    /// Returns selected [TropoModel]
+    pub fn tropo_model(&self) -> TropoModel {
+        self.matches
+            .get_one::<TropoModel>("tropo")
+            .copied()
+            .unwrap_or_default()
+    }
+    /// Returns SP3 file path, if any
+    pub fn sp3(&self) -> Option<&Path> {
+        self.matches.get_one::<PathBuf>("sp3").map(|p| p.as_path())
+    }
+    /// Returns precise clocks file path, if any
+    pub fn clk(&self) -> Option<&Path> {
+        self.matches.get_one::<PathBuf>("clk").map(|p| p.as_path())
+    }
+    /// Returns IONEX file path, if any
+    pub fn ionex(&self) -> Option<&Path> {
+        self.matches
+            .get_one::<PathBuf>("ionex")
+            .map(|p| p.as_path())
+    }
+    /// Returns true if clock model should filter the clock solution
+    pub fn clock_filter(&self) -> bool {
+        self.matches.get_flag("clock-filter")
+    }
+    /// Returns static survey options, if requested
+    pub fn survey_opts(&self) -> Option<SurveyOpts> {
+        let duration = self.matches.get_one::<Duration>("survey")?;
+        Some(SurveyOpts {
+            duration: *duration,
+            marker: self.matches.get_one::<PathBuf>("survey-marker").cloned(),
+        })
+    }
 }
@@ -0,0 +1,168 @@
+//! Precise SV clocks, loaded from RINEX clock files
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
+
+use gnss_rtk::prelude::{Duration, Epoch, TimeScale, SV};
+
+use crate::Error;
+
+/// Tabulated SV clock offsets (s), sorted by [Epoch]
+type Record = Vec<(Epoch, f64)>;
+
+/// Precise clocks source, linearly interpolating clock records
+#[derive(Debug, Default)]
+pub struct PreciseClockSource {
+    records: HashMap<SV, Record>,
+}
+
+/// Parses SV clock record: "AS G01  2024 01 15 00 00  0.000000  2   -1.234567890123E-04 ..."
+fn parse_record(line: &str, ts: TimeScale) -> Option<(SV, Epoch, f64)> {
+    let mut items = line.get(3..)?.split_ascii_whitespace();
+    let sv = SV::from_str(items.next()?).ok()?;
+    let y = items.next()?.parse::<i32>().ok()?;
+    let m = items.next()?.parse::<u8>().ok()?;
+    let d = items.next()?.parse::<u8>().ok()?;
+    let hh = items.next()?.parse::<u8>().ok()?;
+    let mm = items.next()?.parse::<u8>().ok()?;
+    let secs = items.next()?.parse::<f64>().ok()?;
+    let _nb_values = items.next()?;
+    let bias = items.next()?.replace('D', "E").parse::<f64>().ok()?;
+    let nanos = (secs.fract() * 1.0E9).round() as u32;
+    let t = Epoch::from_gregorian(y, m, d, hh, mm, secs.trunc() as u8, nanos, ts);
+    Some((sv, t, bias))
+}
+
+impl PreciseClockSource {
+    /// Loads all SV clock records from RINEX clock file
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let fd = File::open(path)
+            .map_err(|e| Error::InvalidClk(format!("{}: {}", path.display(), e)))?;
+
+        let mut ts = TimeScale::GPST;
+        let mut header = true;
+        let mut records = HashMap::<SV, Record>::new();
+
+        for line in BufReader::new(fd).lines() {
+            let line = line.map_err(|e| Error::InvalidClk(format!("{}: {}", path.display(), e)))?;
+            if header {
+                let label = line.get(60..).unwrap_or_default().trim();
+                if label == "TIME SYSTEM ID" {
+                    match line.get(..6).map(|s| s.trim()) {
+                        Some("GAL") => ts = TimeScale::GST,
+                        Some("BDT") => ts = TimeScale::BDT,
+                        Some("UTC") => ts = TimeScale::UTC,
+                        Some("TAI") => ts = TimeScale::TAI,
+                        _ => {},
+                    }
+                } else if label == "END OF HEADER" {
+                    header = false;
+                }
+            } else if line.starts_with("AS ") {
+                if let Some((sv, t, bias)) = parse_record(&line, ts) {
+                    records.entry(sv).or_default().push((t, bias));
+                }
+            }
+        }
+
+        if records.is_empty() {
+            return Err(Error::InvalidClk(format!(
+                "{}: no satellite clock record",
+                path.display()
+            )));
+        }
+        for record in records.values_mut() {
+            record.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        info!(
+            "{}: precise clocks for {} satellites",
+            path.display(),
+            records.len()
+        );
+        Ok(Self { records })
+    }
+
+    /// Interpolates [SV] clock correction at `t`, linearly between surrounding records.
+    /// Returns None when `t` is not covered.
+    pub fn clock_correction(&self, t: Epoch, sv: SV) -> Option<Duration> {
+        let record = self.records.get(&sv)?;
+        let (t0, _) = record.first()?;
+        let t = t.to_time_scale(t0.time_scale);
+
+        let after = record.partition_point(|(ti, _)| *ti <= t);
+        let (t_a, bias_a) = record.get(after.checked_sub(1)?)?;
+        if *t_a == t {
+            return Some(Duration::from_seconds(*bias_a));
+        }
+        let (t_b, bias_b) = record.get(after)?;
+        let ratio = (t - *t_a).to_seconds() / (*t_b - *t_a).to_seconds();
+        Some(Duration::from_seconds(bias_a + (bias_b - bias_a) * ratio))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Constellation;
+
+    fn clk_file(name: &str) -> std::path::PathBuf {
+        let content = [
+            format!("{:<60}{}", "     3.04           C", "RINEX VERSION / TYPE"),
+            format!("{:<60}{}", "GPS", "TIME SYSTEM ID"),
+            format!("{:<60}{}", "", "END OF HEADER"),
+            "AR ABMF 2024 01 15 00 00  0.000000  1   -1.000000000000E-06".to_string(),
+            "AS G01  2024 01 15 00 00  0.000000  2   -1.234500000000E-04  1.0E-11".to_string(),
+            "AS G01  2024 01 15 00 00 30.000000  2   -1.234200000000E-04  1.0E-11".to_string(),
+            "AS G07  2024 01 15 00 00  0.000000  1    4.500000000000D-05".to_string(),
+        ]
+        .join("\n");
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn precise_clock_interpolation() {
+        let clocks = PreciseClockSource::from_file(&clk_file("rt-navi-clocks.clk")).unwrap();
+        let g01 = SV::new(Constellation::GPS, 1);
+        let t0 = Epoch::from_gregorian(2024, 1, 15, 0, 0, 0, 0, TimeScale::GPST);
+
+        // tabulated, then linearly interpolated
+        let corr = clocks.clock_correction(t0, g01).unwrap();
+        assert!((corr.to_seconds() + 1.2345E-4).abs() < 1.0E-15);
+        let corr = clocks
+            .clock_correction(t0 + Duration::from_seconds(10.0), g01)
+            .unwrap();
+        assert!((corr.to_seconds() + 1.2344E-4).abs() < 1.0E-15);
+
+        // Fortran exponent
+        let g07 = SV::new(Constellation::GPS, 7);
+        let corr = clocks.clock_correction(t0, g07).unwrap();
+        assert!((corr.to_seconds() - 4.5E-5).abs() < 1.0E-15);
+    }
+
+    #[test]
+    fn uncovered_clocks_fall_back() {
+        let clocks = PreciseClockSource::from_file(&clk_file("rt-navi-fallback.clk")).unwrap();
+        let t0 = Epoch::from_gregorian(2024, 1, 15, 0, 0, 0, 0, TimeScale::GPST);
+
+        // no precise clock past the records, or for other SV: the candidate is proposed
+        // without clock correction, or withheld with --strict-clocks
+        let g01 = SV::new(Constellation::GPS, 1);
+        assert!(clocks
+            .clock_correction(t0 + Duration::from_seconds(31.0), g01)
+            .is_none());
+        assert!(clocks
+            .clock_correction(t0 - Duration::from_seconds(1.0), g01)
+            .is_none());
+        assert!(clocks
+            .clock_correction(t0, SV::new(Constellation::GPS, 2))
+            .is_none());
+        // receiver clocks are ignored
+        assert_eq!(clocks.records.len(), 2);
+    }
+}
@@ -0,0 +1,158 @@
+//! Receiver clock model, filtering the clock offset reported by the solver
+use gnss_rtk::prelude::{Duration, Epoch};
+
+use crate::pvt::Pvt;
+
+/// Offset random walk spectral density (s²/s)
+const OFFSET_NOISE: f64 = 1.0E-18;
+
+/// Drift white noise spectral density ((s/s)²/s)
+const DRIFT_NOISE: f64 = 1.0E-22;
+
+/// Clock offset measurement variance (s²), typical of code based navigation
+const MEASUREMENT_NOISE: f64 = 1.0E-16;
+
+/// Solutions further apart are not propagated: the model is reset
+const MAX_GAP: Duration = Duration::from_seconds(10.0);
+
+/// Two states (offset, drift) Kalman filter: random walk on offset,
+/// white noise on drift.
+#[derive(Debug, Default)]
+pub struct ClockFilter {
+    /// Last update [Epoch]
+    epoch: Option<Epoch>,
+    /// Clock offset (s)
+    offset: f64,
+    /// Clock drift (s/s)
+    drift: f64,
+    /// State covariance
+    p: [[f64; 2]; 2],
+}
+
+impl ClockFilter {
+    /// Resets the model, next solution initializes it again
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Filters the clock offset of this [Pvt] and reports the estimated drift
+    pub fn filter(&mut self, pvt: &mut Pvt) {
+        let dt = match self.epoch {
+            Some(epoch) if pvt.epoch > epoch && pvt.epoch - epoch <= MAX_GAP => {
+                (pvt.epoch - epoch).to_seconds()
+            },
+            Some(_) => {
+                debug!("{} clock model reset", pvt.epoch);
+                self.reset();
+                self.initialize(pvt);
+                return;
+            },
+            None => {
+                self.initialize(pvt);
+                return;
+            },
+        };
+
+        // prediction
+        let offset = self.offset + self.drift * dt;
+        let [[p00, p01], [p10, p11]] = self.p;
+        let p00 = p00 + dt * (p01 + p10) + dt * dt * p11 + OFFSET_NOISE * dt;
+        let p01 = p01 + dt * p11;
+        let p10 = p10 + dt * p11;
+        let p11 = p11 + DRIFT_NOISE * dt;
+
+        // correction
+        let innovation = pvt.dt - offset;
+        let s = p00 + MEASUREMENT_NOISE;
+        let (k0, k1) = (p00 / s, p10 / s);
+
+        self.offset = offset + k0 * innovation;
+        self.drift += k1 * innovation;
+        self.p = [
+            [(1.0 - k0) * p00, (1.0 - k0) * p01],
+            [p10 - k1 * p00, p11 - k1 * p01],
+        ];
+        self.epoch = Some(pvt.epoch);
+
+        pvt.dt = self.offset;
+        pvt.drift = Some(self.drift);
+    }
+
+    /// Initializes the model from the first solution: drift is not observable yet
+    fn initialize(&mut self, pvt: &Pvt) {
+        self.epoch = Some(pvt.epoch);
+        self.offset = pvt.dt;
+        self.drift = 0.0;
+        self.p = [[MEASUREMENT_NOISE, 0.0], [0.0, 1.0E-12]];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clock drift (s/s)
+    const DRIFT: f64 = 5.0E-8;
+
+    /// Deterministic noise, uniform over ±20 ns
+    fn noise(seed: &mut u64) -> f64 {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((*seed >> 11) as f64 / (1_u64 << 53) as f64 - 0.5) * 4.0E-8
+    }
+
+    fn pvt(t: Epoch, dt: f64) -> Pvt {
+        let mut pvt = Pvt::fixture(t, (4_201_575.0, 189_856.0, 4_779_066.0));
+        pvt.dt = dt;
+        pvt
+    }
+
+    #[test]
+    fn drift_smoother_than_differences() {
+        let t0 = Epoch::from_gpst_seconds(1_000_000.0);
+        let mut filter = ClockFilter::default();
+        let mut seed = 1;
+        let mut previous = None;
+        let (mut raw_sq, mut filtered_sq) = (0.0, 0.0);
+
+        for i in 0..120 {
+            let t = t0 + Duration::from_seconds(i as f64);
+            let mut pvt = pvt(t, 1.0E-4 + DRIFT * i as f64 + noise(&mut seed));
+            // drift as consecutive offsets difference
+            let raw = previous.replace(pvt.dt).map(|dt0| pvt.dt - dt0);
+            filter.filter(&mut pvt);
+            // once converged
+            if i >= 60 {
+                raw_sq += (raw.unwrap() - DRIFT).powi(2);
+                filtered_sq += (pvt.drift.unwrap() - DRIFT).powi(2);
+            }
+        }
+
+        let raw_rms = (raw_sq / 60.0).sqrt();
+        let filtered_rms = (filtered_sq / 60.0).sqrt();
+        assert!(
+            filtered_rms < raw_rms / 20.0,
+            "filtered={:e} raw={:e}",
+            filtered_rms,
+            raw_rms
+        );
+    }
+
+    #[test]
+    fn gap_resets_model() {
+        let t0 = Epoch::from_gpst_seconds(1_000_000.0);
+        let mut filter = ClockFilter::default();
+        for i in 0..10 {
+            let t = t0 + Duration::from_seconds(i as f64);
+            filter.filter(&mut pvt(t, 1.0E-4 + DRIFT * i as f64));
+        }
+
+        // initialized again: solution preserved, drift not observable
+        let mut pvt = pvt(t0 + Duration::from_seconds(30.0), 2.0E-4);
+        filter.filter(&mut pvt);
+        assert_eq!(pvt.dt, 2.0E-4);
+        assert_eq!(pvt.drift, None);
+        assert_eq!(filter.drift, 0.0);
+    }
+}
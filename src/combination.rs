@@ -0,0 +1,210 @@
+//! Dual frequency combinations (widelane, narrowlane, multipath),
+//! for ambiguity resolution and multipath diagnostics
+use std::collections::HashMap;
+
+use gnss_rtk::prelude::{Carrier, SV};
+
+use crate::carrier::{
+    carrier_frequency_hz, iono_free_coefficients, wavelength, SPEED_OF_LIGHT_M_S,
+};
+
+/// Widelane wavelength (m) of two carriers: 86cm for L1/L2, 75cm for L1/L5
+fn widelane_wavelength(c1: Carrier, c2: Carrier) -> f64 {
+    SPEED_OF_LIGHT_M_S / (carrier_frequency_hz(c1) - carrier_frequency_hz(c2)).abs()
+}
+
+/// MP1 and MP2 (m) from the codes and phases (m) of two carriers, f1 > f2
+fn multipath(p1: f64, p2: f64, l1: f64, l2: f64, f1: f64, f2: f64) -> (f64, f64) {
+    let alpha = (f1 / f2).powi(2);
+    let k = 2.0 / (alpha - 1.0);
+    (
+        p1 - (1.0 + k) * l1 + k * l2,
+        p2 - alpha * k * l1 + (alpha * k - 1.0) * l2,
+    )
+}
+
+/// One signal observation
+#[derive(Debug, Clone, Copy)]
+struct Observation {
+    carrier: Carrier,
+    /// Pseudo range (m)
+    code: f64,
+    /// Carrier phase (cycles)
+    phase: Option<f64>,
+}
+
+/// Combinations of two signals of one SV
+#[derive(Debug, Clone, Copy)]
+pub struct Combination {
+    pub sv: SV,
+    /// Carriers, highest frequency first
+    pub carriers: (Carrier, Carrier),
+    /// Widelane phase (m), when both phases are tracked
+    pub widelane_phase: Option<f64>,
+    /// Narrowlane code (m)
+    pub narrowlane_code: f64,
+    /// Melbourne-Wübbena (widelane cycles): widelane phase minus narrowlane code,
+    /// free of geometry, clocks and first order ionosphere. Converges to the widelane ambiguity.
+    pub melbourne_wubbena: Option<f64>,
+    /// Ionosphere free code (m)
+    pub iono_free_code: f64,
+    /// Multipath combinations MP1 and MP2 (m), when both phases are tracked:
+    /// code minus a geometry and ionosphere free phase combination. They carry a constant
+    /// bias (phase ambiguities) while tracking is continuous: their variation is the code multipath.
+    pub multipath: Option<(f64, f64)>,
+}
+
+impl Combination {
+    /// Combines two observations of `sv`, None on identical frequencies
+    fn new(sv: SV, a: &Observation, b: &Observation) -> Option<Self> {
+        let (o1, o2) = if carrier_frequency_hz(a.carrier) > carrier_frequency_hz(b.carrier) {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let (f1, f2) = (
+            carrier_frequency_hz(o1.carrier),
+            carrier_frequency_hz(o2.carrier),
+        );
+        let (if1, if2) = iono_free_coefficients(o1.carrier, o2.carrier)?;
+
+        let phases = match (o1.phase, o2.phase) {
+            (Some(phi1), Some(phi2)) => {
+                Some((phi1 * wavelength(o1.carrier), phi2 * wavelength(o2.carrier)))
+            },
+            _ => None,
+        };
+        let widelane_phase = phases.map(|(l1, l2)| (f1 * l1 - f2 * l2) / (f1 - f2));
+        let narrowlane_code = (f1 * o1.code + f2 * o2.code) / (f1 + f2);
+        let melbourne_wubbena = widelane_phase
+            .map(|wl| (wl - narrowlane_code) / widelane_wavelength(o1.carrier, o2.carrier));
+
+        Some(Self {
+            sv,
+            carriers: (o1.carrier, o2.carrier),
+            widelane_phase,
+            narrowlane_code,
+            melbourne_wubbena,
+            iono_free_code: if1 * o1.code + if2 * o2.code,
+            multipath: phases.map(|(l1, l2)| multipath(o1.code, o2.code, l1, l2, f1, f2)),
+        })
+    }
+}
+
+/// Collects the observations of one measurement epoch, and forms
+/// the combinations of every SV tracked on two frequencies.
+#[derive(Debug, Default)]
+pub struct Combiner {
+    observations: HashMap<SV, Vec<Observation>>,
+}
+
+impl Combiner {
+    /// New observation: pseudo range (m) and carrier phase (cycles)
+    pub fn push(&mut self, sv: SV, carrier: Carrier, code: f64, phase: Option<f64>) {
+        self.observations.entry(sv).or_default().push(Observation {
+            carrier,
+            code,
+            phase,
+        });
+    }
+
+    /// Forms the combinations of this epoch, combining the first two signals
+    /// of each SV, then starts a new epoch
+    pub fn combine(&mut self) -> Vec<Combination> {
+        let mut combinations = Vec::new();
+        for (sv, observations) in self.observations.drain() {
+            if let [a, b, ..] = observations.as_slice() {
+                if let Some(combination) = Combination::new(sv, a, b) {
+                    combinations.push(combination);
+                }
+            }
+        }
+        combinations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Constellation;
+
+    /// Code and phase (cycles) of a synthetic L1/L2 observation:
+    /// geometric range `rho`, L1 ionosphere delay `iono` (m) and code multipath (m)
+    fn observe(combiner: &mut Combiner, sv: SV, rho: f64, iono: f64, mp: (f64, f64)) {
+        let alpha = (carrier_frequency_hz(Carrier::L1) / carrier_frequency_hz(Carrier::L2)).powi(2);
+        combiner.push(
+            sv,
+            Carrier::L1,
+            rho + iono + mp.0,
+            Some((rho - iono) / wavelength(Carrier::L1)),
+        );
+        combiner.push(
+            sv,
+            Carrier::L2,
+            rho + alpha * iono + mp.1,
+            Some((rho - alpha * iono) / wavelength(Carrier::L2)),
+        );
+    }
+
+    #[test]
+    fn multipath_from_synthetic_code_phase() {
+        let sv = SV::new(Constellation::GPS, 1);
+        let mut combiner = Combiner::default();
+        observe(&mut combiner, sv, 21_000_000.0, 4.2, (0.7, -0.4));
+
+        let combinations = combiner.combine();
+        assert_eq!(combinations.len(), 1);
+        assert_eq!(combinations[0].carriers, (Carrier::L1, Carrier::L2));
+
+        // geometry and ionosphere cancel: only the code multipath remains
+        let (mp1, mp2) = combinations[0].multipath.unwrap();
+        assert!((mp1 - 0.7).abs() < 1.0E-6, "mp1={}", mp1);
+        assert!((mp2 + 0.4).abs() < 1.0E-6, "mp2={}", mp2);
+    }
+
+    #[test]
+    fn widelane_ambiguity() {
+        // 86cm for L1/L2, 75cm for L1/L5, whatever the order
+        assert!((widelane_wavelength(Carrier::L1, Carrier::L2) - 0.861918400).abs() < 1.0E-9);
+        assert!((widelane_wavelength(Carrier::L5, Carrier::L1) - 0.751416041).abs() < 1.0E-9);
+
+        // the Melbourne-Wubbena combination is the widelane ambiguity N1 - N2
+        let sv = SV::new(Constellation::GPS, 1);
+        let (rho, iono) = (21_000_000.0, 4.2);
+        let alpha = (carrier_frequency_hz(Carrier::L1) / carrier_frequency_hz(Carrier::L2)).powi(2);
+        let mut combiner = Combiner::default();
+        combiner.push(
+            sv,
+            Carrier::L2,
+            rho + alpha * iono,
+            Some((rho - alpha * iono) / wavelength(Carrier::L2) - 12.0),
+        );
+        combiner.push(
+            sv,
+            Carrier::L1,
+            rho + iono,
+            Some((rho - iono) / wavelength(Carrier::L1) + 5.0),
+        );
+        let combinations = combiner.combine();
+        assert_eq!(combinations[0].carriers, (Carrier::L1, Carrier::L2));
+        let mw = combinations[0].melbourne_wubbena.unwrap();
+        assert!((mw - 17.0).abs() < 1.0E-6, "mw={}", mw);
+        assert!((combinations[0].iono_free_code - rho).abs() < 1.0E-6);
+    }
+
+    #[test]
+    fn multipath_requires_both_phases() {
+        let sv = SV::new(Constellation::GPS, 1);
+        let mut combiner = Combiner::default();
+        combiner.push(sv, Carrier::L1, 21_000_000.0, Some(1.0E8));
+        combiner.push(sv, Carrier::L2, 21_000_000.0, None);
+        let combinations = combiner.combine();
+        assert_eq!(combinations.len(), 1);
+        assert!(combinations[0].multipath.is_none());
+
+        // single frequency: no combination, and the combiner starts a new epoch
+        combiner.push(sv, Carrier::L1, 21_000_000.0, Some(1.0E8));
+        assert!(combiner.combine().is_empty());
+        assert!(combiner.combine().is_empty());
+    }
+}
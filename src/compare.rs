@@ -0,0 +1,91 @@
+//! Running comparison between our solutions and the receiver's own fix
+use gnss_rtk::prelude::Epoch;
+
+use crate::{pvt::Pvt, source::ReceiverPosition};
+
+/// Compares each epoch outcome to the latest receiver fix (NAV-PVT),
+/// a self check of the orbits and clocks we navigate with.
+#[derive(Debug, Default)]
+pub struct Comparison {
+    /// Latest receiver fix, None while the receiver has no fix
+    receiver: Option<ReceiverPosition>,
+}
+
+impl Comparison {
+    /// Stores latest receiver fix
+    pub fn on_receiver_fix(&mut self, fix: Option<ReceiverPosition>) {
+        self.receiver = fix;
+    }
+
+    /// Logs how our solution (if any) compares to the receiver fix (if any).
+    /// Returns horizontal and vertical differences (m), when both have a fix.
+    pub fn on_epoch(&self, t: Epoch, fix: Option<&Pvt>) -> Option<(f64, f64)> {
+        match (fix, &self.receiver) {
+            (Some(pvt), Some(rx)) => {
+                let (horizontal, vertical) = pvt.offset_to(rx.lat, rx.lon, rx.alt);
+                info!(
+                    "{} difference to receiver fix: horizontal={:.3}m vertical={:.3}m \
+                     (receiver hacc={:.3}m vacc={:.3}m)",
+                    t, horizontal, vertical, rx.h_acc, rx.v_acc
+                );
+                Some((horizontal, vertical))
+            },
+            (Some(_), None) => {
+                warn!("{} solution obtained, but receiver has no fix", t);
+                None
+            },
+            (None, Some(_)) => {
+                warn!("{} receiver has a fix, but no solution was obtained", t);
+                None
+            },
+            (None, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::geodetic_to_ecef;
+
+    const RECEIVER: ReceiverPosition = ReceiverPosition {
+        lat: 45.0,
+        lon: 5.0,
+        alt: 100.0,
+        h_acc: 1.5,
+        v_acc: 2.5,
+    };
+
+    /// Our fix, displaced from the receiver fix along its local east, north and up
+    fn displaced(t: Epoch, east: f64, north: f64, up: f64) -> Pvt {
+        let (x, y, z) = geodetic_to_ecef(RECEIVER.lat, RECEIVER.lon, RECEIVER.alt);
+        let (lat, lon) = (RECEIVER.lat.to_radians(), RECEIVER.lon.to_radians());
+        let position = (
+            x - lon.sin() * east - lat.sin() * lon.cos() * north + lat.cos() * lon.cos() * up,
+            y + lon.cos() * east - lat.sin() * lon.sin() * north + lat.cos() * lon.sin() * up,
+            z + lat.cos() * north + lat.sin() * up,
+        );
+        Pvt::fixture(t, position)
+    }
+
+    #[test]
+    fn difference_to_receiver_fix() {
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let pvt = displaced(t, 4.0, 3.0, -1.5);
+        let mut comparison = Comparison::default();
+
+        // receiver without fix
+        assert_eq!(comparison.on_epoch(t, Some(&pvt)), None);
+
+        comparison.on_receiver_fix(Some(RECEIVER));
+        let (horizontal, vertical) = comparison.on_epoch(t, Some(&pvt)).unwrap();
+        assert!((horizontal - 5.0).abs() < 1.0E-4, "{}", horizontal);
+        // the receiver fix lies above ours
+        assert!((vertical - 1.5).abs() < 1.0E-4, "{}", vertical);
+
+        // no solution
+        assert_eq!(comparison.on_epoch(t, None), None);
+        comparison.on_receiver_fix(None);
+        assert_eq!(comparison.on_epoch(t, None), None);
+    }
+}
@@ -0,0 +1,326 @@
+//! Solver configuration file
+use std::{fs::File, io::BufReader, path::Path, str::FromStr};
+
+use gnss_rtk::prelude::{Config, Filter, Method};
+use serde::Deserialize;
+
+use crate::{quality::QualityConfig, reset::ResetStrategy, Error};
+
+/// User dynamics, that drive the navigation filter
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserProfile {
+    Static,
+    Walking,
+    Cycling,
+    Driving,
+    Flying,
+}
+
+impl FromStr for UserProfile {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "static" => Ok(Self::Static),
+            "walking" => Ok(Self::Walking),
+            "cycling" => Ok(Self::Cycling),
+            "driving" => Ok(Self::Driving),
+            "flying" => Ok(Self::Flying),
+            _ => Err(Error::InvalidConfig(format!("unknown profile \"{}\"", s))),
+        }
+    }
+}
+
+impl UserProfile {
+    /// Solver profile name. gnss-rtk only models static and pedestrian users:
+    /// faster dynamics use the pedestrian profile.
+    fn solver_profile(&self) -> &'static str {
+        match self {
+            Self::Static => "Static",
+            Self::Walking => "Pedestrian",
+            Self::Cycling | Self::Driving | Self::Flying => {
+                warn!(
+                    "{:?} profile not supported by solver: using pedestrian",
+                    self
+                );
+                "Pedestrian"
+            },
+        }
+    }
+}
+
+/// Atmospheric and physical models, all optional:
+/// unspecified fields preserve the preset
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ModelingConfig {
+    pub sv_clock_bias: Option<bool>,
+    pub sv_total_group_delay: Option<bool>,
+    pub relativistic_clock_bias: Option<bool>,
+    pub relativistic_path_range: Option<bool>,
+    pub tropo_delay: Option<bool>,
+    pub iono_delay: Option<bool>,
+    pub earth_rotation: Option<bool>,
+    pub phase_windup: Option<bool>,
+}
+
+/// Commonly tuned solver parameters, as described in the configuration file (JSON).
+/// Unspecified fields preserve the static preset of the selected [Method].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SolverConfig {
+    /// Positioning [Method]
+    pub method: Option<Method>,
+    /// [UserProfile]
+    pub profile: Option<UserProfile>,
+    /// Navigation [Filter]
+    pub filter: Option<Filter>,
+    /// Reject solutions past this GDOP
+    pub gdop_threshold: Option<f64>,
+    /// Reject solutions past this TDOP
+    pub tdop_threshold: Option<f64>,
+    /// Post fit Kalman filter
+    pub postfit_kf: Option<bool>,
+    /// Minimal SV elevation (degrees)
+    pub min_sv_elev: Option<f64>,
+    /// Minimal SNR (dB)
+    pub min_snr: Option<f64>,
+    /// Minimal SV sunlight rate
+    pub min_sv_sunlight_rate: Option<f64>,
+    /// Fixed altitude (m)
+    pub fixed_altitude: Option<f64>,
+    /// Code smoothing
+    pub code_smoothing: Option<bool>,
+    /// Orbit interpolation order
+    pub interp_order: Option<usize>,
+    /// Physical models
+    pub modeling: ModelingConfig,
+    /// Solver [ResetStrategy], applied by rt-navi. Example: {"reset": {"after_n_errors": 3}}
+    pub reset: Option<ResetStrategy>,
+    /// Fix quality score [QualityConfig], applied by rt-navi. Example: {"quality": {"max_gdop": 6}}
+    pub quality: QualityConfig,
+}
+
+impl SolverConfig {
+    /// Loads [SolverConfig] from JSON file
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let fd = File::open(path)
+            .map_err(|e| Error::InvalidConfig(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_reader(BufReader::new(fd))
+            .map_err(|e| Error::InvalidConfig(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Verifies all parameters are within range
+    fn validate(&self) -> Result<(), Error> {
+        if let Some(elev) = self.min_sv_elev {
+            if !(0.0..=90.0).contains(&elev) {
+                return Err(Error::InvalidConfig(format!(
+                    "min_sv_elev {} out of [0, 90] range",
+                    elev
+                )));
+            }
+        }
+        if let Some(rate) = self.min_sv_sunlight_rate {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(Error::InvalidConfig(format!(
+                    "min_sv_sunlight_rate {} out of [0, 1] range",
+                    rate
+                )));
+            }
+        }
+        if let Some(snr) = self.min_snr {
+            if snr < 0.0 {
+                return Err(Error::InvalidConfig(format!("negative min_snr {}", snr)));
+            }
+        }
+        for (name, dop) in [("gdop", self.gdop_threshold), ("tdop", self.tdop_threshold)] {
+            if let Some(dop) = dop {
+                if dop <= 0.0 {
+                    return Err(Error::InvalidConfig(format!(
+                        "{}_threshold {} should be positive",
+                        name, dop
+                    )));
+                }
+            }
+        }
+        match self.reset {
+            Some(ResetStrategy::AfterNErrors(0)) => {
+                return Err(Error::InvalidConfig(
+                    "after_n_errors should be at least 1".to_string(),
+                ));
+            },
+            Some(ResetStrategy::OnBigJump(threshold)) if threshold <= 0.0 => {
+                return Err(Error::InvalidConfig(format!(
+                    "on_big_jump {} should be positive",
+                    threshold
+                )));
+            },
+            _ => {},
+        }
+        self.quality.validate()?;
+        if let Some(order) = self.interp_order {
+            if order % 2 == 0 {
+                return Err(Error::InvalidConfig(format!(
+                    "interp_order {} should be odd",
+                    order
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds solver [Config]: static preset of the selected [Method] (SPP by default),
+    /// overridden by all specified parameters
+    pub fn to_config(&self) -> Result<Config, Error> {
+        self.validate()?;
+
+        let mut cfg = Config::static_preset(self.method.unwrap_or(Method::SPP));
+
+        if let Some(profile) = self.profile {
+            // solver profiles are not exposed by gnss-rtk, other than through serde
+            cfg.profile = serde_json::from_value(profile.solver_profile().into())
+                .map_err(|e| Error::InvalidConfig(e.to_string()))?;
+        }
+        if let Some(filter) = self.filter {
+            cfg.solver.filter = filter;
+        }
+        if let Some(gdop) = self.gdop_threshold {
+            cfg.solver.gdop_threshold = Some(gdop);
+        }
+        if let Some(tdop) = self.tdop_threshold {
+            cfg.solver.tdop_threshold = Some(tdop);
+        }
+        if let Some(postfit_kf) = self.postfit_kf {
+            cfg.solver.postfit_kf = postfit_kf;
+        }
+        if let Some(elev) = self.min_sv_elev {
+            cfg.min_sv_elev = Some(elev);
+        }
+        if let Some(snr) = self.min_snr {
+            cfg.min_snr = Some(snr);
+        }
+        if let Some(rate) = self.min_sv_sunlight_rate {
+            cfg.min_sv_sunlight_rate = Some(rate);
+        }
+        if let Some(alt) = self.fixed_altitude {
+            cfg.fixed_altitude = Some(alt);
+        }
+        if let Some(smoothing) = self.code_smoothing {
+            cfg.code_smoothing = smoothing;
+        }
+        if let Some(order) = self.interp_order {
+            cfg.interp_order = order;
+        }
+
+        let modeling = &self.modeling;
+        if let Some(flag) = modeling.sv_clock_bias {
+            cfg.modeling.sv_clock_bias = flag;
+        }
+        if let Some(flag) = modeling.sv_total_group_delay {
+            cfg.modeling.sv_total_group_delay = flag;
+        }
+        if let Some(flag) = modeling.relativistic_clock_bias {
+            cfg.modeling.relativistic_clock_bias = flag;
+        }
+        if let Some(flag) = modeling.relativistic_path_range {
+            cfg.modeling.relativistic_path_range = flag;
+        }
+        if let Some(flag) = modeling.tropo_delay {
+            cfg.modeling.tropo_delay = flag;
+        }
+        if let Some(flag) = modeling.iono_delay {
+            cfg.modeling.iono_delay = flag;
+        }
+        if let Some(flag) = modeling.earth_rotation {
+            cfg.modeling.earth_rotation = flag;
+        }
+        if let Some(flag) = modeling.phase_windup {
+            cfg.modeling.phase_windup = flag;
+        }
+
+        Ok(cfg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> SolverConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn overrides_propagate() {
+        let cfg = parse(
+            r#"{
+                "method": "PPP",
+                "gdop_threshold": 4.5,
+                "min_sv_elev": 15.0,
+                "min_snr": 30.0,
+                "fixed_altitude": 102.5,
+                "code_smoothing": true,
+                "interp_order": 9,
+                "modeling": {"tropo_delay": false, "earth_rotation": true}
+            }"#,
+        )
+        .to_config()
+        .unwrap();
+
+        assert_eq!(cfg.method, Method::PPP);
+        assert_eq!(cfg.solver.gdop_threshold, Some(4.5));
+        assert_eq!(cfg.min_sv_elev, Some(15.0));
+        assert_eq!(cfg.min_snr, Some(30.0));
+        assert_eq!(cfg.fixed_altitude, Some(102.5));
+        assert!(cfg.code_smoothing);
+        assert_eq!(cfg.interp_order, 9);
+        assert!(!cfg.modeling.tropo_delay);
+        assert!(cfg.modeling.earth_rotation);
+    }
+
+    #[test]
+    fn unspecified_preserve_preset() {
+        let cfg = parse("{}").to_config().unwrap();
+        let preset = Config::static_preset(Method::SPP);
+        assert_eq!(cfg.method, Method::SPP);
+        assert_eq!(cfg.min_sv_elev, preset.min_sv_elev);
+        assert_eq!(cfg.interp_order, preset.interp_order);
+        assert_eq!(cfg.solver.gdop_threshold, preset.solver.gdop_threshold);
+        assert_eq!(cfg.modeling.tropo_delay, preset.modeling.tropo_delay);
+    }
+
+    #[test]
+    fn invalid_parameters_rejected() {
+        for json in [
+            r#"{"min_sv_elev": 95.0}"#,
+            r#"{"min_sv_sunlight_rate": 1.5}"#,
+            r#"{"min_snr": -1.0}"#,
+            r#"{"tdop_threshold": 0.0}"#,
+            r#"{"interp_order": 8}"#,
+        ] {
+            assert!(parse(json).to_config().is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn profile_mapping() {
+        for (flag, profile, solver) in [
+            ("static", UserProfile::Static, "Static"),
+            ("walking", UserProfile::Walking, "Pedestrian"),
+            ("cycling", UserProfile::Cycling, "Pedestrian"),
+            ("Driving", UserProfile::Driving, "Pedestrian"),
+            (" flying", UserProfile::Flying, "Pedestrian"),
+        ] {
+            assert_eq!(UserProfile::from_str(flag).unwrap(), profile);
+            assert_eq!(profile.solver_profile(), solver);
+
+            // accepted by the solver
+            let cfg = SolverConfig {
+                profile: Some(profile),
+                ..Default::default()
+            };
+            assert!(cfg.to_config().is_ok(), "{}", flag);
+        }
+        assert!(UserProfile::from_str("running").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! WGS84 coordinates conversions
+
+/// WGS84 semi major axis (m)
+pub const WGS84_A: f64 = 6378137.0;
+/// WGS84 first eccentricity squared
+pub const WGS84_E2: f64 = 6.69437999014E-3;
+/// WGS84 flattening
+pub const WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// Latitude convergence (rad), about 0.01 mm on ground
+const LATITUDE_CONVERGENCE: f64 = 1.0E-12;
+
+/// Maximal number of latitude iterations. Bowring's first guess converges
+/// in one or two iterations, from the ground to geostationary altitudes.
+const MAX_ITERATIONS: usize = 10;
+
+/// Prime vertical radius of curvature (m), at latitude `lat` (rad)
+fn prime_vertical_radius(lat: f64) -> f64 {
+    WGS84_A / (1.0 - WGS84_E2 * lat.sin().powi(2)).sqrt()
+}
+
+/// Converts ECEF coordinates (m) to geodetic latitude and longitude (ddeg),
+/// and altitude above ellipsoid (m). Latitude is iterated from Bowring's first guess
+/// until it converges, and altitude is derived without dividing by cos(lat),
+/// so it remains accurate at the poles.
+pub fn ecef_to_geodetic(x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+    let b = WGS84_A * (1.0 - WGS84_E2).sqrt();
+    let ep2 = (WGS84_A.powi(2) - b.powi(2)) / b.powi(2);
+    let p = x.hypot(y);
+    let lon = y.atan2(x);
+
+    let theta = (z * WGS84_A).atan2(p * b);
+    let mut lat =
+        (z + ep2 * b * theta.sin().powi(3)).atan2(p - WGS84_E2 * WGS84_A * theta.cos().powi(3));
+
+    for _ in 0..MAX_ITERATIONS {
+        let n = prime_vertical_radius(lat);
+        let next = (z + WGS84_E2 * n * lat.sin()).atan2(p);
+        let converged = (next - lat).abs() < LATITUDE_CONVERGENCE;
+        lat = next;
+        if converged {
+            break;
+        }
+    }
+
+    let alt = p * lat.cos() + z * lat.sin() - WGS84_A * (1.0 - WGS84_E2 * lat.sin().powi(2)).sqrt();
+    (lat.to_degrees(), lon.to_degrees(), alt)
+}
+
+/// Converts geodetic latitude and longitude (ddeg), and altitude above ellipsoid (m),
+/// to ECEF coordinates (m). Inverse of [ecef_to_geodetic].
+pub fn geodetic_to_ecef(lat: f64, lon: f64, alt: f64) -> (f64, f64, f64) {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    let n = prime_vertical_radius(lat);
+    (
+        (n + alt) * lat.cos() * lon.cos(),
+        (n + alt) * lat.cos() * lon.sin(),
+        (n * (1.0 - WGS84_E2) + alt) * lat.sin(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// WGS84 semi minor axis (m)
+    const WGS84_B: f64 = 6356752.314245;
+
+    /// Asserts geodetic coordinates: 1E-9 ddeg (0.1 mm) and 0.1 mm
+    fn assert_geodetic((lat, lon, alt): (f64, f64, f64), expected: (f64, f64, f64)) {
+        assert!((lat - expected.0).abs() < 1.0E-9, "lat={}", lat);
+        assert!((lon - expected.1).abs() < 1.0E-9, "lon={}", lon);
+        assert!((alt - expected.2).abs() < 1.0E-4, "alt={}", alt);
+    }
+
+    #[test]
+    fn equator() {
+        assert_geodetic(ecef_to_geodetic(WGS84_A, 0.0, 0.0), (0.0, 0.0, 0.0));
+        assert_geodetic(ecef_to_geodetic(0.0, WGS84_A, 0.0), (0.0, 90.0, 0.0));
+        assert_geodetic(
+            ecef_to_geodetic(-WGS84_A - 100.0, 0.0, 0.0),
+            (0.0, 180.0, 100.0),
+        );
+    }
+
+    #[test]
+    fn poles() {
+        assert_geodetic(ecef_to_geodetic(0.0, 0.0, WGS84_B), (90.0, 0.0, 0.0));
+        assert_geodetic(
+            ecef_to_geodetic(0.0, 0.0, -WGS84_B - 10.0),
+            (-90.0, 0.0, 10.0),
+        );
+        // 1 mm off the axis
+        let (lat, lon, alt) = ecef_to_geodetic(1.0E-3, 0.0, WGS84_B + 1000.0);
+        assert!(lat < 90.0 && (lat - 90.0).abs() < 1.0E-8, "lat={}", lat);
+        assert!((alt - 1000.0).abs() < 1.0E-4, "alt={}", alt);
+        let (x, y, z) = geodetic_to_ecef(lat, lon, alt);
+        assert!((x - 1.0E-3).abs() < 1.0E-4 && y.abs() < 1.0E-4);
+        assert!((z - WGS84_B - 1000.0).abs() < 1.0E-4);
+    }
+
+    #[test]
+    fn high_altitude() {
+        // geostationary and GPS orbits
+        assert_geodetic(
+            ecef_to_geodetic(WGS84_A + 35_786_000.0, 0.0, 0.0),
+            (0.0, 0.0, 35_786_000.0),
+        );
+        let (x, y, z) = geodetic_to_ecef(55.0, -120.0, 20_200_000.0);
+        assert_geodetic(ecef_to_geodetic(x, y, z), (55.0, -120.0, 20_200_000.0));
+    }
+
+    #[test]
+    fn round_trip() {
+        for lat in [-89.999, -60.0, -12.5, 0.0, 33.3, 45.0, 78.9, 89.999] {
+            for lon in [-179.9, -90.0, 0.0, 4.5, 120.0, 180.0] {
+                for alt in [-100.0, 0.0, 1000.0, 400_000.0] {
+                    let (x, y, z) = geodetic_to_ecef(lat, lon, alt);
+                    assert_geodetic(ecef_to_geodetic(x, y, z), (lat, lon, alt));
+                }
+            }
+        }
+    }
+}
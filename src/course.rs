@@ -0,0 +1,114 @@
+//! Course over ground, from consecutive fixes
+use gnss_rtk::prelude::Epoch;
+
+use crate::pvt::{Pvt, MIN_HEADING_SPEED};
+
+/// Mean earth radius (m)
+const EARTH_RADIUS: f64 = 6371008.8;
+
+/// Returns great circle distance (m) and initial bearing (degrees, clockwise from north)
+/// from (lat1, lon1) to (lat2, lon2), all in ddeg
+pub fn distance_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f64) {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dphi = phi2 - phi1;
+    let dlambda = (lon2 - lon1).to_radians();
+
+    let a = (dphi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (dlambda / 2.0).sin().powi(2);
+    let distance = 2.0 * EARTH_RADIUS * a.sqrt().atan2((1.0 - a).sqrt());
+
+    let y = dlambda.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * dlambda.cos();
+    let bearing = y.atan2(x).to_degrees().rem_euclid(360.0);
+
+    (distance, bearing)
+}
+
+/// Tracks the last fix, to derive speed and heading when the solution
+/// does not provide any velocity
+#[derive(Debug, Default)]
+pub struct Course {
+    /// Last fix [Epoch], latitude and longitude (ddeg)
+    last: Option<(Epoch, f64, f64)>,
+}
+
+impl Course {
+    /// Updates course with new [Pvt]. Speed and heading are only
+    /// filled in when the solver did not provide a velocity.
+    pub fn update(&mut self, pvt: &mut Pvt) {
+        let (lat, lon, _) = pvt.geodetic;
+        let last = self.last.replace((pvt.epoch, lat, lon));
+
+        if pvt.velocity != (0.0, 0.0, 0.0) {
+            return;
+        }
+
+        let (t, lat0, lon0) = match last {
+            Some(last) => last,
+            None => return,
+        };
+
+        let dt = (pvt.epoch - t).to_seconds();
+        if dt <= 0.0 {
+            return;
+        }
+
+        let (distance, bearing) = distance_bearing(lat0, lon0, lat, lon);
+        pvt.speed = distance / dt;
+        pvt.heading = if pvt.speed < MIN_HEADING_SPEED {
+            None
+        } else {
+            Some(bearing)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::geodetic_to_ecef;
+    use gnss_rtk::prelude::Duration;
+
+    fn fix(t: Epoch, lat: f64, lon: f64) -> Pvt {
+        Pvt::fixture(t, geodetic_to_ecef(lat, lon, 50.0))
+    }
+
+    #[test]
+    fn bearings() {
+        // due north: 0.001° of arc
+        let (distance, bearing) = distance_bearing(45.0, 5.0, 45.001, 5.0);
+        assert!((distance - 111.195).abs() < 1.0E-3, "{}", distance);
+        assert!(bearing.abs() < 1.0E-9);
+
+        // due east on the equator, then due west and south
+        let (_, bearing) = distance_bearing(0.0, 5.0, 0.0, 5.001);
+        assert!((bearing - 90.0).abs() < 1.0E-9);
+        let (_, bearing) = distance_bearing(0.0, 5.0, 0.0, 4.999);
+        assert!((bearing - 270.0).abs() < 1.0E-9);
+        let (_, bearing) = distance_bearing(45.0, 5.0, 44.999, 5.0);
+        assert!((bearing - 180.0).abs() < 1.0E-9);
+    }
+
+    #[test]
+    fn course_from_consecutive_fixes() {
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let t1 = t0 + Duration::from_seconds(10.0);
+        let mut course = Course::default();
+
+        let mut pvt = fix(t0, 45.0, 5.0);
+        course.update(&mut pvt);
+        assert_eq!(pvt.heading, None);
+
+        // about 111 m north in 10 s
+        let mut pvt = fix(t1, 45.001, 5.0);
+        course.update(&mut pvt);
+        assert!((pvt.speed - 11.1195).abs() < 1.0E-3, "{}", pvt.speed);
+        let heading = pvt.heading.unwrap();
+        assert!(heading < 1.0E-6 || heading > 360.0 - 1.0E-6, "{}", heading);
+
+        // 1 m in 10 s: heading is noise
+        let mut pvt = fix(t1 + Duration::from_seconds(10.0), 45.001_009, 5.0);
+        course.update(&mut pvt);
+        assert!(pvt.speed < MIN_HEADING_SPEED);
+        assert_eq!(pvt.heading, None);
+    }
+}
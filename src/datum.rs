@@ -0,0 +1,181 @@
+//! Regional datum transformations (14 parameter Helmert), applied to the fix prior output
+use std::{fs::File, io::BufReader, path::Path, str::FromStr};
+
+use gnss_rtk::prelude::{Epoch, TimeScale};
+use serde::Deserialize;
+
+use crate::Error;
+
+/// Milliarcseconds to radians
+const MAS: f64 = std::f64::consts::PI / 180.0 / 3_600_000.0;
+
+/// Seconds per Julian year
+const SECONDS_PER_YEAR: f64 = 365.25 * 86_400.0;
+
+/// Helmert transformation from ITRF (which WGS84 realizations are aligned to,
+/// at the centimeter level) to a regional datum. Rotations follow the IERS convention
+/// (position vector): X' = X + T + D.X + R x X.
+/// Every parameter drifts linearly from the reference epoch, at its own rate,
+/// which includes the tectonic plate motion the regional datum is fixed to.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Helmert {
+    /// Translations (mm)
+    pub t: [f64; 3],
+    /// Scale (ppb)
+    pub d: f64,
+    /// Rotations (mas)
+    pub r: [f64; 3],
+    /// Translation rates (mm/y)
+    #[serde(default)]
+    pub t_rate: [f64; 3],
+    /// Scale rate (ppb/y)
+    #[serde(default)]
+    pub d_rate: f64,
+    /// Rotation rates (mas/y)
+    #[serde(default)]
+    pub r_rate: [f64; 3],
+    /// Reference epoch (decimal year)
+    pub epoch: f64,
+}
+
+impl Helmert {
+    /// Loads custom parameters from JSON file
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let fd = File::open(path)
+            .map_err(|e| Error::InvalidConfig(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_reader(BufReader::new(fd))
+            .map_err(|e| Error::InvalidConfig(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Transforms ECEF coordinates (m) at `t`
+    pub fn transform(&self, t: Epoch, position: (f64, f64, f64)) -> (f64, f64, f64) {
+        let dt = decimal_year(t) - self.epoch;
+        let tr = |i: usize| (self.t[i] + self.t_rate[i] * dt) * 1.0E-3;
+        let rot = |i: usize| (self.r[i] + self.r_rate[i] * dt) * MAS;
+        let d = (self.d + self.d_rate * dt) * 1.0E-9;
+        let (r1, r2, r3) = (rot(0), rot(1), rot(2));
+
+        let (x, y, z) = position;
+        (
+            x + tr(0) + d * x - r3 * y + r2 * z,
+            y + tr(1) + r3 * x + d * y - r1 * z,
+            z + tr(2) - r2 * x + r1 * y + d * z,
+        )
+    }
+}
+
+/// Returns `t` as a decimal year
+fn decimal_year(t: Epoch) -> f64 {
+    let j2000 = Epoch::from_gregorian_at_noon(2000, 1, 1, TimeScale::UTC);
+    2000.0 + (t - j2000).to_seconds() / SECONDS_PER_YEAR
+}
+
+/// Built-in regional datums
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Datum {
+    /// ETRS89, realized as ETRF2000 (from ITRF2014, EUREF TN)
+    Etrs89,
+    /// NAD83(2011) (from ITRF2008, Pearson & Snay 2013)
+    Nad83,
+    /// GDA2020 (from ITRF2014, Australian plate motion model)
+    Gda2020,
+}
+
+impl FromStr for Datum {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "etrs89" | "etrf2000" => Ok(Self::Etrs89),
+            "nad83" | "nad83(2011)" => Ok(Self::Nad83),
+            "gda2020" => Ok(Self::Gda2020),
+            _ => Err(Error::InvalidDatum(s.to_string())),
+        }
+    }
+}
+
+impl Datum {
+    /// Transformation parameters
+    pub fn helmert(&self) -> Helmert {
+        match self {
+            Self::Etrs89 => Helmert {
+                t: [54.7, 52.2, -74.1],
+                d: 2.12,
+                r: [1.701, 10.290, -16.632],
+                t_rate: [0.1, 0.1, -1.9],
+                d_rate: 0.11,
+                r_rate: [0.081, 0.490, -0.792],
+                epoch: 2010.0,
+            },
+            // published in the coordinate frame convention: rotations are negated
+            Self::Nad83 => Helmert {
+                t: [993.43, -1903.31, -526.55],
+                d: 1.71504,
+                r: [-25.91467, -9.42645, -11.59935],
+                t_rate: [0.79, -0.60, -1.34],
+                d_rate: -0.10201,
+                r_rate: [-0.06667, 0.75744, 0.05133],
+                epoch: 1997.0,
+            },
+            // plate motion only, published in the coordinate frame convention
+            Self::Gda2020 => Helmert {
+                t: [0.0; 3],
+                d: 0.0,
+                r: [0.0; 3],
+                t_rate: [0.0; 3],
+                d_rate: 0.0,
+                r_rate: [-1.50379, -1.18346, -1.20716],
+                epoch: 2020.0,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Duration;
+
+    /// Epoch of this decimal year
+    fn year(year: f64) -> Epoch {
+        let j2000 = Epoch::from_gregorian_at_noon(2000, 1, 1, TimeScale::UTC);
+        j2000 + Duration::from_days((year - 2000.0) * 365.25)
+    }
+
+    #[test]
+    fn itrf_to_etrs89() {
+        // Wettzell (WTZR), ITRF2014
+        let itrf = (4_075_580.384, 931_853.941, 4_801_568.231);
+        let helmert = Datum::from_str("ETRS89").unwrap().helmert();
+        for (t, expected) in [
+            // reference epoch: rates do not contribute
+            (year(2010.0), (4_075_580.7620, 931_853.6269, 4_801_567.9714)),
+            // the frame drifted with the Eurasian plate
+            (year(2024.0), (4_075_580.9795, 931_853.3843, 4_801_567.8218)),
+        ] {
+            let (x, y, z) = helmert.transform(t, itrf);
+            assert!((x - expected.0).abs() < 1.0E-3, "x={:.4}", x);
+            assert!((y - expected.1).abs() < 1.0E-3, "y={:.4}", y);
+            assert!((z - expected.2).abs() < 1.0E-3, "z={:.4}", z);
+        }
+    }
+
+    #[test]
+    fn custom_parameters() {
+        let path = std::env::temp_dir().join("rt-navi-helmert.json");
+        std::fs::write(
+            &path,
+            r#"{"t": [1000.0, -2000.0, 500.0], "d": 0.0, "r": [0.0, 0.0, 0.0], "epoch": 2020.0}"#,
+        )
+        .unwrap();
+        let helmert = Helmert::from_file(&path).unwrap();
+        assert_eq!(helmert.t_rate, [0.0; 3]);
+
+        // translation only, whatever the epoch
+        let (x, y, z) = helmert.transform(year(2024.0), (4_075_580.0, 931_853.0, 4_801_568.0));
+        assert!((x - 4_075_581.0).abs() < 1.0E-9);
+        assert!((y - 931_851.0).abs() < 1.0E-9);
+        assert!((z - 4_801_568.5).abs() < 1.0E-9);
+
+        assert!(Datum::from_str("ed50").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Secondary receivers, running alongside the navigating one
+use std::collections::BTreeMap;
+
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::{
+    pvt::Pvt,
+    source::{Message, ReceiverPosition},
+};
+
+/// Identifies the receiver a [Message] comes from: 0 is the navigating receiver,
+/// secondary receivers follow, in command line order
+pub type DeviceId = usize;
+
+/// Tags every [Message] of one receiver with its [DeviceId], and merges it into `tx`
+pub async fn forward(id: DeviceId, mut rx: Receiver<Message>, tx: Sender<(DeviceId, Message)>) {
+    while let Some(msg) = rx.recv().await {
+        if tx.send((id, msg)).await.is_err() {
+            return;
+        }
+    }
+    info!("receiver #{} terminated", id);
+}
+
+/// Latest fix of each secondary receiver, displayed next to the navigation solution.
+/// Secondary receivers only contribute their own fix (NAV-PVT): their measurements
+/// are not resolved.
+#[derive(Debug, Default)]
+pub struct Devices {
+    fixes: BTreeMap<DeviceId, Option<ReceiverPosition>>,
+}
+
+impl Devices {
+    /// Handles a [Message] of a secondary receiver
+    pub fn on_message(&mut self, id: DeviceId, msg: Message) {
+        match msg {
+            Message::ReceiverFix(fix) => {
+                if fix.is_none() && self.fixes.get(&id).copied().flatten().is_some() {
+                    warn!("receiver #{} lost its fix", id);
+                }
+                self.fixes.insert(id, fix);
+            },
+            Message::Antenna(state) => info!("receiver #{} antenna: {:?}", id, state),
+            _ => {},
+        }
+    }
+
+    /// Reports the secondary fixes next to this solution,
+    /// with their horizontal and vertical offsets to it
+    pub fn report(&self, pvt: &Pvt) {
+        for (id, fix) in self.fixes.iter() {
+            match fix {
+                Some(fix) => {
+                    let (horizontal, vertical) = pvt.offset_to(fix.lat, fix.lon, fix.alt);
+                    info!(
+                        "{} receiver #{} lat={:.8} lon={:.8} alt={:.3}m offset: horizontal={:.3}m vertical={:.3}m",
+                        pvt.epoch, id, fix.lat, fix.lon, fix.alt, horizontal, vertical
+                    );
+                },
+                None => info!("{} receiver #{}: no fix", pvt.epoch, id),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    fn fix(lat: f64) -> Message {
+        Message::ReceiverFix(Some(ReceiverPosition {
+            lat,
+            lon: 5.0,
+            alt: 100.0,
+            h_acc: 1.0,
+            v_acc: 2.0,
+        }))
+    }
+
+    #[tokio::test]
+    async fn two_tagged_streams() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (first_tx, first_rx) = mpsc::channel(4);
+        let (second_tx, second_rx) = mpsc::channel(4);
+        let first = tokio::spawn(forward(1, first_rx, tx.clone()));
+        let second = tokio::spawn(forward(2, second_rx, tx));
+
+        first_tx.send(fix(45.0)).await.unwrap();
+        second_tx.send(fix(46.0)).await.unwrap();
+        first_tx.send(Message::ReceiverFix(None)).await.unwrap();
+        second_tx.send(fix(46.5)).await.unwrap();
+        drop(first_tx);
+        drop(second_tx);
+        first.await.unwrap();
+        second.await.unwrap();
+
+        let mut devices = Devices::default();
+        let mut tagged = Vec::new();
+        while let Some((id, msg)) = rx.recv().await {
+            if let Message::ReceiverFix(fix) = &msg {
+                tagged.push((id, fix.map(|fix| fix.lat)));
+            }
+            devices.on_message(id, msg);
+        }
+
+        // each stream is tagged, and keeps its own order
+        let of = |device| {
+            tagged
+                .iter()
+                .filter(|(id, _)| *id == device)
+                .map(|(_, lat)| *lat)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tagged.len(), 4);
+        assert_eq!(of(1), vec![Some(45.0), None]);
+        assert_eq!(of(2), vec![Some(46.0), Some(46.5)]);
+
+        // latest fix of each receiver, side by side
+        let latest = devices
+            .fixes
+            .iter()
+            .map(|(id, fix)| (*id, fix.map(|fix| fix.lat)))
+            .collect::<Vec<_>>();
+        assert_eq!(latest, vec![(1, None), (2, Some(46.5))]);
+    }
+}
@@ -0,0 +1,246 @@
+//! Vehicle dynamics (UBX-ESF-INS), not supported by the ublox crate yet,
+//! and the dead reckoning that bridges GNSS outages with them
+use crate::{
+    coords::{ecef_to_geodetic, geodetic_to_ecef},
+    pvt::Pvt,
+};
+
+/// UBX-ESF-INS class
+pub const CLASS: u8 = 0x10;
+
+/// UBX-ESF-INS ID
+pub const ID: u8 = 0x15;
+
+/// Payload length
+const PAYLOAD_LEN: usize = 36;
+
+/// Milliseconds per GPS week
+const WEEK_MS: i64 = 604_800_000;
+
+/// Dead reckoning starts once no fix was obtained for this long (ms)
+const GAP_MS: i64 = 1_500;
+
+/// Dead reckoning stops after this long without fix (ms): the drift is then too large
+const MAX_BRIDGE_MS: i64 = 30_000;
+
+/// Longest interval between two samples (ms) that is integrated
+const MAX_STEP_MS: i64 = 1_000;
+
+/// Compensated vehicle dynamics, in the vehicle frame (x forward, y right, z down)
+#[derive(Debug, Clone, Copy)]
+pub struct EsfIns {
+    /// GPS time of week (ms)
+    pub itow: u32,
+    /// Angular rate around z (deg/s): positive when turning right
+    pub yaw_rate: f64,
+    /// Acceleration along x (m/s²), gravity free
+    pub accel_x: f64,
+}
+
+fn i32_at(payload: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes([
+        payload[offset],
+        payload[offset + 1],
+        payload[offset + 2],
+        payload[offset + 3],
+    ])
+}
+
+impl EsfIns {
+    /// Decodes payload. Returns None unless both the yaw rate and
+    /// the forward acceleration are valid.
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        if payload.len() != PAYLOAD_LEN {
+            return None;
+        }
+        let bitfield = i32_at(payload, 0) as u32;
+        // zAngRateValid, xAccelValid
+        if bitfield & 0x400 == 0 || bitfield & 0x800 == 0 {
+            return None;
+        }
+        Some(Self {
+            itow: i32_at(payload, 8) as u32,
+            yaw_rate: i32_at(payload, 20) as f64 * 1.0E-3,
+            accel_x: i32_at(payload, 24) as f64 * 1.0E-2,
+        })
+    }
+}
+
+/// Milliseconds from `from` to `to` time of week, across week rollovers
+fn elapsed_ms(from: u32, to: u32) -> i64 {
+    (to as i64 - from as i64).rem_euclid(WEEK_MS)
+}
+
+/// Latest fix, dead reckoning starts from
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    /// GPS time of week (ms)
+    itow: u32,
+    /// Latitude (ddeg), longitude (ddeg) and altitude (m)
+    geodetic: (f64, f64, f64),
+}
+
+/// Loose coupling of the GNSS fixes with the vehicle dynamics: while fixes go on,
+/// speed and heading are taken from them. When they stop, the last fix is extrapolated
+/// by integrating the yaw rate and the forward acceleration, in the local horizontal plane.
+#[derive(Debug, Default)]
+pub struct DeadReckoning {
+    anchor: Option<Anchor>,
+    /// Latest sample time of week (ms)
+    itow: u32,
+    /// East and north displacement since the anchor (m)
+    east: f64,
+    north: f64,
+    /// Speed (m/s)
+    speed: f64,
+    /// Heading (degrees, clockwise from north)
+    heading: f64,
+    /// Dead reckoning is bridging an outage
+    bridging: bool,
+}
+
+impl DeadReckoning {
+    /// New GNSS fix: the dead reckoning restarts from it
+    pub fn on_fix(&mut self, pvt: &Pvt) {
+        let (_, tow_ns) = pvt.epoch.to_time_of_week();
+        let itow = (tow_ns / 1_000_000) as u32;
+        if self.bridging {
+            if let Some(anchor) = self.anchor {
+                info!(
+                    "dead reckoning: fix is back after {:.1}s",
+                    elapsed_ms(anchor.itow, itow) as f64 * 1.0E-3
+                );
+            }
+            self.bridging = false;
+        }
+        self.anchor = Some(Anchor {
+            itow,
+            geodetic: pvt.geodetic,
+        });
+        self.itow = itow;
+        self.east = 0.0;
+        self.north = 0.0;
+        self.speed = pvt.speed;
+        // heading is not reported when standing still: preserve the previous one
+        if let Some(heading) = pvt.heading {
+            self.heading = heading;
+        }
+    }
+
+    /// New vehicle dynamics: returns the dead reckoning position
+    /// (latitude, longitude in ddeg, altitude in m), while bridging an outage
+    pub fn on_ins(&mut self, ins: &EsfIns) -> Option<(f64, f64, f64)> {
+        let anchor = self.anchor?;
+
+        let step = elapsed_ms(self.itow, ins.itow);
+        self.itow = ins.itow;
+        if step > 0 && step <= MAX_STEP_MS {
+            let dt = step as f64 * 1.0E-3;
+            self.heading = (self.heading + ins.yaw_rate * dt).rem_euclid(360.0);
+            self.speed = (self.speed + ins.accel_x * dt).max(0.0);
+            let heading = self.heading.to_radians();
+            self.east += self.speed * heading.sin() * dt;
+            self.north += self.speed * heading.cos() * dt;
+        }
+
+        let gap = elapsed_ms(anchor.itow, ins.itow);
+        if gap < GAP_MS {
+            return None;
+        }
+        if gap > MAX_BRIDGE_MS {
+            if self.bridging {
+                warn!(
+                    "dead reckoning: no fix for {}s, giving up",
+                    MAX_BRIDGE_MS / 1000
+                );
+                self.bridging = false;
+                self.anchor = None;
+            }
+            return None;
+        }
+        if !self.bridging {
+            info!("dead reckoning: no fix, bridging the outage");
+            self.bridging = true;
+        }
+
+        // local horizontal displacement, back to ECEF
+        let (lat, lon, alt) = anchor.geodetic;
+        let (x0, y0, z0) = geodetic_to_ecef(lat, lon, alt);
+        let (phi, lambda) = (lat.to_radians(), lon.to_radians());
+        let x = x0 - lambda.sin() * self.east - phi.sin() * lambda.cos() * self.north;
+        let y = y0 + lambda.cos() * self.east - phi.sin() * lambda.sin() * self.north;
+        let z = z0 + phi.cos() * self.north;
+        Some(ecef_to_geodetic(x, y, z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pvt::EnuOrigin;
+    use gnss_rtk::prelude::{Epoch, TimeScale};
+
+    /// ESF-INS payload: yaw rate (deg/s) and forward acceleration (m/s²)
+    fn payload(itow: u32, yaw_rate: f64, accel_x: f64) -> Vec<u8> {
+        let mut payload = vec![0; PAYLOAD_LEN];
+        payload[0..4].copy_from_slice(&0xC00_u32.to_le_bytes());
+        payload[8..12].copy_from_slice(&itow.to_le_bytes());
+        payload[20..24].copy_from_slice(&((yaw_rate * 1.0E3) as i32).to_le_bytes());
+        payload[24..28].copy_from_slice(&((accel_x * 1.0E2) as i32).to_le_bytes());
+        payload
+    }
+
+    #[test]
+    fn decode_ins() {
+        let ins = EsfIns::decode(&payload(100_000, -2.5, 0.25)).unwrap();
+        assert_eq!(ins.itow, 100_000);
+        assert!((ins.yaw_rate + 2.5).abs() < 1.0E-9);
+        assert!((ins.accel_x - 0.25).abs() < 1.0E-9);
+
+        // yaw rate not valid
+        let mut invalid = payload(100_000, -2.5, 0.25);
+        invalid[1] = 0x08;
+        assert!(EsfIns::decode(&invalid).is_none());
+        assert!(EsfIns::decode(&invalid[..32]).is_none());
+    }
+
+    #[test]
+    fn outage_bridged() {
+        // heading east at 10 m/s, last fix at tow 100s
+        let t = Epoch::from_time_of_week(2304, 100_000_000_000, TimeScale::GPST);
+        let pvt = Pvt {
+            speed: 10.0,
+            heading: Some(90.0),
+            ..Pvt::fixture(t, geodetic_to_ecef(45.0, 5.0, 100.0))
+        };
+        let mut dead_reckoning = DeadReckoning::default();
+        dead_reckoning.on_fix(&pvt);
+
+        // 10 Hz dynamics, steady
+        let mut bridged = Vec::new();
+        for i in 1..=20 {
+            let ins = EsfIns::decode(&payload(100_000 + i * 100, 0.0, 0.0)).unwrap();
+            bridged.push(dead_reckoning.on_ins(&ins));
+        }
+        // not before the gap
+        assert!(bridged[..14].iter().all(|position| position.is_none()));
+
+        // 2s later: 20m east
+        let (lat, lon, alt) = bridged[19].unwrap();
+        let origin = EnuOrigin {
+            lat: 45.0,
+            lon: 5.0,
+            alt: 100.0,
+        };
+        let (x, y, z) = geodetic_to_ecef(lat, lon, alt);
+        let (east, north, up) = origin.enu(x, y, z);
+        assert!((east - 20.0).abs() < 1.0E-3, "east={}", east);
+        assert!(north.abs() < 1.0E-3, "north={}", north);
+        // the plane is tangent to the ellipsoid
+        assert!(up.abs() < 1.0E-3, "up={}", up);
+
+        // gave up after too long an outage
+        let ins = EsfIns::decode(&payload(131_000, 0.0, 0.0)).unwrap();
+        assert!(dead_reckoning.on_ins(&ins).is_none());
+    }
+}
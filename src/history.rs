@@ -0,0 +1,179 @@
+//! Recent epochs, kept for look-back analysis
+use std::collections::VecDeque;
+
+use gnss_rtk::prelude::{Candidate, Epoch, SV};
+
+use crate::pvt::Pvt;
+
+/// One past epoch: proposed candidates and resulting solution, if any
+#[derive(Debug, Clone)]
+struct Entry {
+    epoch: Epoch,
+    candidates: Vec<Candidate>,
+    pvt: Option<Pvt>,
+}
+
+impl Entry {
+    fn sv(&self) -> Vec<SV> {
+        self.candidates.iter().map(|cd| cd.sv).collect()
+    }
+}
+
+/// Bounded history of the last epochs, browsed at runtime
+#[derive(Debug)]
+pub struct History {
+    /// Maximal number of epochs
+    capacity: usize,
+    /// Oldest epoch first
+    entries: VecDeque<Entry>,
+    /// Browsed entry, when browsing
+    cursor: Option<usize>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            cursor: None,
+        }
+    }
+
+    /// Stores new epoch, dropping the oldest one when full
+    pub fn push(&mut self, epoch: Epoch, candidates: &[Candidate], pvt: Option<&Pvt>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+            // keep browsing the same entry
+            self.cursor = self.cursor.and_then(|cursor| cursor.checked_sub(1));
+        }
+        self.entries.push_back(Entry {
+            epoch,
+            candidates: candidates.to_vec(),
+            pvt: pvt.cloned(),
+        });
+    }
+
+    /// Discards all epochs
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cursor = None;
+    }
+
+    /// Browses one epoch back, starting from the latest one
+    pub fn older(&mut self) {
+        let cursor = match self.cursor {
+            Some(cursor) => cursor.saturating_sub(1),
+            None => match self.entries.len().checked_sub(1) {
+                Some(latest) => latest,
+                None => {
+                    info!("history is empty");
+                    return;
+                },
+            },
+        };
+        self.cursor = Some(cursor);
+        self.show(cursor);
+    }
+
+    /// Browses one epoch forward, until the latest one
+    pub fn newer(&mut self) {
+        let cursor = match self.cursor {
+            Some(cursor) if cursor + 1 < self.entries.len() => cursor + 1,
+            _ => {
+                info!("history: latest epoch reached");
+                self.cursor = None;
+                return;
+            },
+        };
+        self.cursor = Some(cursor);
+        self.show(cursor);
+    }
+
+    /// Logs entry, with what changed since the previous one
+    fn show(&self, index: usize) {
+        let entry = &self.entries[index];
+        let sv = entry.sv();
+        info!(
+            "history [{}/{}] {}: {} candidates {:?}",
+            index + 1,
+            self.entries.len(),
+            entry.epoch,
+            sv.len(),
+            sv
+        );
+        if let Some(previous) = index.checked_sub(1).map(|i| &self.entries[i]) {
+            let prev_sv = previous.sv();
+            let gained = sv
+                .iter()
+                .filter(|s| !prev_sv.contains(s))
+                .collect::<Vec<_>>();
+            let lost = prev_sv
+                .iter()
+                .filter(|s| !sv.contains(s))
+                .collect::<Vec<_>>();
+            info!("history: gained {:?}, lost {:?}", gained, lost);
+            if let (Some(pvt), Some(prev_pvt)) = (&entry.pvt, &previous.pvt) {
+                let (lat, lon, alt) = prev_pvt.geodetic;
+                let (horizontal, vertical) = pvt.offset_to(lat, lon, alt);
+                info!(
+                    "history: moved horizontal={:.3}m vertical={:.3}m",
+                    horizontal, vertical
+                );
+            }
+        }
+        match &entry.pvt {
+            Some(pvt) => info!("history: {}", pvt),
+            None => info!("history: no solution"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Duration;
+
+    fn epochs(history: &History) -> Vec<Epoch> {
+        history.entries.iter().map(|entry| entry.epoch).collect()
+    }
+
+    #[test]
+    fn retains_last_epochs() {
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let t = |i: u32| t0 + Duration::from_seconds(i as f64);
+        let mut history = History::new(3);
+
+        for i in 0..5 {
+            let pvt = Pvt::fixture(t(i), (4_201_575.0, 189_856.0, 4_779_066.0));
+            history.push(t(i), &[], (i % 2 == 0).then_some(&pvt));
+        }
+        assert_eq!(epochs(&history), [t(2), t(3), t(4)]);
+        assert!(history.entries[0].pvt.is_some());
+        assert!(history.entries[1].pvt.is_none());
+
+        // browsing the latest epoch, which remains browsed when the history rolls
+        history.older();
+        assert_eq!(history.cursor, Some(2));
+        history.push(t(5), &[], None);
+        assert_eq!(epochs(&history), [t(3), t(4), t(5)]);
+        assert_eq!(history.cursor, Some(1));
+        history.newer();
+        history.newer();
+        assert_eq!(history.cursor, None);
+
+        history.clear();
+        assert!(history.entries.is_empty());
+    }
+
+    #[test]
+    fn disabled_history() {
+        let mut history = History::new(0);
+        history.push(Epoch::from_gregorian_utc_at_midnight(2024, 3, 1), &[], None);
+        assert!(history.entries.is_empty());
+        history.older();
+        assert_eq!(history.cursor, None);
+    }
+}
@@ -0,0 +1,119 @@
+//! Stationary hold: static navigation and averaging, toggled at runtime
+use gnss_rtk::prelude::Config;
+
+use crate::{coords::ecef_to_geodetic, pvt::Pvt, Error};
+
+/// Static solver profile, while holding
+fn static_profile(cfg: &mut Config) -> Result<(), Error> {
+    // solver profiles are not exposed by gnss-rtk, other than through serde
+    cfg.profile =
+        serde_json::from_value("Static".into()).map_err(|e| Error::InvalidConfig(e.to_string()))?;
+    Ok(())
+}
+
+/// While the user holds the receiver still, the solver navigates with the static profile
+/// and solutions are averaged. Releasing restores the previous profile.
+#[derive(Debug, Default)]
+pub struct Hold {
+    /// Solver configuration prior holding, while held
+    kinematic: Option<Config>,
+    count: usize,
+    sum: (f64, f64, f64),
+}
+
+impl Hold {
+    /// True while held
+    pub fn is_held(&self) -> bool {
+        self.kinematic.is_some()
+    }
+
+    /// Number of solutions averaged, while held
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Holds, or releases, switching the profile of this solver [Config]
+    pub fn toggle(&mut self, cfg: &mut Config) {
+        match self.kinematic.take() {
+            Some(kinematic) => {
+                cfg.profile = kinematic.profile;
+                self.report();
+                info!("hold released: back to kinematic navigation");
+            },
+            None => {
+                let kinematic = cfg.clone();
+                match static_profile(cfg) {
+                    Ok(_) => {
+                        self.kinematic = Some(kinematic);
+                        self.count = 0;
+                        self.sum = (0.0, 0.0, 0.0);
+                        info!("hold: static navigation, averaging solutions");
+                    },
+                    Err(e) => error!("failed to hold: {}", e),
+                }
+            },
+        }
+    }
+
+    /// Averages new solution, while held
+    pub fn on_fix(&mut self, pvt: &Pvt) {
+        if !self.is_held() {
+            return;
+        }
+        let (x, y, z) = pvt.position;
+        self.count += 1;
+        self.sum = (self.sum.0 + x, self.sum.1 + y, self.sum.2 + z);
+    }
+
+    /// Reports averaged position
+    fn report(&self) {
+        if self.count == 0 {
+            warn!("hold: no solution collected");
+            return;
+        }
+        let n = self.count as f64;
+        let (lat, lon, alt) = ecef_to_geodetic(self.sum.0 / n, self.sum.1 / n, self.sum.2 / n);
+        info!(
+            "hold: {} solutions averaged lat={:.8} lon={:.8} alt={:.3}m",
+            self.count, lat, lon, alt
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Epoch, Method};
+
+    fn profile(cfg: &Config) -> serde_json::Value {
+        serde_json::to_value(&cfg.profile).unwrap()
+    }
+
+    #[test]
+    fn hold_switches_to_static() {
+        let mut cfg = Config::static_preset(Method::SPP);
+        cfg.profile = serde_json::from_value("Pedestrian".into()).unwrap();
+        let mut hold = Hold::default();
+
+        hold.toggle(&mut cfg);
+        assert!(hold.is_held());
+        assert_eq!(profile(&cfg), "Static");
+
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        for _ in 0..3 {
+            hold.on_fix(&Pvt::fixture(t, (4_201_575.0, 189_856.0, 4_779_066.0)));
+        }
+        assert_eq!(hold.count(), 3);
+        // other settings changed while holding are preserved on release
+        cfg.fixed_altitude = Some(51.2);
+
+        hold.toggle(&mut cfg);
+        assert!(!hold.is_held());
+        assert_eq!(profile(&cfg), "Pedestrian");
+        assert_eq!(cfg.fixed_altitude, Some(51.2));
+
+        // not averaged while released
+        hold.on_fix(&Pvt::fixture(t, (0.0, 0.0, 0.0)));
+        assert_eq!(hold.count(), 3);
+    }
+}
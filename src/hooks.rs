@@ -0,0 +1,66 @@
+//! User hooks, to tap into the processing pipeline
+use gnss_rtk::prelude::{Candidate, Epoch};
+
+/// Invoked each epoch prior resolution: may inspect or modify the candidates
+pub type CandidatesHook = Box<dyn FnMut(Epoch, &mut Vec<Candidate>)>;
+
+/// Registered hooks. Costs nothing when none is registered.
+#[derive(Default)]
+pub struct Hooks {
+    candidates: Vec<CandidatesHook>,
+}
+
+impl Hooks {
+    /// Registers new [CandidatesHook]
+    pub fn on_candidates(&mut self, hook: CandidatesHook) {
+        self.candidates.push(hook);
+    }
+
+    /// Runs all [CandidatesHook]s, in registration order
+    pub fn candidates(&mut self, t: Epoch, candidates: &mut Vec<Candidate>) {
+        for hook in self.candidates.iter_mut() {
+            hook(t, candidates);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Carrier, Constellation, Duration, PseudoRange, SV};
+    use std::{cell::RefCell, rc::Rc};
+
+    fn candidate(t: Epoch, prn: u8) -> Candidate {
+        let pr = PseudoRange {
+            carrier: Carrier::L1,
+            value: 2.1E7 + prn as f64,
+            snr: None,
+        };
+        let sv = SV::new(Constellation::GPS, prn);
+        Candidate::new(sv, t, Duration::default(), None, vec![pr], vec![])
+    }
+
+    #[test]
+    fn hooks_see_candidates_in_order() {
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut hooks = Hooks::default();
+
+        // first hook drops G05, the second one sees the outcome
+        hooks.on_candidates(Box::new(|_, candidates: &mut Vec<Candidate>| {
+            candidates.retain(|cd| cd.sv.prn != 5)
+        }));
+        let observer = seen.clone();
+        hooks.on_candidates(Box::new(
+            move |t: Epoch, candidates: &mut Vec<Candidate>| {
+                let prn = candidates.iter().map(|cd| cd.sv.prn).collect::<Vec<_>>();
+                observer.borrow_mut().push((t, prn));
+            },
+        ));
+
+        let mut candidates = vec![candidate(t, 2), candidate(t, 5), candidate(t, 13)];
+        hooks.candidates(t, &mut candidates);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(*seen.borrow(), [(t, vec![2, 13])]);
+    }
+}
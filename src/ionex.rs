@@ -0,0 +1,359 @@
+//! Global ionosphere maps, loaded from IONEX files
+use std::{
+    f64::consts::PI,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use gnss_rtk::prelude::{Epoch, TimeScale};
+
+use crate::Error;
+
+/// Missing TEC value
+const NO_VALUE: i32 = 9999;
+
+/// Ionosphere delay (m) per TECU, at 1 Hz: 40.3E16 / f²
+const DELAY_PER_TECU: f64 = 40.3E16;
+
+/// Regular grid: first value, spacing and number of points
+#[derive(Debug, Default, Clone, Copy)]
+struct Axis {
+    first: f64,
+    step: f64,
+    len: usize,
+}
+
+impl Axis {
+    /// Parses "FIRST LAST STEP" header values
+    fn parse(line: &str) -> Option<Self> {
+        let mut items = line.get(..60)?.split_ascii_whitespace();
+        let first = items.next()?.parse::<f64>().ok()?;
+        let last = items.next()?.parse::<f64>().ok()?;
+        let step = items.next()?.parse::<f64>().ok()?;
+        if step == 0.0 {
+            return None;
+        }
+        Some(Self {
+            first,
+            step,
+            len: ((last - first) / step).round() as usize + 1,
+        })
+    }
+
+    /// Returns lower grid index and interpolation ratio, for `value` within the grid
+    fn locate(&self, value: f64) -> Option<(usize, f64)> {
+        let position = (value - self.first) / self.step;
+        if self.len < 2 || position < 0.0 || position > (self.len - 1) as f64 {
+            return None;
+        }
+        let index = (position.floor() as usize).min(self.len.saturating_sub(2));
+        Some((index, position - index as f64))
+    }
+}
+
+/// Parses "EPOCH OF ... MAP" values
+fn parse_epoch(line: &str) -> Option<Epoch> {
+    let mut items = line.get(..60)?.split_ascii_whitespace();
+    let y = items.next()?.parse::<i32>().ok()?;
+    let mut next = || items.next()?.parse::<u8>().ok();
+    let (m, d, hh, mm, ss) = (next()?, next()?, next()?, next()?, next()?);
+    Some(Epoch::from_gregorian(
+        y,
+        m,
+        d,
+        hh,
+        mm,
+        ss,
+        0,
+        TimeScale::UTC,
+    ))
+}
+
+/// Vertical TEC maps (TECU), single layer model
+#[derive(Debug, Default)]
+pub struct IonexMap {
+    /// Latitude grid (ddeg)
+    lat: Axis,
+    /// Longitude grid (ddeg)
+    lon: Axis,
+    /// Single layer height (km)
+    height_km: f64,
+    /// Earth radius (km)
+    radius_km: f64,
+    /// TEC maps, sorted by [Epoch], latitude major
+    maps: Vec<(Epoch, Vec<Option<f64>>)>,
+}
+
+impl IonexMap {
+    /// Loads all TEC maps from IONEX file. RMS and height maps are ignored.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let fd = File::open(path)
+            .map_err(|e| Error::InvalidIonex(format!("{}: {}", path.display(), e)))?;
+
+        let mut ionex = Self::default();
+        let mut scale = 0.1;
+        let mut header = true;
+        let mut in_tec_map = false;
+        // current map: epoch, values, and position within the latitude band being read
+        let mut epoch = None;
+        let mut values = Vec::<Option<f64>>::new();
+        let (mut band, mut column) = (0, 0);
+
+        for line in BufReader::new(fd).lines() {
+            let line =
+                line.map_err(|e| Error::InvalidIonex(format!("{}: {}", path.display(), e)))?;
+            let label = line.get(60..).unwrap_or_default().trim();
+            if header {
+                match label {
+                    "HGT1 / HGT2 / DHGT" => {
+                        ionex.height_km = line
+                            .split_ascii_whitespace()
+                            .next()
+                            .and_then(|h| h.parse::<f64>().ok())
+                            .unwrap_or_default();
+                    },
+                    "LAT1 / LAT2 / DLAT" => ionex.lat = Axis::parse(&line).unwrap_or_default(),
+                    "LON1 / LON2 / DLON" => ionex.lon = Axis::parse(&line).unwrap_or_default(),
+                    "BASE RADIUS" => {
+                        ionex.radius_km = line
+                            .split_ascii_whitespace()
+                            .next()
+                            .and_then(|r| r.parse::<f64>().ok())
+                            .unwrap_or_default();
+                    },
+                    "EXPONENT" => {
+                        if let Some(exp) = line
+                            .split_ascii_whitespace()
+                            .next()
+                            .and_then(|e| e.parse::<i32>().ok())
+                        {
+                            scale = 10.0_f64.powi(exp);
+                        }
+                    },
+                    "END OF HEADER" => header = false,
+                    _ => {},
+                }
+                continue;
+            }
+            match label {
+                "START OF TEC MAP" => {
+                    in_tec_map = true;
+                    epoch = None;
+                    values = vec![None; ionex.lat.len * ionex.lon.len];
+                },
+                "EPOCH OF CURRENT MAP" if in_tec_map => epoch = parse_epoch(&line),
+                "LAT/LON1/LON2/DLON/H" if in_tec_map => {
+                    band = line
+                        .get(2..8)
+                        .and_then(|lat| lat.trim().parse::<f64>().ok())
+                        .and_then(|lat| ionex.lat.locate(lat))
+                        .map(|(index, ratio)| index + ratio.round() as usize)
+                        .unwrap_or(ionex.lat.len);
+                    column = 0;
+                },
+                "END OF TEC MAP" if in_tec_map => {
+                    in_tec_map = false;
+                    if let Some(t) = epoch {
+                        ionex.maps.push((t, values.clone()));
+                    }
+                },
+                _ if in_tec_map && band < ionex.lat.len => {
+                    // 16 values per line, for the current latitude band
+                    for tec in line.split_ascii_whitespace() {
+                        if column < ionex.lon.len {
+                            values[band * ionex.lon.len + column] = match tec.parse::<i32>() {
+                                Ok(NO_VALUE) | Err(_) => None,
+                                Ok(tec) => Some(tec as f64 * scale),
+                            };
+                            column += 1;
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        if ionex.maps.is_empty() || ionex.lat.len < 2 || ionex.lon.len < 2 {
+            return Err(Error::InvalidIonex(format!(
+                "{}: no TEC map",
+                path.display()
+            )));
+        }
+        ionex.maps.sort_by(|a, b| a.0.cmp(&b.0));
+        info!(
+            "{}: {} TEC maps, from {} to {}",
+            path.display(),
+            ionex.maps.len(),
+            ionex.maps[0].0,
+            ionex.maps[ionex.maps.len() - 1].0,
+        );
+        Ok(ionex)
+    }
+
+    /// Interpolates vertical TEC (TECU) of one map, bilinearly
+    fn map_tec(&self, values: &[Option<f64>], lat: f64, lon: f64) -> Option<f64> {
+        // longitudes wrap around
+        let lon = if self.lon.step > 0.0 {
+            self.lon.first + (lon - self.lon.first).rem_euclid(360.0)
+        } else {
+            self.lon.first - (self.lon.first - lon).rem_euclid(360.0)
+        };
+        let (i, dlat) = self.lat.locate(lat)?;
+        let (j, dlon) = self.lon.locate(lon)?;
+        let value = |i: usize, j: usize| values[i * self.lon.len + j];
+        let (e00, e01) = (value(i, j)?, value(i, j + 1)?);
+        let (e10, e11) = (value(i + 1, j)?, value(i + 1, j + 1)?);
+        Some(
+            (1.0 - dlat) * (1.0 - dlon) * e00
+                + (1.0 - dlat) * dlon * e01
+                + dlat * (1.0 - dlon) * e10
+                + dlat * dlon * e11,
+        )
+    }
+
+    /// Interpolates vertical TEC (TECU) at `t`, latitude and longitude (ddeg):
+    /// bilinearly within each map, linearly between surrounding maps.
+    /// Returns None outside the maps' time or space coverage.
+    pub fn vertical_tec(&self, t: Epoch, lat: f64, lon: f64) -> Option<f64> {
+        let t = t.to_time_scale(TimeScale::UTC);
+        let after = self.maps.partition_point(|(ti, _)| *ti <= t);
+        let (t_a, map_a) = self.maps.get(after.checked_sub(1)?)?;
+        let tec_a = self.map_tec(map_a, lat, lon)?;
+        if *t_a == t {
+            return Some(tec_a);
+        }
+        let (t_b, map_b) = self.maps.get(after)?;
+        let tec_b = self.map_tec(map_b, lat, lon)?;
+        let ratio = (t - *t_a).to_seconds() / (*t_b - *t_a).to_seconds();
+        Some(tec_a + (tec_b - tec_a) * ratio)
+    }
+
+    /// Slant ionosphere delay (m) on `frequency` (Hz), for a receiver at `lat`, `lon` (ddeg)
+    /// observing an SV at `elevation`, `azimuth` (deg), from the vertical TEC
+    /// at the ionosphere pierce point. Returns None outside the maps' coverage.
+    pub fn slant_delay(
+        &self,
+        t: Epoch,
+        lat: f64,
+        lon: f64,
+        elevation: f64,
+        azimuth: f64,
+        frequency: f64,
+    ) -> Option<f64> {
+        let (phi, lambda) = (lat.to_radians(), lon.to_radians());
+        let (elev, azim) = (elevation.to_radians(), azimuth.to_radians());
+        let ratio = self.radius_km / (self.radius_km + self.height_km) * elev.cos();
+
+        // Earth central angle, between receiver and pierce point
+        let psi = PI / 2.0 - elev - ratio.asin();
+        let phi_ipp = (phi.sin() * psi.cos() + phi.cos() * psi.sin() * azim.cos()).asin();
+        let lambda_ipp = lambda + (psi.sin() * azim.sin() / phi_ipp.cos()).asin();
+
+        let vtec = self.vertical_tec(t, phi_ipp.to_degrees(), lambda_ipp.to_degrees())?;
+        let obliquity = 1.0 / (1.0 - ratio.powi(2)).sqrt();
+        Some(DELAY_PER_TECU * vtec * obliquity / frequency.powi(2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Duration;
+
+    /// Header or map line, labelled from column 60
+    fn line(content: &str, label: &str) -> String {
+        format!("{:<60}{}\n", content, label)
+    }
+
+    /// Two maps, 2 hours apart, over a 3x3 grid: 50°N to 40°N, 0°E to 10°E.
+    /// The second map is 2 TECU above the first.
+    fn ionex_file(name: &str) -> std::path::PathBuf {
+        let mut content = line(
+            "     1.0            IONOSPHERE MAPS     GNSS",
+            "IONEX VERSION / TYPE",
+        );
+        content.push_str(&line("  6371.0", "BASE RADIUS"));
+        content.push_str(&line("   450.0 450.0   0.0", "HGT1 / HGT2 / DHGT"));
+        content.push_str(&line("    50.0  40.0  -5.0", "LAT1 / LAT2 / DLAT"));
+        content.push_str(&line("     0.0  10.0   5.0", "LON1 / LON2 / DLON"));
+        content.push_str(&line("    -1", "EXPONENT"));
+        content.push_str(&line("", "END OF HEADER"));
+        let bands = [
+            (50.0, [100, 110, 120]),
+            (45.0, [130, 140, 150]),
+            (40.0, [160, 170, 9999]),
+        ];
+        for (i, hh) in [0, 2].iter().enumerate() {
+            content.push_str(&line(&format!("{:6}", i + 1), "START OF TEC MAP"));
+            content.push_str(&line(
+                &format!("  2024     3     1{:6}     0     0", hh),
+                "EPOCH OF CURRENT MAP",
+            ));
+            for (lat, values) in bands.iter() {
+                content.push_str(&line(
+                    &format!(
+                        "  {:6.1}{:6.1}{:6.1}{:6.1}{:6.1}",
+                        lat, 0.0, 10.0, 5.0, 450.0
+                    ),
+                    "LAT/LON1/LON2/DLON/H",
+                ));
+                let values = values
+                    .iter()
+                    .map(|tec| match tec {
+                        9999 => format!("{:5}", tec),
+                        tec => format!("{:5}", tec + 20 * i),
+                    })
+                    .collect::<String>();
+                content.push_str(&format!("{}\n", values));
+            }
+            content.push_str(&line(&format!("{:6}", i + 1), "END OF TEC MAP"));
+        }
+        content.push_str(&line("", "END OF FILE"));
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn grid_cell_interpolation() {
+        let ionex = IonexMap::from_file(&ionex_file("rt-navi-grid.ionex")).unwrap();
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let tec = |t: Epoch, lat: f64, lon: f64| ionex.vertical_tec(t, lat, lon);
+
+        // grid points, then cell center
+        assert!((tec(t0, 50.0, 0.0).unwrap() - 10.0).abs() < 1.0E-9);
+        assert!((tec(t0, 40.0, 0.0).unwrap() - 16.0).abs() < 1.0E-9);
+        assert!((tec(t0, 47.5, 2.5).unwrap() - 12.0).abs() < 1.0E-9);
+        assert!((tec(t0, 46.0, 1.0).unwrap() - 12.6).abs() < 1.0E-9);
+        // longitudes wrap around
+        assert!((tec(t0, 47.5, 362.5).unwrap() - 12.0).abs() < 1.0E-9);
+
+        // between maps
+        let t = t0 + Duration::from_seconds(3600.0);
+        assert!((tec(t, 47.5, 2.5).unwrap() - 13.0).abs() < 1.0E-9);
+
+        // cells with a missing value, outside the grid, past the last map
+        assert!(tec(t0, 42.5, 7.5).is_none());
+        assert!(tec(t0, 55.0, 2.5).is_none());
+        assert!(tec(t0 + Duration::from_seconds(3.0 * 3600.0), 47.5, 2.5).is_none());
+    }
+
+    #[test]
+    fn zenith_slant_delay() {
+        let ionex = IonexMap::from_file(&ionex_file("rt-navi-slant.ionex")).unwrap();
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+
+        // vertical: 12 TECU on L1
+        let delay = ionex
+            .slant_delay(t0, 47.5, 2.5, 90.0, 0.0, 1575.42E6)
+            .unwrap();
+        assert!((delay - 1.948_469).abs() < 1.0E-6, "{}", delay);
+
+        // looking south: pierce point at 45.34°N (13.30 TECU), 1.131 obliquity
+        let slant = ionex
+            .slant_delay(t0, 47.5, 2.5, 60.0, 180.0, 1575.42E6)
+            .unwrap();
+        assert!((slant - 2.441_440).abs() < 1.0E-5, "{}", slant);
+    }
+}
@@ -0,0 +1,179 @@
+//! Klobuchar ionosphere model, from the GPS broadcast parameters
+use std::f64::consts::PI;
+
+use gnss_rtk::prelude::{Carrier, Epoch};
+
+use crate::carrier::{carrier_frequency_hz, SPEED_OF_LIGHT_M_S};
+
+/// LNAV subframe preamble
+const PREAMBLE: u32 = 0x8B;
+
+/// Subframe 4 page 18 (ionosphere and UTC parameters) is identified by this SV ID
+const IONO_PAGE_SV_ID: u32 = 56;
+
+/// Night time vertical delay (s)
+const NIGHT_DELAY_S: f64 = 5.0E-9;
+
+/// Klobuchar model parameters, broadcast in GPS LNAV subframe 4 page 18
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Klobuchar {
+    /// Amplitude coefficients (s, s/sc, s/sc², s/sc³)
+    pub alpha: [f64; 4],
+    /// Period coefficients (s, s/sc, s/sc², s/sc³)
+    pub beta: [f64; 4],
+}
+
+impl Klobuchar {
+    /// Decodes the parameters from the 10 RXM-SFRBX words of a GPS LNAV subframe:
+    /// each word holds 24 data bits above its 6 parity bits.
+    /// Returns None for any other subframe, or page.
+    pub fn decode<I: Iterator<Item = u32>>(words: I) -> Option<Self> {
+        let data = words
+            .take(10)
+            .map(|word| (word >> 6) & 0xFF_FFFF)
+            .collect::<Vec<_>>();
+        if data.len() != 10 {
+            return None;
+        }
+        // Reads a signed byte at `pos` within the 24 bit words
+        let byte = |pos: usize| -> f64 {
+            let (word, shift) = (pos / 24, 16 - pos % 24);
+            ((data[word] >> shift) & 0xFF) as u8 as i8 as f64
+        };
+
+        // TLM preamble, HOW subframe ID, page SV ID
+        if data[0] >> 16 != PREAMBLE
+            || (data[1] >> 2) & 0x07 != 4
+            || (data[2] >> 16) & 0x3F != IONO_PAGE_SV_ID
+        {
+            return None;
+        }
+
+        Some(Self {
+            alpha: [
+                byte(56) * 2.0_f64.powi(-30),
+                byte(64) * 2.0_f64.powi(-27),
+                byte(72) * 2.0_f64.powi(-24),
+                byte(80) * 2.0_f64.powi(-24),
+            ],
+            beta: [
+                byte(88) * 2.0_f64.powi(11),
+                byte(96) * 2.0_f64.powi(14),
+                byte(104) * 2.0_f64.powi(16),
+                byte(112) * 2.0_f64.powi(16),
+            ],
+        })
+    }
+
+    /// Returns the slant ionosphere delay (m) at `t` (GPST), of a signal received
+    /// on `frequency` (Hz) at `lat`, `lon` (ddeg), from `elevation` and `azimuth` (ddeg)
+    pub fn slant_delay(
+        &self,
+        t: Epoch,
+        lat: f64,
+        lon: f64,
+        elevation: f64,
+        azimuth: f64,
+        frequency: f64,
+    ) -> f64 {
+        // semi circles
+        let elev = elevation / 180.0;
+        let azim = azimuth.to_radians();
+
+        // earth central angle, pierce point latitude and longitude
+        let psi = 0.0137 / (elev + 0.11) - 0.022;
+        let phi = (lat / 180.0 + psi * azim.cos()).clamp(-0.416, 0.416);
+        let lambda = lon / 180.0 + psi * azim.sin() / (phi * PI).cos();
+
+        // geomagnetic latitude, local time (s)
+        let phi_m = phi + 0.064 * ((lambda - 1.617) * PI).cos();
+        let (_, tow_ns) = t.to_time_of_week();
+        let local = (43_200.0 * lambda + tow_ns as f64 * 1.0E-9).rem_euclid(86_400.0);
+
+        let obliquity = 1.0 + 16.0 * (0.53 - elev).powi(3);
+        let poly = |c: &[f64; 4]| c[0] + phi_m * (c[1] + phi_m * (c[2] + phi_m * c[3]));
+        let amplitude = poly(&self.alpha).max(0.0);
+        let period = poly(&self.beta).max(72_000.0);
+
+        let x = 2.0 * PI * (local - 50_400.0) / period;
+        let delay = if x.abs() < 1.57 {
+            NIGHT_DELAY_S + amplitude * (1.0 - x.powi(2) / 2.0 + x.powi(4) / 24.0)
+        } else {
+            NIGHT_DELAY_S
+        };
+
+        // model applies to L1
+        let scaling = (carrier_frequency_hz(Carrier::L1) / frequency).powi(2);
+        SPEED_OF_LIGHT_M_S * obliquity * delay * scaling
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::TimeScale;
+
+    /// GPS LNAV subframe 4, page 18, as 10 RXM-SFRBX words
+    fn subframe(alpha: [i8; 4], beta: [i8; 4]) -> Vec<u32> {
+        let mut data = [0_u32; 10];
+        data[0] = PREAMBLE << 16;
+        data[1] = 4 << 2;
+        let bytes = [alpha, beta].concat();
+        data[2] = (0x01 << 22) | (IONO_PAGE_SV_ID << 16);
+        for (i, byte) in bytes.iter().enumerate() {
+            let pos = 56 + i * 8;
+            data[pos / 24] |= (*byte as u8 as u32) << (16 - pos % 24);
+        }
+        data.iter().map(|data| data << 6).collect()
+    }
+
+    #[test]
+    fn decode_iono_page() {
+        let words = subframe([12, 8, -60, -64], [82, 0, -128, 64]);
+        let model = Klobuchar::decode(words.into_iter()).unwrap();
+        assert_eq!(
+            model.alpha,
+            [
+                12.0 * 2.0_f64.powi(-30),
+                8.0 * 2.0_f64.powi(-27),
+                -60.0 * 2.0_f64.powi(-24),
+                -64.0 * 2.0_f64.powi(-24),
+            ]
+        );
+        assert_eq!(model.beta, [167_936.0, 0.0, -8_388_608.0, 4_194_304.0]);
+
+        // other page (SV ID 57), other subframe, incomplete
+        let mut words = subframe([12, 8, -60, -64], [82, 0, -128, 64]);
+        words[2] ^= 0x01 << (16 + 6);
+        assert!(Klobuchar::decode(words.into_iter()).is_none());
+        let mut words = subframe([12, 8, -60, -64], [82, 0, -128, 64]);
+        words[1] = (3 << 2) << 6;
+        assert!(Klobuchar::decode(words.into_iter()).is_none());
+        let words = subframe([12, 8, -60, -64], [82, 0, -128, 64]);
+        assert!(Klobuchar::decode(words.into_iter().take(9)).is_none());
+    }
+
+    #[test]
+    fn slant_delays() {
+        let model = Klobuchar {
+            alpha: [1.1176E-8, 7.4506E-9, -5.9605E-8, -5.9605E-8],
+            beta: [90_112.0, 0.0, -196_608.0, -65_536.0],
+        };
+        let l1 = carrier_frequency_hz(Carrier::L1);
+
+        // night time, at zenith: 5ns
+        let t = Epoch::from_time_of_week(2304, 0, TimeScale::GPST);
+        let delay = model.slant_delay(t, 45.0, 0.0, 90.0, 0.0, l1);
+        assert!((delay - 1.499_609_8).abs() < 1.0E-6, "{}", delay);
+
+        // local afternoon, low elevation
+        let t = Epoch::from_time_of_week(2304, 50_400_000_000_000, TimeScale::GPST);
+        let delay = model.slant_delay(t, 45.0, 0.0, 20.0, 135.0, l1);
+        assert!((delay - 8.941_958).abs() < 1.0E-5, "{}", delay);
+
+        // scaled to L5
+        let l5 = carrier_frequency_hz(Carrier::L5);
+        let delay_l5 = model.slant_delay(t, 45.0, 0.0, 20.0, 135.0, l5);
+        assert!((delay_l5 / delay - (l1 / l5).powi(2)).abs() < 1.0E-12);
+    }
+}
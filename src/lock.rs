@@ -0,0 +1,131 @@
+//! Carrier tracking continuity, per SV and signal
+use std::collections::HashMap;
+
+use gnss_rtk::prelude::{Carrier, Duration, Epoch, SV};
+
+/// Continuous tracking of one signal: its phase ambiguity is constant
+#[derive(Debug, Clone, Copy)]
+struct Track {
+    /// Start of continuous tracking
+    start: Epoch,
+    /// Latest measurement
+    seen: Epoch,
+    /// Latest receiver lock time (ms)
+    lock_time: u16,
+}
+
+/// Detects phase ambiguity resets: the receiver lost lock (its lock time restarted),
+/// or the signal disappeared for longer than `max_gap` and was then reacquired.
+/// Phase based processing (code smoothing) must not run across a reset.
+#[derive(Debug)]
+pub struct LockTracker {
+    max_gap: Duration,
+    tracks: HashMap<(SV, Carrier), Track>,
+}
+
+impl LockTracker {
+    pub fn new(max_gap: Duration) -> Self {
+        Self {
+            max_gap,
+            tracks: HashMap::new(),
+        }
+    }
+
+    /// New measurement at `t`, with the receiver lock time (ms).
+    /// Returns how long this signal has been continuously tracked.
+    pub fn update(&mut self, sv: SV, carrier: Carrier, t: Epoch, lock_time: u16) -> Duration {
+        let locked_since = t - Duration::from_milliseconds(lock_time as f64);
+        let track = match self.tracks.get(&(sv, carrier)) {
+            Some(track) if lock_time < track.lock_time => {
+                debug!("{} {:?} loss of lock: phase ambiguity reset", sv, carrier);
+                Track {
+                    start: locked_since,
+                    seen: t,
+                    lock_time,
+                }
+            },
+            Some(track) if t - track.seen > self.max_gap => {
+                debug!(
+                    "{} {:?} reacquired after {}: phase ambiguity reset",
+                    sv,
+                    carrier,
+                    t - track.seen
+                );
+                Track {
+                    start: t,
+                    seen: t,
+                    lock_time,
+                }
+            },
+            Some(track) => Track {
+                seen: t,
+                lock_time,
+                ..*track
+            },
+            // first measurement: trust the receiver lock time
+            None => Track {
+                start: locked_since,
+                seen: t,
+                lock_time,
+            },
+        };
+        self.tracks.insert((sv, carrier), track);
+        t - track.start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Constellation;
+
+    #[test]
+    fn gap_resets_tracking() {
+        let g07 = SV::new(Constellation::GPS, 7);
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let t = |s: f64| t0 + Duration::from_seconds(s);
+        let mut locks = LockTracker::new(Duration::from_seconds(5.0));
+
+        // first measurement: locked for 10s already
+        assert_eq!(
+            locks.update(g07, Carrier::L1, t(0.0), 10_000).to_seconds(),
+            10.0
+        );
+        assert_eq!(
+            locks.update(g07, Carrier::L1, t(1.0), 11_000).to_seconds(),
+            11.0
+        );
+        // short gaps are bridged
+        assert_eq!(
+            locks.update(g07, Carrier::L1, t(6.0), 16_000).to_seconds(),
+            16.0
+        );
+
+        // reacquired after a longer gap, although the lock time kept increasing
+        assert_eq!(
+            locks.update(g07, Carrier::L1, t(12.0), 22_000).to_seconds(),
+            0.0
+        );
+        assert_eq!(
+            locks.update(g07, Carrier::L1, t(13.0), 23_000).to_seconds(),
+            1.0
+        );
+
+        // other signals are tracked separately
+        assert_eq!(
+            locks.update(g07, Carrier::L2, t(13.0), 2_000).to_seconds(),
+            2.0
+        );
+    }
+
+    #[test]
+    fn loss_of_lock_resets_tracking() {
+        let g07 = SV::new(Constellation::GPS, 7);
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let mut locks = LockTracker::new(Duration::from_seconds(5.0));
+
+        locks.update(g07, Carrier::L1, t0, 30_000);
+        let tracked = locks.update(g07, Carrier::L1, t0 + Duration::from_seconds(1.0), 2_000);
+        assert_eq!(tracked.to_seconds(), 2.0);
+    }
+}
@@ -1,23 +1,99 @@
 //! High precision navigation, in real time
 
 // private
+mod altitude;
+mod anomaly;
+mod antenna;
+mod bias;
+mod carrier;
 mod cli;
+mod clk;
+mod clock;
+mod combination;
+mod compare;
+mod config;
+mod coords;
+mod course;
+mod datum;
+mod devices;
+mod esf;
+mod history;
+mod hold;
+mod hooks;
+mod ionex;
+mod klobuchar;
+mod lock;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "osnma")]
+mod osnma;
+mod output;
+mod pvt;
+mod quality;
+mod relpos;
+mod reset;
+mod rtcm;
+mod sbas;
+mod selection;
+mod skylog;
+mod source;
+mod sp3;
+#[cfg(feature = "spartn")]
+mod spartn;
+mod status;
+mod summary;
+mod survey;
+mod time;
+mod timing;
+mod toggles;
+mod tropo;
+mod ttff;
 mod ublox;
 
 use env_logger::{Builder, Target};
+use std::{fs::File, sync::Arc};
 
 #[macro_use]
 extern crate log;
 
+use altitude::AltitudeConstraint;
 use cli::Cli;
+use clk::PreciseClockSource;
+use clock::ClockFilter;
+use compare::Comparison;
+use course::Course;
+use devices::Devices;
+use esf::DeadReckoning;
+use history::History;
+use hold::Hold;
+use hooks::Hooks;
+use ionex::IonexMap;
+use output::Outputs;
+use pvt::Pvt;
+use quality::Quality;
+use reset::SolverReset;
+use skylog::SkyLog;
+use sp3::Sp3OrbitSource;
+use status::Status;
+use summary::Summary;
+use survey::Survey;
 use thiserror::Error;
+use timing::SvThreshold;
+use toggles::Toggles;
+use tropo::TropoModel;
+use ttff::Ttff;
 
 use gnss_rtk::prelude::{
-    Config, Error as RTKError, InvalidationCause, IonosphereBias, Method, Solver, TroposphereBias,
+    AprioriPosition, Config, Epoch, Error as RTKError, InterpolationResult, InvalidationCause,
+    IonosphereBias, Method, Solver, TroposphereBias, SV,
 };
 
-use tokio::sync::mpsc;
-use ublox::{Command, Message, Ublox};
+use source::{Command, Message, Source};
+use tokio::{
+    io::{stdin, AsyncBufReadExt, BufReader},
+    sync::mpsc,
+};
+use ublox::{Recorder, Ublox};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -25,6 +101,74 @@ pub enum Error {
     NonSupportedGnss(u8),
     #[error("non supported signal {0}")]
     NonSupportedSignal(u8),
+    #[error("invalid output \"{0}\"")]
+    InvalidOutput(String),
+    #[error("invalid code bias \"{0}\"")]
+    InvalidBias(String),
+    #[error("invalid signal \"{0}\"")]
+    InvalidSignal(String),
+    #[error("invalid start type \"{0}\"")]
+    InvalidStartType(String),
+    #[error("invalid sp3: {0}")]
+    InvalidSp3(String),
+    #[error("invalid clock product: {0}")]
+    InvalidClk(String),
+    #[error("invalid ionex: {0}")]
+    InvalidIonex(String),
+    #[error("invalid datum \"{0}\"")]
+    InvalidDatum(String),
+    #[error("invalid position \"{0}\"")]
+    InvalidPosition(String),
+    #[error("invalid enu origin \"{0}\"")]
+    InvalidOrigin(String),
+    #[error("invalid troposphere model \"{0}\"")]
+    InvalidTropo(String),
+    #[error("invalid timestamp format \"{0}\"")]
+    InvalidTimestamp(String),
+    #[error("invalid dynamic model \"{0}\"")]
+    InvalidDynModel(String),
+    #[error("invalid antenna power \"{0}\"")]
+    InvalidAntennaPower(String),
+    #[error("invalid receiver port \"{0}\"")]
+    InvalidPort(String),
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("failed to open port {0}: {1}")]
+    Port(String, serialport::Error),
+    #[error("failed to configure {0}: {1}")]
+    Configuration(&'static str, std::io::Error),
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to deploy solver: {0}")]
+    Solver(String),
+}
+
+/// Deploys a new [Solver], from an optional [AprioriPosition],
+/// interpolating precise orbits when provided
+fn deploy_solver(
+    cfg: &Config,
+    apriori: Option<AprioriPosition>,
+    sp3: Option<Arc<Sp3OrbitSource>>,
+) -> Result<Solver<impl Fn(Epoch, SV, usize) -> Option<InterpolationResult>>, Error> {
+    Solver::new(cfg, apriori, move |t, sv, order| {
+        sp3.as_ref()?.interpolate(t, sv, order)
+    })
+    .map_err(|e| Error::Solver(e.to_string()))
+}
+
+/// Redeploys the solver from this [Config], switching between SPP and PPP.
+/// Orbits (almanac, SP3) are preserved, the filter state is not.
+fn switch_method(
+    cfg: &Config,
+    apriori: Option<AprioriPosition>,
+    sp3: Option<Arc<Sp3OrbitSource>>,
+) -> Result<Solver<impl Fn(Epoch, SV, usize) -> Option<InterpolationResult>>, Error> {
+    let mut cfg = cfg.clone();
+    cfg.method = match cfg.method {
+        Method::SPP => Method::PPP,
+        _ => Method::SPP,
+    };
+    deploy_solver(&cfg, apriori, sp3)
 }
 
 #[tokio::main]
@@ -38,125 +182,494 @@ async fn main() -> Result<(), Error> {
 
     // cli and user args
     let cli = Cli::new();
-    let opts = cli.serial_opts();
+    let mut outputs = Outputs::new(&cli.outputs(), cli.output_interval(), cli.timescale());
+
+    if let Some(survey_opts) = cli.survey_opts() {
+        outputs.push(Box::new(Survey::new(survey_opts)));
+    }
+
+    #[cfg(unix)]
+    if let Some(unit) = cli.shm() {
+        outputs.push(Box::new(output::Shm::new(unit)?));
+    }
 
     // create channels
     let (ublox_tx, mut rx) = mpsc::channel(16);
     let (tx, mut ublox_rx) = mpsc::channel(16);
 
-    let method = Method::SPP;
-    let cfg = Config::static_preset(method);
+    let solver_cfg = cli.solver_config()?;
+    let mut cfg = solver_cfg.to_config()?;
+    let known_position = cli.known_position();
+    if let Some(known) = &known_position {
+        known.time_only(&mut cfg);
+        info!(
+            "time only navigation at lat={} lon={} alt={}m",
+            known.lat, known.lon, known.alt
+        );
+    }
+    info!("solver configuration: {:#?}", cfg);
 
-    let mut solver = Solver::new(&cfg, None, |t, sv, _| None)
-        .unwrap_or_else(|e| panic!("failed to deploy solver: {}", e));
+    let sp3 = match cli.sp3() {
+        Some(path) => Some(Arc::new(Sp3OrbitSource::from_file(path)?)),
+        None => None,
+    };
 
-    // deploy hardware
-    let mut ublox = Ublox::new(opts, ublox_rx, ublox_tx);
-    ublox.init();
-    tokio::spawn(async move {
-        ublox.tasklet();
+    let known_apriori = known_position.as_ref().map(|known| known.apriori());
+    let mut solver = deploy_solver(&cfg, known_apriori.clone(), sp3.clone())?;
+    let mut reset = SolverReset::new(solver_cfg.reset.unwrap_or_default());
+    let mut quality = Quality::new(solver_cfg.quality);
+
+    let mut ublox_opts = cli.ublox_opts();
+    if let Some(path) = cli.clk() {
+        ublox_opts.clocks = Some(Arc::new(PreciseClockSource::from_file(path)?));
+    }
+    if let Some(path) = cli.ionex() {
+        ublox_opts.ionex = Some(Arc::new(IonexMap::from_file(path)?));
+    }
+    // SV geometry is fed back for ionosphere maps
+    let geometry = ublox_opts.ionex.is_some();
+
+    // secondary receivers only report their fixes
+    let secondary_opts = ublox_opts.secondary();
+
+    // replayed streams are not restarted
+    let start = match cli.replay() {
+        Some(_) => None,
+        None => ublox_opts.start,
+    };
+    let mut ttff = Ttff::new(start);
+    let mut summary = Summary::default();
+
+    // deploy hardware, or replay
+    let mut source: Box<dyn Source> = match cli.replay() {
+        Some(path) => Box::new(Ublox::replay(path, ublox_opts, ublox_rx, ublox_tx)?),
+        None => {
+            let mut ublox = Ublox::new(cli.serial_opts(), ublox_opts, ublox_rx, ublox_tx)?;
+            if let Some(path) = cli.record() {
+                ublox = ublox.with_recorder(Recorder::new(path)?);
+            }
+            ublox.init()?;
+            Box::new(ublox)
+        },
+    };
+    std::thread::spawn(move || {
+        source.run();
     });
 
+    // secondary receivers: their fixes are displayed next to the solution
+    let (devices_tx, mut devices_rx) = mpsc::channel(16);
+    let mut devices = Devices::default();
+    // command channels are kept open, for the receivers to keep running
+    let mut secondary = Vec::new();
+    for (index, serial) in cli.secondary_serial_opts().into_iter().enumerate() {
+        let id = index + 1;
+        let (device_tx, device_rx) = mpsc::channel(16);
+        let (command_tx, command_rx) = mpsc::channel(16);
+        info!("receiver #{}: {}", id, serial.port);
+        let mut ublox = Ublox::new(serial, secondary_opts.clone(), command_rx, device_tx)?;
+        ublox.init()?;
+        std::thread::spawn(move || {
+            ublox.run();
+        });
+        tokio::spawn(devices::forward(id, device_rx, devices_tx.clone()));
+        secondary.push(command_tx);
+    }
+    drop(devices_tx);
+
+    if let Some(rtcm_opts) = cli.rtcm_opts() {
+        tokio::spawn(rtcm::relay(rtcm_opts, tx.clone()));
+    }
+
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt_opts) = cli.mqtt_opts()? {
+        tokio::spawn(mqtt::relay(mqtt_opts, tx.clone()));
+    }
+
     let mut ionod = IonosphereBias::default();
     let mut tropod = TroposphereBias::default();
+    let tropo_model = cli.tropo_model();
+
+    // latitude (ddeg) and altitude (m) of latest fix
+    let mut apriori = None;
+
+    // altitude to hold, when constraint is toggled at runtime
+    let mut altitude = AltitudeConstraint::new(cfg.fixed_altitude);
+
+    let cable_delay = cli.cable_delay_ns().unwrap_or_default() * 1.0E-9;
+
+    let mut clock_filter = cli.clock_filter().then(ClockFilter::default);
+
+    let datum = cli.datum()?;
+    let enu_origin = cli.enu_origin();
+    let utm = cli.utm();
+    let mut comparison = cli.compare_receiver().then(Comparison::default);
+    let mut course = Course::default();
+    let mut dead_reckoning = DeadReckoning::default();
+    #[cfg(feature = "osnma")]
+    let mut osnma = osnma::Osnma::default();
+
+    let mut status = Status::default();
+    let mut history = History::new(cli.history());
+    let status_file = cli.status_file();
+
+    let selection = cli.selection();
+    let mut sv_threshold = SvThreshold::new(cli.min_sv().unwrap_or_default());
+    // elevation and azimuth of the SV that contributed to the latest fix
+    let mut sky = Vec::new();
+
+    let mut reference = None;
+    let mut range_rates = None;
+
+    let mut sky_log = match cli.sky_log() {
+        Some(path) => Some(SkyLog::new(path)?),
+        None => None,
+    };
+    let mut cno = None;
+
+    let mut hooks = Hooks::default();
+    if log_enabled!(log::Level::Trace) {
+        hooks.on_candidates(Box::new(|t, candidates| {
+            let sv = candidates.iter().map(|cd| cd.sv).collect::<Vec<_>>();
+            trace!("{} proposal: {:?}", t, sv);
+        }));
+    }
+
+    let mut hold = Hold::default();
+
+    let mut toggles = Toggles::new(cli.excluded_sv(), cli.included_sv());
+    let mut keys = BufReader::new(stdin()).lines();
+    toggles.help();
 
     loop {
-        while let Some(msg) = rx.recv().await {
-            match msg {
-                Message::Candidates((t, candidates)) => {
-                    match solver.resolve(t, &candidates, &ionod, &tropod) {
-                        Ok((_, solution)) => {
-                            let (x, y, z) = (
-                                solution.position.x,
-                                solution.position.y,
-                                solution.position.z,
-                            );
-                            let (vel_x, vel_y, vel_z) = (
-                                solution.velocity.x,
-                                solution.velocity.y,
-                                solution.velocity.z,
-                            );
-                            let dt = solution.dt;
-                            info!("new solution");
-                            info!("x={}, y={}, z={}", x, y, z);
-                            info!("vel_x={}, vel_y={}, vel_z={}", vel_x, vel_y, vel_z);
-                            info!("dt={}", dt);
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                info!("exiting..");
+                break;
+            },
+            Ok(Some(key)) = keys.next_line() => {
+                if key.trim() == toggles::ALTITUDE_KEY {
+                    altitude.toggle(&mut solver.cfg);
+                } else if key.trim() == toggles::HOLD_KEY {
+                    hold.toggle(&mut solver.cfg);
+                    status.on_hold(hold.is_held().then(|| hold.count()));
+                } else if key.trim() == toggles::METHOD_KEY {
+                    match switch_method(&solver.cfg, known_apriori.clone(), sp3.clone()) {
+                        Ok(new) => {
+                            info!("navigation method: {:?}", new.cfg.method);
+                            solver = new;
                         },
-                        Err(e) => match e {
-                            RTKError::Almanac(e) => {
-                                panic!("failed to load latest almanac: {}", e);
-                            },
-                            RTKError::NotEnoughCandidates => {
-                                error!("not enough candidates");
-                            },
-                            RTKError::NotEnoughMatchingCandidates => {
-                                error!("not enough quality candidates");
-                            },
-                            RTKError::MatrixError
-                            | RTKError::NavigationError
-                            | RTKError::MatrixInversionError => {
-                                error!("navigation error");
-                                warn!("check configuration setup");
-                            },
-                            RTKError::MissingPseudoRange | RTKError::PseudoRangeCombination => {
-                                error!("missing pseudo range observation");
-                            },
-                            RTKError::PhaseRangeCombination => {
-                                error!("missing pseudo range observation");
-                            },
-                            RTKError::UnresolvedState => {
-                                error!("solver internal error");
-                            },
-                            RTKError::UnresolvedAmbiguity => {
-                                error!("solver internal error (ambiguity)");
-                            },
-                            RTKError::InvalidStrategy => error!("invalid solving strategy"),
-                            RTKError::BancroftError => {
-                                error!("bancroft error");
-                                warn!("check configuration setup");
-                            },
-                            RTKError::BancroftImaginarySolution => {
-                                error!("imaginary solution");
-                                warn!("check configuration setup");
-                            },
-                            RTKError::FirstGuess => {
-                                error!("first guess error");
-                                warn!("check configuration setup");
-                            },
-                            RTKError::TimeIsNan => {
-                                error!("resolved time is NaN");
-                                warn!("check configuration setup");
-                            },
-                            RTKError::PhysicalNonSenseRxPriorTx
-                            | RTKError::PhysicalNonSenseRxTooLate => {
-                                error!("physical non sense");
-                                warn!("check configuration setup");
+                        Err(e) => error!("failed to switch the navigation method: {}", e),
+                    }
+                } else if key.trim() == toggles::OLDER_KEY {
+                    history.older();
+                } else if key.trim() == toggles::NEWER_KEY {
+                    history.newer();
+                } else if key.trim() == toggles::RESET_KEY {
+                    outputs.reset();
+                    history.clear();
+                    course = Course::default();
+                    info!("tracks, survey and history cleared");
+                } else if key.trim() == toggles::STATUS_KEY {
+                    match status.dump(&solver.cfg, &status_file) {
+                        Ok(_) => info!("status written to {}", status_file.display()),
+                        Err(e) => error!("failed to write {}: {}", status_file.display(), e),
+                    }
+                } else {
+                    toggles.toggle(&key);
+                }
+            },
+            Some((id, msg)) = devices_rx.recv() => {
+                devices.on_message(id, msg);
+            },
+            msg = rx.recv() => match msg {
+                None => {
+                    info!("receiver terminated");
+                    break;
+                },
+                Some(msg) => match msg {
+                    Message::RangeRates(rates) => {
+                        range_rates = Some(rates);
+                    },
+                    Message::Cno(latest) => {
+                        cno = Some(latest);
+                    },
+                    Message::HpPosition(hp) => {
+                        debug!(
+                            "receiver position: lat={:.9} lon={:.9} alt={:.4} hacc={:.4}m vacc={:.4}m",
+                            hp.lat, hp.lon, hp.alt, hp.h_acc, hp.v_acc
+                        );
+                        reference = Some(hp);
+                    },
+                    Message::Antenna(state) => {
+                        if state.is_fault() {
+                            warn!("antenna: {:?}", state);
+                        } else {
+                            info!("antenna: {:?}", state);
+                        }
+                        status.on_antenna(state);
+                    },
+                    Message::ParserStats(stats) => {
+                        status.on_parser_stats(stats);
+                        summary.on_parser_stats(stats);
+                    },
+                    Message::Ins(ins) => {
+                        if let Some((lat, lon, alt)) = dead_reckoning.on_ins(&ins) {
+                            info!(
+                                "dead reckoning lat={:.8} lon={:.8} alt={:.3}m",
+                                lat, lon, alt
+                            );
+                        }
+                    },
+                    #[cfg(feature = "osnma")]
+                    Message::Osnma((sv, authentication)) => {
+                        let global = osnma.global();
+                        osnma.update(sv, authentication);
+                        info!("{} OSNMA: {:?}", sv, authentication);
+                        if osnma.global() != global {
+                            info!("OSNMA: {:?}", osnma.global());
+                        }
+                    },
+                    Message::ReceiverFix(fix) => {
+                        if let Some(comparison) = comparison.as_mut() {
+                            comparison.on_receiver_fix(fix);
+                        }
+                    },
+                    Message::Candidates((t, mut candidates)) => {
+                        ttff.on_epoch(t);
+                        summary.on_epoch(t);
+                        toggles.retain(&mut candidates);
+                        if let Some(selection) = &selection {
+                            selection.select(&mut candidates, &sky);
+                        }
+                        hooks.candidates(t, &mut candidates);
+                        status.on_candidates(candidates.iter().map(|cd| cd.sv).collect());
+                        if !sv_threshold.ready(t, &solver.cfg, selection::nb_sv(&candidates)) {
+                            history.push(t, &candidates, None);
+                            if let (Some(sky_log), Some((t_cno, values))) =
+                                (sky_log.as_mut(), cno.take())
+                            {
+                                if t_cno == t {
+                                    sky_log.log(t, &values, None);
+                                }
+                            }
+                            if let Some(comparison) = &comparison {
+                                status.on_comparison(comparison.on_epoch(t, None));
+                            }
+                            continue;
+                        }
+                        if tropo_model == TropoModel::Unb3m {
+                            if let Some((lat, alt)) = apriori {
+                                tropod.zwd_zdd = Some(tropo::unb3m(lat, alt, t.day_of_year()));
+                            }
+                        }
+                        let mut fix = None;
+                        let mut reset_solver = false;
+                        match solver.resolve(t, &candidates, &ionod, &tropod) {
+                            Ok(solution) => {
+                                let mut pvt = Pvt::from(solution).with_cable_delay(cable_delay);
+                                course.update(&mut pvt);
+                                apriori = Some((pvt.geodetic.0, pvt.geodetic.2));
+                                altitude.on_fix(&solver.cfg, pvt.geodetic.2);
+                                if let Some(datum) = &datum {
+                                    pvt = pvt.with_datum(datum);
+                                }
+                                if let Some(origin) = &enu_origin {
+                                    pvt = pvt.with_enu_origin(origin);
+                                }
+                                if let Some(zone) = utm {
+                                    pvt = pvt.with_utm(zone);
+                                }
+                                if let Some(clock_filter) = clock_filter.as_mut() {
+                                    clock_filter.filter(&mut pvt);
+                                }
+                                if let Some((t_rr, rates)) = range_rates.take() {
+                                    if t_rr == t {
+                                        pvt.range_rates = rates;
+                                    }
+                                }
+                                let score = quality.score(&pvt);
+                                pvt.quality = Some(score);
+                                info!(
+                                    "{} fix quality: {}/100 ({:?})",
+                                    pvt.epoch,
+                                    score,
+                                    quality.rating(score)
+                                );
+                                debug!("new solution: {}", pvt);
+                                pvt.report_range_rates();
+                                if let Some(hp) = reference {
+                                    let (horizontal, vertical) =
+                                        pvt.offset_to(hp.lat, hp.lon, hp.alt);
+                                    info!(
+                                        "{} offset to receiver: horizontal={:.3}m vertical={:.3}m",
+                                        pvt.epoch, horizontal, vertical
+                                    );
+                                }
+                                sky = pvt.sv.clone();
+                                hold.on_fix(&pvt);
+                                devices.report(&pvt);
+                                dead_reckoning.on_fix(&pvt);
+                                status.on_hold(hold.is_held().then(|| hold.count()));
+                                ttff.on_fix(pvt.epoch);
+                                summary.on_fix(&pvt);
+                                status.on_fix(&pvt, ttff.ttff());
+                                outputs.on_fix(&pvt);
+                                if geometry {
+                                    let (lat, lon, _) = pvt.geodetic;
+                                    let sv = pvt.sv.clone();
+                                    if let Err(e) = tx.try_send(Command::Geometry((lat, lon, sv))) {
+                                        error!("failed to update sv geometry: {}", e);
+                                    }
+                                }
+                                reset_solver = reset.on_fix(pvt.position);
+                                fix = Some(pvt);
                             },
-                            RTKError::Physics(e) => {
-                                error!("physical non sense: {}", e);
-                                warn!("check configuration setup");
+                            Err(e) => {
+                                if !matches!(
+                                    e,
+                                    RTKError::InvalidatedSolution(InvalidationCause::FirstSolution)
+                                ) {
+                                    reset_solver = reset.on_error();
+                                }
+                                match e {
+                                    RTKError::Almanac(e) => {
+                                        error!("failed to load latest almanac: {}", e);
+                                        break;
+                                    },
+                                    RTKError::NotEnoughCandidates => {
+                                        error!("not enough candidates");
+                                    },
+                                    RTKError::NotEnoughMatchingCandidates => {
+                                        match solver.cfg.min_sv_elev {
+                                            Some(mask) => warn!(
+                                                "{} insufficient satellites above mask ({}°)",
+                                                t, mask
+                                            ),
+                                            None => warn!("{} insufficient quality satellites", t),
+                                        }
+                                    },
+                                    RTKError::MatrixError
+                                    | RTKError::NavigationError
+                                    | RTKError::MatrixInversionError => {
+                                        error!("navigation error");
+                                        warn!("check configuration setup");
+                                    },
+                                    RTKError::MissingPseudoRange | RTKError::PseudoRangeCombination => {
+                                        error!("missing pseudo range observation");
+                                    },
+                                    RTKError::PhaseRangeCombination => {
+                                        error!("missing pseudo range observation");
+                                    },
+                                    RTKError::UnresolvedState => {
+                                        error!("solver internal error");
+                                    },
+                                    RTKError::UnresolvedAmbiguity => {
+                                        error!("solver internal error (ambiguity)");
+                                    },
+                                    RTKError::InvalidStrategy => error!("invalid solving strategy"),
+                                    RTKError::BancroftError => {
+                                        error!("bancroft error");
+                                        warn!("check configuration setup");
+                                    },
+                                    RTKError::BancroftImaginarySolution => {
+                                        error!("imaginary solution");
+                                        warn!("check configuration setup");
+                                    },
+                                    RTKError::FirstGuess => {
+                                        error!("first guess error");
+                                        warn!("check configuration setup");
+                                    },
+                                    RTKError::TimeIsNan => {
+                                        error!("resolved time is NaN");
+                                        warn!("check configuration setup");
+                                    },
+                                    RTKError::PhysicalNonSenseRxPriorTx
+                                    | RTKError::PhysicalNonSenseRxTooLate => {
+                                        error!("physical non sense");
+                                        warn!("check configuration setup");
+                                    },
+                                    RTKError::Physics(e) => {
+                                        error!("physical non sense: {}", e);
+                                        warn!("check configuration setup");
+                                    },
+                                    RTKError::InvalidatedSolution(cause) => match cause {
+                                        InvalidationCause::FirstSolution => {
+                                            info!("first fix is pending!");
+                                        },
+                                        InvalidationCause::GDOPOutlier(gdop) => {
+                                            error!("solution rejected: gdop={}", gdop);
+                                        },
+                                        InvalidationCause::TDOPOutlier(tdop) => {
+                                            error!("solution rejected: tdop={}", tdop);
+                                        },
+                                        InvalidationCause::InnovationOutlier(innov) => {
+                                            error!("solution rejected: innov={}", innov);
+                                        },
+                                        InvalidationCause::CodeResidual(code_res) => {
+                                            error!("solution rejected: code_res={}", code_res);
+                                        },
+                                    },
+                                }
                             },
-                            RTKError::InvalidatedSolution(cause) => match cause {
-                                InvalidationCause::FirstSolution => {
-                                    info!("first fix is pending!");
-                                },
-                                InvalidationCause::GDOPOutlier(gdop) => {
-                                    error!("solution rejected: gdop={}", gdop);
+                        }
+                        if reset_solver {
+                            let cfg = solver.cfg.clone();
+                            match deploy_solver(&cfg, known_apriori.clone(), sp3.clone()) {
+                                Ok(new) => {
+                                    info!("{} solver reset", t);
+                                    solver = new;
                                 },
-                                InvalidationCause::TDOPOutlier(tdop) => {
-                                    error!("solution rejected: tdop={}", tdop);
-                                },
-                                InvalidationCause::InnovationOutlier(innov) => {
-                                    error!("solution rejected: innov={}", innov);
-                                },
-                                InvalidationCause::CodeResidual(code_res) => {
-                                    error!("solution rejected: code_res={}", code_res);
-                                },
-                            },
-                        },
-                    }
+                                Err(e) => error!("failed to reset solver: {}", e),
+                            }
+                        }
+                        if let Some(comparison) = &comparison {
+                            status.on_comparison(comparison.on_epoch(t, fix.as_ref()));
+                        }
+                        history.push(t, &candidates, fix.as_ref());
+                        if let (Some(sky_log), Some((t_cno, values))) =
+                            (sky_log.as_mut(), cno.take())
+                        {
+                            if t_cno == t {
+                                sky_log.log(t, &values, fix.as_ref());
+                            }
+                        }
+                    },
                 },
-            }
+            },
+        }
+    }
+
+    outputs.flush();
+    if let Some(sky_log) = sky_log.as_mut() {
+        sky_log.flush();
+    }
+
+    if let Err(e) = summary.write(&mut std::io::stdout(), ttff.ttff()) {
+        error!("failed to write summary: {}", e);
+    }
+    if let Some(path) = cli.summary() {
+        match File::create(path).and_then(|mut fd| summary.write(&mut fd, ttff.ttff())) {
+            Ok(_) => info!("summary: {} generated", path.display()),
+            Err(e) => error!("summary: failed to write {}: {}", path.display(), e),
         }
     }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_switch_redeploys_solver() {
+        let cfg = Config::static_preset(Method::SPP);
+        let solver = switch_method(&cfg, None, None).unwrap();
+        assert!(matches!(solver.cfg.method, Method::PPP));
+        assert_eq!(solver.cfg.min_sv_elev, cfg.min_sv_elev);
+
+        let solver = switch_method(&solver.cfg, None, None).unwrap();
+        assert!(matches!(solver.cfg.method, Method::SPP));
+        // the running configuration is left untouched
+        assert!(matches!(cfg.method, Method::SPP));
+    }
 }
@@ -0,0 +1,205 @@
+//! Corrections relay from an MQTT broker (SPARTN-style casters)
+use std::{fs::File, io::BufReader, path::Path, time::Duration as StdDuration};
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS, Transport};
+use serde::Deserialize;
+use tokio::sync::mpsc::Sender;
+
+use crate::{source::Command, Error};
+
+/// Keep alive interval
+const KEEP_ALIVE: StdDuration = StdDuration::from_secs(60);
+
+/// Delay prior reconnecting, after a connection error
+const RECONNECT_DELAY: StdDuration = StdDuration::from_secs(5);
+
+/// Broker endpoint, credentials and topics, as described in the configuration file (JSON)
+#[derive(Debug, Clone, Deserialize)]
+pub struct MqttOpts {
+    /// Broker host name
+    pub host: String,
+    /// Broker port
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Client identifier, as registered with the correction service
+    pub client_id: String,
+    /// Optional user name and password
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// CA certificate (PEM): connects over TLS when specified
+    pub ca: Option<String>,
+    /// Client certificate and private key (PEM), for mutual TLS
+    pub cert: Option<String>,
+    pub key: Option<String>,
+    /// Topics to subscribe to. Example: ["/pp/ip/eu", "/pp/ubx/0236/ip"]
+    pub topics: Vec<String>,
+}
+
+fn default_port() -> u16 {
+    8883
+}
+
+impl MqttOpts {
+    /// Loads [MqttOpts] from JSON file
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let fd = File::open(path)
+            .map_err(|e| Error::InvalidConfig(format!("{}: {}", path.display(), e)))?;
+        let opts: Self = serde_json::from_reader(BufReader::new(fd))
+            .map_err(|e| Error::InvalidConfig(format!("{}: {}", path.display(), e)))?;
+        if opts.topics.is_empty() {
+            return Err(Error::InvalidConfig(format!(
+                "{}: no topic to subscribe to",
+                path.display()
+            )));
+        }
+        if opts.cert.is_some() != opts.key.is_some() {
+            return Err(Error::InvalidConfig(format!(
+                "{}: client certificate and key go together",
+                path.display()
+            )));
+        }
+        Ok(opts)
+    }
+
+    /// Builds the client [MqttOptions]
+    fn mqtt_options(&self) -> Result<MqttOptions, Error> {
+        let mut options = MqttOptions::new(&self.client_id, &self.host, self.port);
+        options.set_keep_alive(KEEP_ALIVE);
+        if let Some(username) = &self.username {
+            options.set_credentials(username, self.password.clone().unwrap_or_default());
+        }
+        if let Some(ca) = &self.ca {
+            let client_auth = match (&self.cert, &self.key) {
+                (Some(cert), Some(key)) => Some((std::fs::read(cert)?, std::fs::read(key)?)),
+                _ => None,
+            };
+            options.set_transport(Transport::tls(std::fs::read(ca)?, client_auth, None));
+        }
+        Ok(options)
+    }
+}
+
+/// Forwards every message received on the subscribed topics to the GNSS receiver,
+/// through the same path as RTCM frames. The payloads (SPARTN, RTCM..) are forwarded as is.
+pub async fn relay(opts: MqttOpts, tx: Sender<Command>) {
+    let options = match opts.mqtt_options() {
+        Ok(options) => options,
+        Err(e) => {
+            error!("mqtt: {}", e);
+            return;
+        },
+    };
+
+    let (client, mut eventloop) = AsyncClient::new(options, 16);
+
+    loop {
+        match eventloop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("mqtt: connected to {}:{}", opts.host, opts.port);
+                // subscriptions do not survive reconnections
+                for topic in opts.topics.iter() {
+                    if let Err(e) = client.subscribe(topic, QoS::AtMostOnce).await {
+                        error!("mqtt: failed to subscribe to {}: {}", topic, e);
+                    }
+                }
+            },
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                debug!("mqtt: {} bytes on {}", publish.payload.len(), publish.topic);
+                #[cfg(feature = "spartn")]
+                crate::spartn::report(&publish.payload);
+                if let Err(e) = tx
+                    .send(Command::Corrections(publish.payload.to_vec()))
+                    .await
+                {
+                    error!("mqtt: failed to forward corrections: {}", e);
+                    return;
+                }
+            },
+            Ok(_) => {},
+            Err(e) => {
+                error!("mqtt: {}", e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        sync::mpsc,
+        time::timeout,
+    };
+
+    /// Reads one MQTT packet: returns its type and body
+    async fn packet(stream: &mut TcpStream) -> (u8, Vec<u8>) {
+        let header = stream.read_u8().await.unwrap();
+        let (mut len, mut shift) = (0_usize, 0);
+        loop {
+            let byte = stream.read_u8().await.unwrap();
+            len |= ((byte & 0x7F) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        let mut body = vec![0; len];
+        stream.read_exact(&mut body).await.unwrap();
+        (header >> 4, body)
+    }
+
+    #[tokio::test]
+    async fn relays_published_corrections() {
+        let broker = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let opts = MqttOpts {
+            host: "127.0.0.1".to_string(),
+            port: broker.local_addr().unwrap().port(),
+            client_id: "rt-navi-test".to_string(),
+            username: None,
+            password: None,
+            ca: None,
+            cert: None,
+            key: None,
+            topics: vec!["/pp/ip/eu".to_string()],
+        };
+        let (tx, mut rx) = mpsc::channel(4);
+        tokio::spawn(relay(opts, tx));
+
+        let broker = async {
+            let (mut stream, _) = broker.accept().await.unwrap();
+
+            // CONNECT / CONNACK
+            assert_eq!(packet(&mut stream).await.0, 1);
+            stream.write_all(&[0x20, 0x02, 0x00, 0x00]).await.unwrap();
+
+            // SUBSCRIBE / SUBACK
+            let (kind, body) = packet(&mut stream).await;
+            assert_eq!(kind, 8);
+            let topic_len = u16::from_be_bytes([body[2], body[3]]) as usize;
+            assert_eq!(&body[4..4 + topic_len], b"/pp/ip/eu");
+            stream
+                .write_all(&[0x90, 0x03, body[0], body[1], 0x00])
+                .await
+                .unwrap();
+
+            // PUBLISH (QoS 0)
+            let topic = b"/pp/ip/eu";
+            let payload = [0x73, 0x01, 0x02, 0x03];
+            let mut publish = vec![0x30, (2 + topic.len() + payload.len()) as u8, 0x00];
+            publish.push(topic.len() as u8);
+            publish.extend_from_slice(topic);
+            publish.extend_from_slice(&payload);
+            stream.write_all(&publish).await.unwrap();
+            stream
+        };
+        let _stream = timeout(StdDuration::from_secs(5), broker).await.unwrap();
+
+        match timeout(StdDuration::from_secs(5), rx.recv()).await.unwrap() {
+            Some(Command::Corrections(payload)) => assert_eq!(payload, [0x73, 0x01, 0x02, 0x03]),
+            command => panic!("unexpected command: {:?}", command),
+        }
+    }
+}
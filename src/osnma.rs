@@ -0,0 +1,229 @@
+//! Galileo OSNMA (navigation message authentication) status, from E1-B I/NAV pages.
+//! Only the broadcast status is reported: the MACs are not verified
+//! (neither the TESLA key chain, nor the public key are handled).
+use std::collections::HashMap;
+
+use gnss_rtk::prelude::SV;
+
+/// I/NAV page part length, as stored by RXM-SFRBX (bits): 4 words
+const PART_LEN: usize = 128;
+
+/// CRC protected bits: even part (114) then odd part (82)
+const EVEN_CRC_BITS: usize = 114;
+const ODD_CRC_BITS: usize = 82;
+
+/// Word type of the first page of a subframe, whose HKROOT byte is the NMA header
+const FIRST_WORD_TYPE: u32 = 2;
+
+/// Authentication status of the navigation data, per SV or globally.
+/// Ordered from the weakest to the strongest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Authentication {
+    /// No OSNMA data broadcast
+    #[default]
+    None,
+    /// OSNMA data broadcast, NMA header not received yet
+    Pending,
+    /// NMA status "don't use"
+    DontUse,
+    /// NMA status "test"
+    Test,
+    /// NMA status "operational": the navigation data is authenticated
+    Operational,
+}
+
+/// OSNMA fields of one I/NAV page (HKROOT and MACK sections)
+#[derive(Debug, Clone, Copy)]
+struct Page {
+    word_type: u32,
+    hkroot: u32,
+    mack: u32,
+}
+
+/// Reads `len` bits (up to 32) at `pos`, MSB first
+fn bitu(bits: &[u8], pos: usize, len: usize) -> u32 {
+    (pos..pos + len).fold(0, |value, i| {
+        (value << 1) | ((bits[i / 8] >> (7 - i % 8)) & 0x01) as u32
+    })
+}
+
+/// CRC-24Q of these bits (0 or 1)
+fn crc24q<I: Iterator<Item = u32>>(bits: I) -> u32 {
+    bits.fold(0, |crc, bit| {
+        let top = ((crc >> 23) & 0x01) ^ bit;
+        let crc = (crc << 1) & 0xFFFFFF;
+        if top != 0 {
+            crc ^ 0x864CFB
+        } else {
+            crc
+        }
+    })
+}
+
+impl Page {
+    /// Decodes a nominal page from 8 RXM-SFRBX words: even part in the first 4 words,
+    /// odd part in the last 4. Returns None on alert pages, part or CRC errors.
+    fn decode<I: Iterator<Item = u32>>(words: I) -> Option<Self> {
+        let mut bits = [0_u8; 32];
+        let mut nb_words = 0;
+        for (i, word) in words.take(8).enumerate() {
+            bits[i * 4..(i + 1) * 4].copy_from_slice(&word.to_be_bytes());
+            nb_words += 1;
+        }
+        if nb_words != 8 {
+            return None;
+        }
+
+        // even/odd then page type (1: alert)
+        if bitu(&bits, 0, 2) != 0 || bitu(&bits, PART_LEN, 2) != 0x02 {
+            return None;
+        }
+
+        let protected = (0..EVEN_CRC_BITS).chain(PART_LEN..PART_LEN + ODD_CRC_BITS);
+        if crc24q(protected.map(|pos| bitu(&bits, pos, 1)))
+            != bitu(&bits, PART_LEN + ODD_CRC_BITS, 24)
+        {
+            return None;
+        }
+
+        Some(Self {
+            word_type: bitu(&bits, 2, 6),
+            hkroot: bitu(&bits, PART_LEN + 18, 8),
+            mack: bitu(&bits, PART_LEN + 26, 32),
+        })
+    }
+
+    /// Authentication status this page indicates, given the previous one
+    fn authentication(&self, previous: Authentication) -> Authentication {
+        if self.hkroot == 0 && self.mack == 0 {
+            return Authentication::None;
+        }
+        if self.word_type != FIRST_WORD_TYPE {
+            return previous.max(Authentication::Pending);
+        }
+        // NMA header: NMAS (2 bits), CID (2), CPKS (3), reserved (1)
+        match self.hkroot >> 6 {
+            1 => Authentication::Test,
+            2 => Authentication::Operational,
+            3 => Authentication::DontUse,
+            _ => previous.max(Authentication::Pending),
+        }
+    }
+}
+
+/// Authentication status of each Galileo SV
+#[derive(Debug, Default)]
+pub struct Osnma {
+    status: HashMap<SV, Authentication>,
+}
+
+impl Osnma {
+    /// New E1-B page of this SV: returns its status when it changed
+    pub fn on_page<I: Iterator<Item = u32>>(&mut self, sv: SV, words: I) -> Option<Authentication> {
+        let page = Page::decode(words)?;
+        let previous = self.status.get(&sv).copied().unwrap_or_default();
+        let status = page.authentication(previous);
+        self.update(sv, status).then_some(status)
+    }
+
+    /// Updates the status of this SV, returns true when it changed
+    pub fn update(&mut self, sv: SV, status: Authentication) -> bool {
+        self.status.insert(sv, status) != Some(status)
+    }
+
+    /// Global status: the strongest of all SV
+    pub fn global(&self) -> Authentication {
+        self.status.values().copied().max().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Constellation;
+
+    /// Writes `len` bits of `value` at `pos`, MSB first
+    fn put(bits: &mut [u8], pos: usize, len: usize, value: u32) {
+        for i in 0..len {
+            if (value >> (len - 1 - i)) & 0x01 != 0 {
+                bits[(pos + i) / 8] |= 0x80 >> ((pos + i) % 8);
+            }
+        }
+    }
+
+    /// Nominal I/NAV page, as 8 RXM-SFRBX words
+    fn page(word_type: u32, hkroot: u32, mack: u32) -> Vec<u32> {
+        let mut bits = [0_u8; 32];
+        put(&mut bits, 2, 6, word_type);
+        // odd part, nominal page
+        put(&mut bits, PART_LEN, 2, 0x02);
+        put(&mut bits, PART_LEN + 18, 8, hkroot);
+        put(&mut bits, PART_LEN + 26, 32, mack);
+        let protected = (0..EVEN_CRC_BITS).chain(PART_LEN..PART_LEN + ODD_CRC_BITS);
+        let crc = crc24q(protected.map(|pos| bitu(&bits, pos, 1)));
+        put(&mut bits, PART_LEN + ODD_CRC_BITS, 24, crc);
+        bits.chunks(4)
+            .map(|word| u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+            .collect()
+    }
+
+    #[test]
+    fn crc24q_check_value() {
+        let bits = b"123456789"
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| (*byte as u32 >> i) & 0x01));
+        assert_eq!(crc24q(bits), 0xCDE703);
+    }
+
+    #[test]
+    fn status_from_nma_header() {
+        let e11 = SV::new(Constellation::Galileo, 11);
+        let e12 = SV::new(Constellation::Galileo, 12);
+        let mut osnma = Osnma::default();
+
+        // no OSNMA data
+        let status = osnma.on_page(e11, page(FIRST_WORD_TYPE, 0x00, 0).into_iter());
+        assert_eq!(status, Some(Authentication::None));
+
+        // OSNMA data, NMA header not received yet
+        let status = osnma.on_page(e11, page(4, 0x25, 0xDEADBEEF).into_iter());
+        assert_eq!(status, Some(Authentication::Pending));
+
+        // NMAS = 2: operational, reported once
+        let header = page(FIRST_WORD_TYPE, 0x80, 0xDEADBEEF);
+        let status = osnma.on_page(e11, header.clone().into_iter());
+        assert_eq!(status, Some(Authentication::Operational));
+        assert_eq!(osnma.on_page(e11, header.into_iter()), None);
+        assert_eq!(osnma.on_page(e11, page(6, 0x12, 1).into_iter()), None);
+
+        // NMAS = 1: test
+        let status = osnma.on_page(e12, page(FIRST_WORD_TYPE, 0x40, 1).into_iter());
+        assert_eq!(status, Some(Authentication::Test));
+        assert_eq!(osnma.global(), Authentication::Operational);
+
+        // NMAS = 3: don't use
+        let status = osnma.on_page(e11, page(FIRST_WORD_TYPE, 0xC0, 1).into_iter());
+        assert_eq!(status, Some(Authentication::DontUse));
+        assert_eq!(osnma.global(), Authentication::Test);
+    }
+
+    #[test]
+    fn invalid_pages_ignored() {
+        let e11 = SV::new(Constellation::Galileo, 11);
+        let mut osnma = Osnma::default();
+
+        let mut words = page(FIRST_WORD_TYPE, 0x80, 1);
+        words[5] ^= 0x0100_0000;
+        assert!(osnma.on_page(e11, words.into_iter()).is_none());
+
+        // alert page
+        let mut words = page(FIRST_WORD_TYPE, 0x80, 1);
+        words[4] |= 0x4000_0000;
+        assert!(osnma.on_page(e11, words.into_iter()).is_none());
+
+        // truncated
+        let words = page(FIRST_WORD_TYPE, 0x80, 1);
+        assert!(osnma.on_page(e11, words.into_iter().take(7)).is_none());
+        assert_eq!(osnma.global(), Authentication::None);
+    }
+}
@@ -0,0 +1,202 @@
+//! Compact binary solutions, for embedded consumers.
+//!
+//! Each solution is one frame, all fields little endian:
+//!
+//! | Offset | Size | Field                                                  |
+//! |--------|------|--------------------------------------------------------|
+//! | 0      | 2    | sync: 0x52, 0x4E ("RN")                                |
+//! | 2      | 1    | version: 1                                             |
+//! | 3      | 2    | payload length (u16): 64                               |
+//! | 5      | 2    | GPST week (u16)                                        |
+//! | 7      | 8    | GPST time of week (u64, ns)                            |
+//! | 15     | 24   | position ECEF x, y, z (f64, m)                         |
+//! | 39     | 12   | velocity ECEF x, y, z (f32, m/s)                       |
+//! | 51     | 8    | clock offset (f64, s)                                  |
+//! | 59     | 1    | number of SV (u8)                                      |
+//! | 60     | 1    | flags (u8): bit 0 heading valid, others reserved (0)   |
+//! | 61     | 4    | heading (f32, degrees), 0 unless valid                 |
+//! | 65     | 4    | HDOP (f32)                                             |
+//! | 69     | 2    | CRC-16                                                 |
+//!
+//! The CRC is CRC-16/CCITT-FALSE (polynomial 0x1021, initial value 0xFFFF),
+//! computed over version, length and payload (offsets 2 to 68).
+//! Consumers should look for the sync bytes, check the version and length,
+//! then the CRC, and resync past the sync bytes on any mismatch.
+//! [decode] is the reference decoder.
+use crate::pvt::Pvt;
+
+/// Frame sync bytes
+const SYNC: [u8; 2] = [0x52, 0x4E];
+
+/// Layout version
+const VERSION: u8 = 1;
+
+/// Payload length (bytes)
+const PAYLOAD_LEN: u16 = 64;
+
+/// Frame length (bytes): header, payload and CRC
+const FRAME_LEN: usize = 5 + PAYLOAD_LEN as usize + 2;
+
+/// Heading is valid
+const FLAG_HEADING: u8 = 0x01;
+
+/// CRC-16/CCITT-FALSE
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFF_u16;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// Encodes [Pvt] as one binary frame
+pub fn encode(pvt: &Pvt) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_LEN);
+    frame.extend_from_slice(&SYNC);
+    frame.push(VERSION);
+    frame.extend_from_slice(&PAYLOAD_LEN.to_le_bytes());
+
+    let (week, tow_ns) = pvt.epoch.to_time_of_week();
+    frame.extend_from_slice(&(week as u16).to_le_bytes());
+    frame.extend_from_slice(&tow_ns.to_le_bytes());
+
+    let (x, y, z) = pvt.position;
+    for value in [x, y, z] {
+        frame.extend_from_slice(&value.to_le_bytes());
+    }
+    let (vel_x, vel_y, vel_z) = pvt.velocity;
+    for value in [vel_x, vel_y, vel_z] {
+        frame.extend_from_slice(&(value as f32).to_le_bytes());
+    }
+    frame.extend_from_slice(&pvt.dt.to_le_bytes());
+
+    frame.push(pvt.nb_sv.min(u8::MAX as usize) as u8);
+    let mut flags = 0;
+    if pvt.heading.is_some() {
+        flags |= FLAG_HEADING;
+    }
+    frame.push(flags);
+    frame.extend_from_slice(&(pvt.heading.unwrap_or_default() as f32).to_le_bytes());
+    frame.extend_from_slice(&(pvt.hdop as f32).to_le_bytes());
+
+    let crc = crc16(&frame[2..]);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    frame
+}
+
+/// Decoded binary frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub struct Frame {
+    /// GPST week
+    pub week: u16,
+    /// GPST time of week (ns)
+    pub tow_ns: u64,
+    /// Position ECEF (m)
+    pub position: (f64, f64, f64),
+    /// Velocity ECEF (m/s)
+    pub velocity: (f32, f32, f32),
+    /// Clock offset (s)
+    pub dt: f64,
+    /// Number of SV
+    pub nb_sv: u8,
+    /// Heading (degrees), when valid
+    pub heading: Option<f32>,
+    /// HDOP
+    pub hdop: f32,
+}
+
+/// Decodes the frame at the start of `bytes`. Returns None on sync, version, length or CRC mismatch.
+/// rt-navi only encodes: this is the reference implementation for consumers.
+#[allow(dead_code)]
+pub fn decode(bytes: &[u8]) -> Option<Frame> {
+    let frame = bytes.get(..FRAME_LEN)?;
+    if frame[..2] != SYNC || frame[2] != VERSION || frame[3..5] != PAYLOAD_LEN.to_le_bytes() {
+        return None;
+    }
+    let crc = u16::from_le_bytes([frame[FRAME_LEN - 2], frame[FRAME_LEN - 1]]);
+    if crc16(&frame[2..FRAME_LEN - 2]) != crc {
+        return None;
+    }
+
+    let f32_at = |offset: usize| f32::from_le_bytes(frame[offset..offset + 4].try_into().unwrap());
+    let f64_at = |offset: usize| f64::from_le_bytes(frame[offset..offset + 8].try_into().unwrap());
+
+    let flags = frame[60];
+    Some(Frame {
+        week: u16::from_le_bytes([frame[5], frame[6]]),
+        tow_ns: u64::from_le_bytes(frame[7..15].try_into().unwrap()),
+        position: (f64_at(15), f64_at(23), f64_at(31)),
+        velocity: (f32_at(39), f32_at(43), f32_at(47)),
+        dt: f64_at(51),
+        nb_sv: frame[59],
+        heading: (flags & FLAG_HEADING != 0).then(|| f32_at(61)),
+        hdop: f32_at(65),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Epoch, TimeScale};
+
+    fn pvt(heading: Option<f64>) -> Pvt {
+        let epoch = Epoch::from_time_of_week(2304, 432_000_500_000_000, TimeScale::GPST);
+        Pvt {
+            velocity: (0.25, -1.5, 0.125),
+            dt: 1.25E-6,
+            nb_sv: 9,
+            hdop: 0.75,
+            speed: 1.5,
+            heading,
+            ..Pvt::fixture(epoch, (4_027_893.5, 307_045.25, 4_919_474.75))
+        }
+    }
+
+    #[test]
+    fn crc16_check() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn round_trip() {
+        let frame = encode(&pvt(Some(92.5)));
+        assert_eq!(frame.len(), FRAME_LEN);
+        assert_eq!(
+            decode(&frame),
+            Some(Frame {
+                week: 2304,
+                tow_ns: 432_000_500_000_000,
+                position: (4_027_893.5, 307_045.25, 4_919_474.75),
+                velocity: (0.25, -1.5, 0.125),
+                dt: 1.25E-6,
+                nb_sv: 9,
+                heading: Some(92.5),
+                hdop: 0.75,
+            })
+        );
+
+        let frame = encode(&pvt(None));
+        assert_eq!(decode(&frame).unwrap().heading, None);
+    }
+
+    #[test]
+    fn corrupt_frames_rejected() {
+        let frame = encode(&pvt(None));
+
+        // any altered byte fails the sync, version, length or CRC checks
+        for i in 0..frame.len() {
+            let mut corrupt = frame.clone();
+            corrupt[i] ^= 0x10;
+            assert!(decode(&corrupt).is_none(), "byte {}", i);
+        }
+        assert!(decode(&frame[..FRAME_LEN - 1]).is_none());
+    }
+}
@@ -0,0 +1,86 @@
+//! GPX track
+use std::{fs::File, io::Result as IoResult, path::PathBuf};
+
+use geo_types::Point;
+use gpx::{Gpx as GpxDocument, GpxVersion, Track, TrackSegment, Waypoint};
+
+use super::SolutionSink;
+use crate::pvt::Pvt;
+
+/// Buffers all solutions, written as GPX track on flush
+pub struct Gpx {
+    path: PathBuf,
+    segment: TrackSegment,
+}
+
+impl Gpx {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            segment: TrackSegment::new(),
+        }
+    }
+}
+
+impl SolutionSink for Gpx {
+    fn on_fix(&mut self, pvt: &Pvt) -> IoResult<()> {
+        let (lat, lon, alt) = pvt.geodetic;
+        let mut waypoint = Waypoint::new(Point::new(lon, lat));
+        waypoint.elevation = Some(alt);
+        waypoint.name = Some(pvt.epoch.to_string());
+        waypoint.hdop = Some(pvt.hdop);
+        waypoint.sat = Some(pvt.nb_sv as u64);
+        self.segment.points.push(waypoint);
+        Ok(())
+    }
+    fn reset(&mut self) {
+        self.segment.points.clear();
+    }
+    fn flush(&mut self) {
+        let mut track = Track::new();
+        track.name = Some("rt-navi".to_string());
+        track.segments.push(self.segment.clone());
+
+        let mut gpx = GpxDocument::default();
+        gpx.version = GpxVersion::Gpx11;
+        gpx.creator = Some(format!("rt-navi v{}", env!("CARGO_PKG_VERSION")));
+        gpx.tracks.push(track);
+
+        match File::create(&self.path) {
+            Ok(fd) => match gpx::write(&gpx, fd) {
+                Ok(_) => info!("{} generated", self.path.display()),
+                Err(e) => error!("failed to write {}: {}", self.path.display(), e),
+            },
+            Err(e) => error!("failed to create {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Duration, Epoch};
+
+    #[test]
+    fn reset_clears_track() {
+        let path = std::env::temp_dir().join("rt-navi-reset-test.gpx");
+        let mut sink = Gpx::new(path.clone());
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let fix = |i: u32| {
+            let t = t0 + Duration::from_seconds(i as f64);
+            Pvt::fixture(t, (4_201_575.0, 189_856.0, 4_779_066.0))
+        };
+        for i in 0..3 {
+            sink.on_fix(&fix(i)).unwrap();
+        }
+        sink.reset();
+        sink.on_fix(&fix(3)).unwrap();
+        sink.flush();
+
+        // the track restarts from the first fix past the reset
+        let gpx = gpx::read(File::open(&path).unwrap()).unwrap();
+        let points = &gpx.tracks[0].segments[0].points;
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].name, Some(fix(3).epoch.to_string()));
+    }
+}
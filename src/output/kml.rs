@@ -0,0 +1,262 @@
+//! KML track
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration as StdDuration,
+};
+
+use kml::{
+    types::{AltitudeMode, Coord, Geometry, LineString, Placemark},
+    Kml as KmlElement, KmlDocument, KmlVersion, KmlWriter,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time::timeout,
+};
+
+use super::SolutionSink;
+use crate::pvt::Pvt;
+
+/// Converts [Pvt] to KML coordinates
+fn coord(pvt: &Pvt) -> Coord<f64> {
+    let (lat, lon, alt) = pvt.geodetic;
+    Coord {
+        x: lon,
+        y: lat,
+        z: Some(alt),
+    }
+}
+
+/// Builds KML document, made of a single track
+fn document(coords: &[Coord<f64>]) -> KmlElement {
+    let track = Placemark {
+        name: Some("rt-navi".to_string()),
+        description: None,
+        geometry: Some(Geometry::LineString(LineString {
+            coords: coords.to_vec(),
+            extrude: false,
+            tessellate: false,
+            altitude_mode: AltitudeMode::Absolute,
+            attrs: HashMap::new(),
+        })),
+        attrs: HashMap::new(),
+        children: vec![],
+    };
+
+    KmlElement::KmlDocument(KmlDocument {
+        version: KmlVersion::V23,
+        attrs: HashMap::new(),
+        elements: vec![KmlElement::Placemark(track)],
+    })
+}
+
+/// Buffers all solutions, written as KML track on flush
+pub struct Kml {
+    path: PathBuf,
+    coords: Vec<Coord<f64>>,
+}
+
+impl Kml {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            coords: Vec::new(),
+        }
+    }
+}
+
+impl SolutionSink for Kml {
+    fn on_fix(&mut self, pvt: &Pvt) -> IoResult<()> {
+        self.coords.push(coord(pvt));
+        Ok(())
+    }
+    fn reset(&mut self) {
+        self.coords.clear();
+    }
+    fn flush(&mut self) {
+        let kml = document(&self.coords);
+        match File::create(&self.path) {
+            Ok(mut fd) => match KmlWriter::from_writer(&mut fd).write(&kml) {
+                Ok(_) => info!("{} generated", self.path.display()),
+                Err(e) => error!("failed to write {}: {}", self.path.display(), e),
+            },
+            Err(e) => error!("failed to create {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// A client is disconnected when its request is not received within this delay
+const REQUEST_TIMEOUT: StdDuration = StdDuration::from_secs(5);
+
+/// Serves the current KML track over HTTP, for real time viewers
+/// (for example a Google Earth network link)
+pub struct Server {
+    coords: Arc<Mutex<Vec<Coord<f64>>>>,
+}
+
+impl Server {
+    /// Deploys HTTP server on `addr`
+    pub fn deploy(addr: &str) -> IoResult<Self> {
+        let listener = std::net::TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let listener = TcpListener::from_std(listener)?;
+        info!("serving live KML on http://{}", addr);
+        Ok(Self::serve(listener))
+    }
+
+    /// Serves every client of this listener on its own task
+    fn serve(listener: TcpListener) -> Self {
+        let coords = Arc::new(Mutex::new(Vec::new()));
+        let shared = coords.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let coords = shared.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = Self::respond(stream, &coords).await {
+                                error!("kml server: {}", e);
+                            }
+                        });
+                    },
+                    Err(e) => {
+                        error!("kml server: {}", e);
+                        return;
+                    },
+                }
+            }
+        });
+
+        Self { coords }
+    }
+
+    /// Answers any request with the current track
+    async fn respond(mut stream: TcpStream, coords: &Mutex<Vec<Coord<f64>>>) -> IoResult<()> {
+        let mut request = [0; 1024];
+        timeout(REQUEST_TIMEOUT, stream.read(&mut request))
+            .await
+            .map_err(|_| IoError::new(IoErrorKind::TimedOut, "request timed out"))??;
+
+        let mut body = Vec::new();
+        {
+            let coords = coords.lock().unwrap_or_else(|e| e.into_inner());
+            if let Err(e) = KmlWriter::from_writer(&mut body).write(&document(&coords)) {
+                error!("kml server: {}", e);
+            }
+        }
+
+        let header = format!(
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: application/vnd.google-earth.kml+xml\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n",
+            body.len()
+        );
+
+        stream.write_all(header.as_bytes()).await?;
+        stream.write_all(&body).await?;
+        stream.shutdown().await
+    }
+}
+
+impl SolutionSink for Server {
+    fn on_fix(&mut self, pvt: &Pvt) -> IoResult<()> {
+        self.coords
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(coord(pvt));
+        Ok(())
+    }
+    fn reset(&mut self) {
+        self.coords
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+    fn flush(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Duration, Epoch};
+
+    #[test]
+    fn reset_clears_track() {
+        let mut sink = Kml::new(std::env::temp_dir().join("rt-navi-reset-test.kml"));
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let position = (4_201_575.0, 189_856.0, 4_779_066.0);
+        for i in 0..3 {
+            let t = t0 + Duration::from_seconds(i as f64);
+            sink.on_fix(&Pvt::fixture(t, position)).unwrap();
+        }
+        assert_eq!(sink.coords.len(), 3);
+        sink.reset();
+        assert!(sink.coords.is_empty());
+        sink.on_fix(&Pvt::fixture(t0, position)).unwrap();
+        assert_eq!(sink.coords.len(), 1);
+    }
+
+    /// First line track of this KML element
+    fn track(kml: &KmlElement) -> Option<Vec<Coord<f64>>> {
+        match kml {
+            KmlElement::KmlDocument(doc) => doc.elements.iter().find_map(track),
+            KmlElement::Document { elements, .. } | KmlElement::Folder { elements, .. } => {
+                elements.iter().find_map(track)
+            },
+            KmlElement::Placemark(placemark) => match &placemark.geometry {
+                Some(Geometry::LineString(line)) => Some(line.coords.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn live_track_served() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut server = Server::serve(listener);
+
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let position = (4_201_575.0, 189_856.0, 4_779_066.0);
+        for i in 0..2 {
+            let t = t0 + Duration::from_seconds(i as f64);
+            server.on_fix(&Pvt::fixture(t, position)).unwrap();
+        }
+
+        // an idle client does not hold the others
+        let _idle = TcpStream::connect(addr).await.unwrap();
+
+        let fetch = async {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(b"GET /track.kml HTTP/1.1\r\nHost: localhost\r\n\r\n")
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let response = timeout(REQUEST_TIMEOUT / 2, fetch).await.unwrap();
+
+        let (header, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(header.starts_with("HTTP/1.1 200 OK"));
+        assert!(header.contains("application/vnd.google-earth.kml+xml"));
+        assert!(header.contains(&format!("Content-Length: {}", body.len())));
+
+        let kml = body.parse::<KmlElement>().unwrap();
+        let coords = track(&kml).unwrap();
+        let (lat, lon, alt) = Pvt::fixture(t0, position).geodetic;
+        assert_eq!(coords.len(), 2);
+        assert!((coords[0].x - lon).abs() < 1.0E-9);
+        assert!((coords[0].y - lat).abs() < 1.0E-9);
+        assert!((coords[0].z.unwrap() - alt).abs() < 1.0E-3);
+    }
+}
@@ -0,0 +1,610 @@
+//! Solutions output
+use std::{
+    fs::File,
+    io::{stdout, BufWriter, Result as IoResult, Write},
+    net::{TcpStream, UdpSocket},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use gnss_rtk::prelude::{Duration, Epoch, TimeScale};
+
+use crate::{pvt::Pvt, time::TimestampFormat, Error};
+
+mod binary;
+mod nmea;
+
+#[cfg(unix)]
+mod shm;
+
+#[cfg(unix)]
+pub use shm::Shm;
+
+#[cfg(feature = "gpx")]
+mod gpx;
+
+#[cfg(feature = "kml")]
+mod kml;
+
+/// A [SolutionSink] receives every resolved [Pvt]
+pub trait SolutionSink {
+    /// Handle new [Pvt] solution
+    fn on_fix(&mut self, pvt: &Pvt) -> IoResult<()>;
+    /// Flush any pending content, typically prior exiting
+    fn flush(&mut self);
+    /// Discards accumulated solutions (tracks, statistics), if any
+    fn reset(&mut self) {}
+}
+
+/// Solutions format
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Format {
+    /// Human readable text
+    #[default]
+    Text,
+    /// One JSON object per solution
+    Json,
+    /// CSV with header
+    Csv,
+    /// NMEA GGA sentences
+    Nmea,
+    /// Compact binary frames, with CRC
+    Binary,
+    /// GPX track, written on exit
+    #[cfg(feature = "gpx")]
+    Gpx,
+    /// KML track, written on exit
+    #[cfg(feature = "kml")]
+    Kml,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "nmea" => Ok(Self::Nmea),
+            "bin" => Ok(Self::Binary),
+            #[cfg(feature = "gpx")]
+            "gpx" => Ok(Self::Gpx),
+            #[cfg(feature = "kml")]
+            "kml" => Ok(Self::Kml),
+            _ => Err(Error::InvalidOutput(s.to_string())),
+        }
+    }
+}
+
+impl Format {
+    /// CSV header, following the timestamp columns
+    const CSV_HEADER: &'static str =
+        "x,y,z,lat,lon,alt,vel_x,vel_y,vel_z,dt,nb_sv,gdop,hdop,speed,heading,east,north,up,\
+         utm_zone,easting,northing,vdop,pdop,tdop,quality";
+
+    /// Encodes [Pvt] in line based formats, the epoch being expressed in `timescale`.
+    /// JSON and CSV render the epoch as `timestamp`. NMEA is always expressed in UTC.
+    fn encode(&self, pvt: &Pvt, timescale: TimeScale, timestamp: TimestampFormat) -> String {
+        let epoch = pvt.epoch.to_time_scale(timescale);
+        let (x, y, z) = pvt.position;
+        let (lat, lon, alt) = pvt.geodetic;
+        let (vel_x, vel_y, vel_z) = pvt.velocity;
+        match self {
+            Self::Json => format!(
+                "{{{},\"x\":{},\"y\":{},\"z\":{},\"lat\":{},\"lon\":{},\"alt\":{},\
+                 \"vel_x\":{},\"vel_y\":{},\"vel_z\":{},\"dt\":{},\"nb_sv\":{},\"gdop\":{},\"hdop\":{},\
+                 \"speed\":{},\"heading\":{},\"vdop\":{},\"pdop\":{},\"tdop\":{}{}{}{}}}",
+                timestamp.json(pvt.epoch, timescale),
+                x,
+                y,
+                z,
+                lat,
+                lon,
+                alt,
+                vel_x,
+                vel_y,
+                vel_z,
+                pvt.dt,
+                pvt.nb_sv,
+                pvt.gdop,
+                pvt.hdop,
+                pvt.speed,
+                match pvt.heading {
+                    Some(heading) => heading.to_string(),
+                    None => "null".to_string(),
+                },
+                pvt.vdop,
+                pvt.pdop,
+                pvt.tdop,
+                match pvt.enu {
+                    Some((east, north, up)) => {
+                        format!(",\"east\":{},\"north\":{},\"up\":{}", east, north, up)
+                    },
+                    None => String::new(),
+                },
+                match pvt.utm {
+                    Some(utm) => format!(
+                        ",\"utm_zone\":\"{}\",\"easting\":{},\"northing\":{}",
+                        utm, utm.easting, utm.northing
+                    ),
+                    None => String::new(),
+                },
+                match pvt.quality {
+                    Some(quality) => format!(",\"quality\":{}", quality),
+                    None => String::new(),
+                },
+            ),
+            Self::Csv => format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                timestamp.csv(pvt.epoch, timescale),
+                x,
+                y,
+                z,
+                lat,
+                lon,
+                alt,
+                vel_x,
+                vel_y,
+                vel_z,
+                pvt.dt,
+                pvt.nb_sv,
+                pvt.gdop,
+                pvt.hdop,
+                pvt.speed,
+                pvt.heading.map(|heading| heading.to_string()).unwrap_or_default(),
+                match pvt.enu {
+                    Some((east, north, up)) => format!("{},{},{}", east, north, up),
+                    None => ",,".to_string(),
+                },
+                match pvt.utm {
+                    Some(utm) => format!("{},{},{}", utm, utm.easting, utm.northing),
+                    None => ",,".to_string(),
+                },
+                pvt.vdop,
+                pvt.pdop,
+                pvt.tdop,
+                pvt.quality.map(|quality| quality.to_string()).unwrap_or_default(),
+            ),
+            Self::Nmea => nmea::gga(pvt),
+            _ => {
+                let mut pvt = pvt.clone();
+                pvt.epoch = epoch;
+                pvt.to_string()
+            },
+        }
+    }
+}
+
+/// Output destination
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destination {
+    /// Standard output
+    Stdout,
+    /// Local file
+    File(PathBuf),
+    /// TCP remote peer
+    Tcp(String),
+    /// UDP remote peer
+    Udp(String),
+    /// Local HTTP server, polled by real time viewers
+    Http(String),
+}
+
+impl FromStr for Destination {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq("stdout") {
+            return Ok(Self::Stdout);
+        }
+        match s.split_once(':') {
+            Some(("file", path)) => Ok(Self::File(PathBuf::from(path))),
+            Some(("tcp", addr)) => Ok(Self::Tcp(addr.to_string())),
+            Some(("udp", addr)) => Ok(Self::Udp(addr.to_string())),
+            Some(("http", addr)) => Ok(Self::Http(addr.to_string())),
+            _ => Err(Error::InvalidOutput(s.to_string())),
+        }
+    }
+}
+
+/// Output, as described by user: [Format], [TimestampFormat] and [Destination]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkOpts {
+    /// [Format]
+    pub format: Format,
+    /// [TimestampFormat], JSON and CSV only
+    pub timestamp: TimestampFormat,
+    /// [Destination]
+    pub destination: Destination,
+}
+
+impl FromStr for SinkOpts {
+    type Err = Error;
+    /// Parses [FORMAT[@TIMESTAMP]:]DESTINATION
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some((format, destination)) = s.split_once(':') {
+            let (format, timestamp) = match format.split_once('@') {
+                Some((format, timestamp)) => (format, Some(timestamp)),
+                None => (format, None),
+            };
+            if let Ok(format) = Format::from_str(format) {
+                let timestamp = match timestamp {
+                    Some(_) if !matches!(format, Format::Json | Format::Csv) => {
+                        return Err(Error::InvalidOutput(s.to_string()));
+                    },
+                    Some(timestamp) => TimestampFormat::from_str(timestamp)?,
+                    None => TimestampFormat::default(),
+                };
+                return Ok(Self {
+                    format,
+                    timestamp,
+                    destination: Destination::from_str(destination)?,
+                });
+            }
+        }
+        Ok(Self {
+            format: Format::default(),
+            timestamp: TimestampFormat::default(),
+            destination: Destination::from_str(s)?,
+        })
+    }
+}
+
+impl SinkOpts {
+    /// Deploys this [SolutionSink], expressing epochs in `timescale`
+    pub fn deploy(&self, timescale: TimeScale) -> IoResult<Box<dyn SolutionSink>> {
+        match self.format {
+            #[cfg(feature = "gpx")]
+            Format::Gpx => Ok(Box::new(gpx::Gpx::new(self.path()?))),
+            #[cfg(feature = "kml")]
+            Format::Kml => match &self.destination {
+                Destination::Http(addr) => Ok(Box::new(kml::Server::deploy(addr)?)),
+                _ => Ok(Box::new(kml::Kml::new(self.path()?))),
+            },
+            format => match &self.destination {
+                Destination::Stdout => Ok(Box::new(Stream::new(
+                    format,
+                    timescale,
+                    self.timestamp,
+                    stdout(),
+                ))),
+                Destination::File(path) => Ok(Box::new(Stream::new(
+                    format,
+                    timescale,
+                    self.timestamp,
+                    BufWriter::new(File::create(path)?),
+                ))),
+                Destination::Tcp(addr) => Ok(Box::new(Stream::new(
+                    format,
+                    timescale,
+                    self.timestamp,
+                    TcpStream::connect(addr)?,
+                ))),
+                Destination::Udp(addr) => {
+                    let socket = UdpSocket::bind("0.0.0.0:0")?;
+                    socket.connect(addr)?;
+                    Ok(Box::new(Udp {
+                        format,
+                        timescale,
+                        timestamp: self.timestamp,
+                        socket,
+                    }))
+                },
+                Destination::Http(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    format!("{:?} output can't be served over http", format),
+                )),
+            },
+        }
+    }
+
+    /// Buffered formats may only be written to files
+    #[cfg(any(feature = "gpx", feature = "kml"))]
+    fn path(&self) -> IoResult<PathBuf> {
+        match &self.destination {
+            Destination::File(path) => Ok(path.clone()),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("{:?} output requires a file", self.format),
+            )),
+        }
+    }
+}
+
+/// Writes one solution per line to any [Write]able stream
+struct Stream<W: Write> {
+    format: Format,
+    timescale: TimeScale,
+    timestamp: TimestampFormat,
+    writer: W,
+    header: bool,
+}
+
+impl<W: Write> Stream<W> {
+    fn new(format: Format, timescale: TimeScale, timestamp: TimestampFormat, writer: W) -> Self {
+        Self {
+            format,
+            timescale,
+            timestamp,
+            writer,
+            header: format == Format::Csv,
+        }
+    }
+}
+
+impl<W: Write> SolutionSink for Stream<W> {
+    fn on_fix(&mut self, pvt: &Pvt) -> IoResult<()> {
+        if self.header {
+            writeln!(
+                self.writer,
+                "{},{}",
+                self.timestamp.csv_header(),
+                Format::CSV_HEADER
+            )?;
+            self.header = false;
+        }
+        match self.format {
+            Format::Binary => self.writer.write_all(&binary::encode(pvt)),
+            format => writeln!(
+                self.writer,
+                "{}",
+                format.encode(pvt, self.timescale, self.timestamp)
+            ),
+        }
+    }
+    fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            error!("failed to flush output: {}", e);
+        }
+    }
+}
+
+/// Sends one datagram per solution
+struct Udp {
+    format: Format,
+    timescale: TimeScale,
+    timestamp: TimestampFormat,
+    socket: UdpSocket,
+}
+
+impl SolutionSink for Udp {
+    fn on_fix(&mut self, pvt: &Pvt) -> IoResult<()> {
+        match self.format {
+            Format::Binary => self.socket.send(&binary::encode(pvt))?,
+            format => self.socket.send(
+                format
+                    .encode(pvt, self.timescale, self.timestamp)
+                    .as_bytes(),
+            )?,
+        };
+        Ok(())
+    }
+    fn flush(&mut self) {}
+}
+
+/// Fans each solution out to all deployed [SolutionSink]s
+pub struct Outputs {
+    sinks: Vec<Box<dyn SolutionSink>>,
+    /// Minimal interval between dispatched solutions, if throttled
+    interval: Option<Duration>,
+    /// Latest dispatched solution [Epoch]
+    last: Option<Epoch>,
+}
+
+impl Outputs {
+    /// Deploys all requested outputs, dispatching at most one solution per `interval`,
+    /// with epochs expressed in `timescale`
+    pub fn new(opts: &[SinkOpts], interval: Option<Duration>, timescale: TimeScale) -> Self {
+        let mut sinks = Vec::with_capacity(opts.len());
+        for opt in opts {
+            match opt.deploy(timescale) {
+                Ok(sink) => sinks.push(sink),
+                Err(e) => error!("failed to deploy output {:?}: {}", opt, e),
+            }
+        }
+        Self {
+            sinks,
+            interval,
+            last: None,
+        }
+    }
+
+    /// Adds a [SolutionSink]
+    pub fn push(&mut self, sink: Box<dyn SolutionSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Dispatches new [Pvt] to all outputs, unless throttled
+    pub fn on_fix(&mut self, pvt: &Pvt) {
+        if let (Some(interval), Some(last)) = (self.interval, self.last) {
+            if pvt.epoch - last < interval {
+                return;
+            }
+        }
+        self.last = Some(pvt.epoch);
+        for sink in self.sinks.iter_mut() {
+            if let Err(e) = sink.on_fix(pvt) {
+                error!("output error: {}", e);
+            }
+        }
+    }
+
+    /// Discards solutions accumulated by all outputs
+    pub fn reset(&mut self) {
+        for sink in self.sinks.iter_mut() {
+            sink.reset();
+        }
+    }
+
+    /// Flushes all outputs
+    pub fn flush(&mut self) {
+        for sink in self.sinks.iter_mut() {
+            sink.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Writer the test keeps a handle on
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, data: &[u8]) -> IoResult<usize> {
+            self.0.borrow_mut().extend_from_slice(data);
+            Ok(data.len())
+        }
+        fn flush(&mut self) -> IoResult<()> {
+            Ok(())
+        }
+    }
+
+    impl Shared {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    fn pvt(epoch: Epoch) -> Pvt {
+        Pvt {
+            dt: 1.0E-6,
+            nb_sv: 7,
+            quality: Some(87),
+            ..Pvt::fixture(epoch, (4_027_893.5, 307_045.25, 4_919_474.75))
+        }
+    }
+
+    #[test]
+    fn two_sinks_receive_the_same_fix() {
+        let (json, csv) = (Shared::default(), Shared::default());
+        let mut outputs = Outputs::new(&[], None, TimeScale::GPST);
+        outputs.push(Box::new(Stream::new(
+            Format::Json,
+            TimeScale::GPST,
+            TimestampFormat::Epoch,
+            json.clone(),
+        )));
+        outputs.push(Box::new(Stream::new(
+            Format::Csv,
+            TimeScale::GPST,
+            TimestampFormat::Week,
+            csv.clone(),
+        )));
+
+        let fix = pvt(Epoch::from_gregorian_utc_at_midnight(2024, 3, 1));
+        outputs.on_fix(&fix);
+        outputs.flush();
+
+        let json = json.text();
+        assert_eq!(json.lines().count(), 1);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["x"].as_f64(), Some(fix.position.0));
+        assert_eq!(value["nb_sv"].as_u64(), Some(7));
+        assert_eq!(value["quality"].as_u64(), Some(87));
+
+        // one header, one row, as many columns
+        let csv = csv.text();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let header = lines[0].split(',').collect::<Vec<_>>();
+        let row = lines[1].split(',').collect::<Vec<_>>();
+        assert_eq!(header.len(), row.len());
+        let column = |name: &str| row[header.iter().position(|h| *h == name).unwrap()];
+        assert_eq!(column("x").parse::<f64>().unwrap(), fix.position.0);
+        assert_eq!(column("nb_sv"), "7");
+        assert_eq!(column("quality"), "87");
+        assert_eq!(column("east"), "");
+    }
+
+    #[test]
+    fn epochs_in_requested_timescale() {
+        let t = Epoch::from_gregorian(2024, 3, 1, 0, 0, 0, 0, TimeScale::GPST);
+        for (timescale, expected) in [
+            (TimeScale::GPST, "2024-03-01T00:00:00 GPST"),
+            // 18 leap seconds behind
+            (TimeScale::UTC, "2024-02-29T23:59:42 UTC"),
+            // steered to GPST
+            (TimeScale::GST, "2024-03-01T00:00:00 GST"),
+        ] {
+            let json = Shared::default();
+            let mut stream = Stream::new(
+                Format::Json,
+                timescale,
+                TimestampFormat::Epoch,
+                json.clone(),
+            );
+            stream.on_fix(&pvt(t)).unwrap();
+            stream.flush();
+
+            let value: serde_json::Value = serde_json::from_str(&json.text()).unwrap();
+            let epoch = value["epoch"].as_str().unwrap();
+            assert_eq!(epoch, expected);
+            assert_eq!(Epoch::from_str(epoch).unwrap(), t);
+        }
+    }
+
+    /// Counts the calls it receives
+    #[derive(Default)]
+    struct Counter {
+        fixes: usize,
+        flushes: usize,
+        resets: usize,
+    }
+
+    struct MockSink(Rc<RefCell<Counter>>);
+
+    impl SolutionSink for MockSink {
+        fn on_fix(&mut self, _: &Pvt) -> IoResult<()> {
+            self.0.borrow_mut().fixes += 1;
+            Ok(())
+        }
+        fn flush(&mut self) {
+            self.0.borrow_mut().flushes += 1;
+        }
+        fn reset(&mut self) {
+            self.0.borrow_mut().resets += 1;
+        }
+    }
+
+    #[test]
+    fn every_sink_is_called() {
+        let counters = [Rc::default(), Rc::default()];
+        let mut outputs = Outputs::new(&[], None, TimeScale::GPST);
+        for counter in counters.iter() {
+            outputs.push(Box::new(MockSink(Rc::clone(counter))));
+        }
+
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        for i in 0..3 {
+            outputs.on_fix(&pvt(t0 + Duration::from_seconds(i as f64)));
+        }
+        outputs.reset();
+        outputs.flush();
+
+        for counter in counters.iter() {
+            let counter = counter.borrow();
+            assert_eq!(counter.fixes, 3);
+            assert_eq!(counter.resets, 1);
+            assert_eq!(counter.flushes, 1);
+        }
+    }
+
+    #[test]
+    fn throttled_dispatch() {
+        let counter = Rc::default();
+        let mut outputs = Outputs::new(&[], Some(Duration::from_seconds(1.0)), TimeScale::GPST);
+        outputs.push(Box::new(MockSink(Rc::clone(&counter))));
+
+        // 10 Hz solutions, dispatched at 1 Hz
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        for i in 0..25 {
+            outputs.on_fix(&pvt(t0 + Duration::from_milliseconds(100.0 * i as f64)));
+        }
+        assert_eq!(counter.borrow().fixes, 3);
+    }
+}
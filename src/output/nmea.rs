@@ -0,0 +1,36 @@
+//! NMEA sentences
+use crate::pvt::Pvt;
+
+/// Appends NMEA checksum to given sentence body
+fn checksum(body: &str) -> String {
+    let crc = body.bytes().fold(0_u8, |crc, b| crc ^ b);
+    format!("${}*{:02X}", body, crc)
+}
+
+/// Formats angle in degrees as NMEA (d)ddmm.mmmmm
+fn angle(value: f64, width: usize) -> String {
+    let value = value.abs();
+    let degrees = value.trunc();
+    let minutes = (value - degrees) * 60.0;
+    format!("{:0width$}{:08.5}", degrees as u32, minutes, width = width)
+}
+
+/// Encodes [Pvt] as NMEA GGA sentence
+pub fn gga(pvt: &Pvt) -> String {
+    let (_, _, _, hh, mm, ss, nanos) = pvt.epoch.to_gregorian_utc();
+    let (lat, lon, alt) = pvt.geodetic;
+    checksum(&format!(
+        "GPGGA,{:02}{:02}{:02}.{:02},{},{},{},{},1,{:02},{:.1},{:.2},M,,M,,",
+        hh,
+        mm,
+        ss,
+        nanos / 10_000_000,
+        angle(lat, 2),
+        if lat < 0.0 { 'S' } else { 'N' },
+        angle(lon, 3),
+        if lon < 0.0 { 'W' } else { 'E' },
+        pvt.nb_sv,
+        pvt.hdop,
+        alt,
+    ))
+}
@@ -0,0 +1,161 @@
+//! NTP shared memory reference clock, as read by the chrony and ntpd SHM drivers
+use std::{
+    io::{Error as IoError, Result as IoResult},
+    mem::size_of,
+    ptr::{addr_of_mut, null, write_volatile},
+    sync::atomic::{fence, Ordering},
+    time::{Duration as StdDuration, SystemTime, UNIX_EPOCH},
+};
+
+use gnss_rtk::prelude::Duration;
+
+use super::SolutionSink;
+use crate::pvt::Pvt;
+
+/// Segment key of unit 0 ("NTP0")
+const NTPD_BASE: i32 = 0x4e545030;
+
+/// Reported precision (log2 s): solutions are timestamped once resolved,
+/// so processing latency (a few ms) dominates
+const PRECISION: i32 = -10;
+
+/// SHM segment layout (struct shmTime)
+#[repr(C)]
+struct ShmTime {
+    mode: i32,
+    count: i32,
+    clock_sec: libc::time_t,
+    clock_usec: i32,
+    receive_sec: libc::time_t,
+    receive_usec: i32,
+    leap: i32,
+    precision: i32,
+    nsamples: i32,
+    valid: i32,
+    clock_nsec: u32,
+    receive_nsec: u32,
+    dummy: [i32; 8],
+}
+
+/// Publishes GNSS time (receiver time corrected by the resolved clock offset)
+/// along with system time, so the system clock may be disciplined.
+pub struct Shm {
+    segment: *mut ShmTime,
+}
+
+impl Shm {
+    /// Attaches to (or creates) SHM segment of given NTP unit
+    pub fn new(unit: u8) -> IoResult<Self> {
+        let id = unsafe {
+            libc::shmget(
+                NTPD_BASE + unit as i32,
+                size_of::<ShmTime>(),
+                libc::IPC_CREAT | 0o600,
+            )
+        };
+        if id < 0 {
+            return Err(IoError::last_os_error());
+        }
+        let segment = unsafe { libc::shmat(id, null(), 0) };
+        if segment as isize == -1 {
+            return Err(IoError::last_os_error());
+        }
+        info!("publishing time on NTP SHM unit {}", unit);
+        Ok(Self {
+            segment: segment as *mut ShmTime,
+        })
+    }
+}
+
+/// GNSS time of the measurement, as UNIX seconds and nanoseconds:
+/// receiver time tag, minus receiver clock offset
+fn clock_time(pvt: &Pvt) -> (i64, u32) {
+    let nanos = (pvt.epoch - Duration::from_seconds(pvt.dt))
+        .to_unix_duration()
+        .total_nanoseconds();
+    (
+        nanos.div_euclid(1_000_000_000) as i64,
+        nanos.rem_euclid(1_000_000_000) as u32,
+    )
+}
+
+/// Offset (s) of GNSS time to system time (since UNIX epoch)
+fn system_offset((clock_sec, clock_nsec): (i64, u32), received: StdDuration) -> f64 {
+    (clock_sec - received.as_secs() as i64) as f64
+        + (clock_nsec as f64 - received.subsec_nanos() as f64) * 1.0E-9
+}
+
+impl SolutionSink for Shm {
+    fn on_fix(&mut self, pvt: &Pvt) -> IoResult<()> {
+        let received = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(IoError::other)?;
+
+        let (clock_sec, clock_nsec) = clock_time(pvt);
+
+        // mode 1: count is incremented prior and after the update
+        unsafe {
+            let shm = self.segment;
+            write_volatile(addr_of_mut!((*shm).mode), 1);
+            write_volatile(addr_of_mut!((*shm).valid), 0);
+            write_volatile(addr_of_mut!((*shm).count), (*shm).count.wrapping_add(1));
+            fence(Ordering::SeqCst);
+            write_volatile(addr_of_mut!((*shm).clock_sec), clock_sec as libc::time_t);
+            write_volatile(addr_of_mut!((*shm).clock_usec), (clock_nsec / 1000) as i32);
+            write_volatile(addr_of_mut!((*shm).clock_nsec), clock_nsec);
+            write_volatile(
+                addr_of_mut!((*shm).receive_sec),
+                received.as_secs() as libc::time_t,
+            );
+            write_volatile(
+                addr_of_mut!((*shm).receive_usec),
+                received.subsec_micros() as i32,
+            );
+            write_volatile(addr_of_mut!((*shm).receive_nsec), received.subsec_nanos());
+            write_volatile(addr_of_mut!((*shm).leap), 0);
+            write_volatile(addr_of_mut!((*shm).precision), PRECISION);
+            fence(Ordering::SeqCst);
+            write_volatile(addr_of_mut!((*shm).count), (*shm).count.wrapping_add(1));
+            write_volatile(addr_of_mut!((*shm).valid), 1);
+        }
+
+        debug!(
+            "{} system clock offset: {:.6}s",
+            pvt.epoch,
+            system_offset((clock_sec, clock_nsec), received)
+        );
+        Ok(())
+    }
+    fn flush(&mut self) {}
+}
+
+impl Drop for Shm {
+    fn drop(&mut self) {
+        unsafe {
+            libc::shmdt(self.segment as *const libc::c_void);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Epoch;
+
+    #[test]
+    fn offset_to_system_time() {
+        // 2024-03-01T12:00:00 UTC, receiver clock 2.5 ms late
+        let t = Epoch::from_gregorian_utc(2024, 3, 1, 12, 0, 0, 0);
+        let mut pvt = Pvt::fixture(t, (4_201_575.0, 189_856.0, 4_779_066.0));
+        pvt.dt = 2.5E-3;
+
+        let (clock_sec, clock_nsec) = clock_time(&pvt);
+        assert_eq!(clock_sec, 1_709_294_399);
+        assert!(clock_nsec.abs_diff(997_500_000) <= 1, "{}", clock_nsec);
+
+        // system clock 10.25 ms ahead of GNSS time
+        let received = StdDuration::new(1_709_294_400, 7_750_000);
+        let offset = system_offset((clock_sec, clock_nsec), received);
+        assert!((offset + 10.25E-3).abs() < 1.0E-8, "{}", offset);
+    }
+}
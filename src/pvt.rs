@@ -0,0 +1,522 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+use gnss_rtk::prelude::{Epoch, PVTSolution, SV};
+
+use crate::{
+    coords::{ecef_to_geodetic, geodetic_to_ecef, WGS84_A, WGS84_F},
+    datum::Helmert,
+    Error,
+};
+
+/// UTM scale factor on the central meridian
+const UTM_K0: f64 = 0.9996;
+/// UTM false easting (m)
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+/// UTM false northing (m), southern hemisphere
+const UTM_FALSE_NORTHING: f64 = 10_000_000.0;
+
+/// Heading is not reported below this speed (m/s), where it is mostly noise
+pub const MIN_HEADING_SPEED: f64 = 0.5;
+
+/// Local East North Up frame origin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnuOrigin {
+    /// Latitude (ddeg)
+    pub lat: f64,
+    /// Longitude (ddeg)
+    pub lon: f64,
+    /// Altitude above ellipsoid (m)
+    pub alt: f64,
+}
+
+impl FromStr for EnuOrigin {
+    type Err = Error;
+    /// Parses "lat,lon,alt" (ddeg, ddeg, m)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let items = s
+            .split(',')
+            .map(|item| item.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::InvalidOrigin(s.to_string()))?;
+        match items[..] {
+            [lat, lon, alt] if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => {
+                Ok(Self { lat, lon, alt })
+            },
+            _ => Err(Error::InvalidOrigin(s.to_string())),
+        }
+    }
+}
+
+impl EnuOrigin {
+    /// Projects ECEF coordinates (m) to local East North Up (m)
+    pub fn enu(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+        let (x0, y0, z0) = geodetic_to_ecef(self.lat, self.lon, self.alt);
+        let (dx, dy, dz) = (x - x0, y - y0, z - z0);
+        let (lat, lon) = (self.lat.to_radians(), self.lon.to_radians());
+        let east = -lon.sin() * dx + lon.cos() * dy;
+        let north = -lat.sin() * lon.cos() * dx - lat.sin() * lon.sin() * dy + lat.cos() * dz;
+        let up = lat.cos() * lon.cos() * dx + lat.cos() * lon.sin() * dy + lat.sin() * dz;
+        (east, north, up)
+    }
+}
+
+/// Universal Transverse Mercator coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utm {
+    /// Easting (m)
+    pub easting: f64,
+    /// Northing (m)
+    pub northing: f64,
+    /// Zone (1-60)
+    pub zone: u8,
+    /// Northern hemisphere
+    pub north: bool,
+}
+
+impl Display for Utm {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let hemisphere = if self.north { 'N' } else { 'S' };
+        write!(f, "{}{}", self.zone, hemisphere)
+    }
+}
+
+/// Returns UTM zone of given latitude and longitude (ddeg),
+/// including the Norway and Svalbard exceptions
+fn utm_zone(lat: f64, lon: f64) -> u8 {
+    match (lat, lon) {
+        (56.0..=64.0, 3.0..=12.0) => 32,
+        (72.0..=84.0, 0.0..=9.0) => 31,
+        (72.0..=84.0, 9.0..=21.0) => 33,
+        (72.0..=84.0, 21.0..=33.0) => 35,
+        (72.0..=84.0, 33.0..=42.0) => 37,
+        _ => (((lon + 180.0) / 6.0).floor() as i32).rem_euclid(60) as u8 + 1,
+    }
+}
+
+impl Utm {
+    /// Projects latitude and longitude (ddeg) in given `zone`, or the zone they belong to,
+    /// using the Krüger series (6th order in n, sub-millimeter within the zone)
+    pub fn from_geodetic(lat: f64, lon: f64, zone: Option<u8>) -> Self {
+        let zone = zone.unwrap_or_else(|| utm_zone(lat, lon));
+        let lon0 = (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0;
+        let (phi, dlambda) = (lat.to_radians(), (lon - lon0).to_radians());
+
+        let n = WGS84_F / (2.0 - WGS84_F);
+        let (n2, n3, n4, n5, n6) = (n.powi(2), n.powi(3), n.powi(4), n.powi(5), n.powi(6));
+        // rectifying radius
+        let a = WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n4 / 64.0 + n6 / 256.0);
+        let alpha = [
+            n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0 + 41.0 * n4 / 180.0 - 127.0 * n5 / 288.0
+                + 7891.0 * n6 / 37800.0,
+            13.0 * n2 / 48.0 - 3.0 * n3 / 5.0 + 557.0 * n4 / 1440.0 + 281.0 * n5 / 630.0
+                - 1983433.0 * n6 / 1935360.0,
+            61.0 * n3 / 240.0 - 103.0 * n4 / 140.0
+                + 15061.0 * n5 / 26880.0
+                + 167603.0 * n6 / 181440.0,
+            49561.0 * n4 / 161280.0 - 179.0 * n5 / 168.0 + 6601661.0 * n6 / 7257600.0,
+            34729.0 * n5 / 80640.0 - 3418889.0 * n6 / 1995840.0,
+            212378941.0 * n6 / 319334400.0,
+        ];
+
+        // conformal latitude
+        let e = (WGS84_F * (2.0 - WGS84_F)).sqrt();
+        let t = (phi.sin().atanh() - e * (e * phi.sin()).atanh()).sinh();
+        let xi = t.atan2(dlambda.cos());
+        let eta = (dlambda.sin() / (1.0 + t.powi(2)).sqrt()).atanh();
+
+        let (mut x, mut y) = (eta, xi);
+        for (j, alpha) in alpha.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            x += alpha * (k * xi).cos() * (k * eta).sinh();
+            y += alpha * (k * xi).sin() * (k * eta).cosh();
+        }
+
+        let north = lat >= 0.0;
+        Self {
+            easting: UTM_FALSE_EASTING + UTM_K0 * a * x,
+            northing: UTM_K0 * a * y + if north { 0.0 } else { UTM_FALSE_NORTHING },
+            zone,
+            north,
+        }
+    }
+}
+
+/// Resolved Position Velocity Time, as handed over to the outputs
+#[derive(Debug, Clone)]
+pub struct Pvt {
+    /// Sampling [Epoch]
+    pub epoch: Epoch,
+    /// Position (ECEF) in meters
+    pub position: (f64, f64, f64),
+    /// Latitude (ddeg), longitude (ddeg) and altitude above ellipsoid (m)
+    pub geodetic: (f64, f64, f64),
+    /// Velocity (ECEF) in meters per second
+    pub velocity: (f64, f64, f64),
+    /// Clock offset to timescale, in seconds
+    pub dt: f64,
+    /// Clock drift (s/s), when estimated by the clock model
+    pub drift: Option<f64>,
+    /// Number of SV that contributed
+    pub nb_sv: usize,
+    /// Elevation and azimuth (degrees) of each contributing [SV], sorted by [SV]
+    pub sv: Vec<(SV, f64, f64)>,
+    /// Geometric Dilution of Precision
+    pub gdop: f64,
+    /// Horizontal Dilution of Precision
+    pub hdop: f64,
+    /// Vertical Dilution of Precision
+    pub vdop: f64,
+    /// Position Dilution of Precision
+    pub pdop: f64,
+    /// Time Dilution of Precision
+    pub tdop: f64,
+    /// Horizontal speed (m/s)
+    pub speed: f64,
+    /// Heading (degrees, clockwise from north), unless standing still
+    pub heading: Option<f64>,
+    /// Range rates (m/s) measured by Doppler, per [SV]
+    pub range_rates: Vec<(SV, f64)>,
+    /// East, North and Up (m) with respect to the [EnuOrigin], when defined
+    pub enu: Option<(f64, f64, f64)>,
+    /// UTM coordinates, when requested
+    pub utm: Option<Utm>,
+    /// Composite quality score (0-100)
+    pub quality: Option<u8>,
+}
+
+impl From<(Epoch, PVTSolution)> for Pvt {
+    fn from((epoch, solution): (Epoch, PVTSolution)) -> Self {
+        let position = (
+            solution.position.x,
+            solution.position.y,
+            solution.position.z,
+        );
+        let (lat, lon, _) = ecef_to_geodetic(position.0, position.1, position.2);
+        let (lat, lon) = (lat.to_radians(), lon.to_radians());
+        let sv = solution
+            .sv
+            .iter()
+            .map(|(sv, input)| (*sv, input.elevation, input.azimuth))
+            .collect::<Vec<_>>();
+        Self::solved(
+            epoch,
+            position,
+            (
+                solution.velocity.x,
+                solution.velocity.y,
+                solution.velocity.z,
+            ),
+            solution.dt.to_seconds(),
+            sv,
+            (
+                solution.gdop,
+                solution.hdop(lat, lon),
+                solution.vdop(lat, lon),
+                solution.pdop,
+                solution.tdop,
+            ),
+        )
+    }
+}
+
+impl Pvt {
+    /// Builds a solution from the solver outputs: ECEF position (m) and velocity (m/s),
+    /// clock offset (s), elevation and azimuth of each contributing [SV],
+    /// and DOPs (geometric, horizontal, vertical, position, time)
+    fn solved(
+        epoch: Epoch,
+        position: (f64, f64, f64),
+        velocity: (f64, f64, f64),
+        dt: f64,
+        mut sv: Vec<(SV, f64, f64)>,
+        (gdop, hdop, vdop, pdop, tdop): (f64, f64, f64, f64, f64),
+    ) -> Self {
+        let geodetic = ecef_to_geodetic(position.0, position.1, position.2);
+        sv.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // ECEF velocity, projected to local east and north
+        let (lat, lon) = (geodetic.0.to_radians(), geodetic.1.to_radians());
+        let (vel_x, vel_y, vel_z) = velocity;
+        let east = -lon.sin() * vel_x + lon.cos() * vel_y;
+        let north =
+            -lat.sin() * lon.cos() * vel_x - lat.sin() * lon.sin() * vel_y + lat.cos() * vel_z;
+        let speed = east.hypot(north);
+
+        Self {
+            epoch,
+            position,
+            geodetic,
+            velocity,
+            dt,
+            drift: None,
+            nb_sv: sv.len(),
+            sv,
+            gdop,
+            hdop,
+            vdop,
+            pdop,
+            tdop,
+            speed,
+            heading: if speed < MIN_HEADING_SPEED {
+                None
+            } else {
+                Some(east.atan2(north).to_degrees().rem_euclid(360.0))
+            },
+            range_rates: Vec::new(),
+            enu: None,
+            utm: None,
+            quality: None,
+        }
+    }
+
+    /// Returns horizontal and vertical offsets (m) to given geodetic coordinates (ddeg, m),
+    /// in the local frame of this solution
+    pub fn offset_to(&self, lat: f64, lon: f64, alt: f64) -> (f64, f64) {
+        let (lat0, lon0, alt0) = self.geodetic;
+        let origin = EnuOrigin {
+            lat: lat0,
+            lon: lon0,
+            alt: alt0,
+        };
+        let (x, y, z) = geodetic_to_ecef(lat, lon, alt);
+        let (east, north, up) = origin.enu(x, y, z);
+        (east.hypot(north), up)
+    }
+
+    /// Compensates antenna cable delay (s). The cable delays all signals equally,
+    /// which the solver sees as a receiver clock running late:
+    /// the delay is removed from the clock offset.
+    pub fn with_cable_delay(mut self, delay: f64) -> Self {
+        self.dt -= delay;
+        self
+    }
+
+    /// Expresses this solution in a regional datum
+    pub fn with_datum(mut self, helmert: &Helmert) -> Self {
+        self.position = helmert.transform(self.epoch, self.position);
+        let (x, y, z) = self.position;
+        self.geodetic = ecef_to_geodetic(x, y, z);
+        self
+    }
+
+    /// Expresses this solution in the local frame of given [EnuOrigin]
+    pub fn with_enu_origin(mut self, origin: &EnuOrigin) -> Self {
+        let (x, y, z) = self.position;
+        self.enu = Some(origin.enu(x, y, z));
+        self
+    }
+
+    /// Expresses this solution in UTM coordinates, in given zone or the one it belongs to
+    pub fn with_utm(mut self, zone: Option<u8>) -> Self {
+        let (lat, lon, _) = self.geodetic;
+        self.utm = Some(Utm::from_geodetic(lat, lon, zone));
+        self
+    }
+
+    /// Reports range rates measured by Doppler
+    pub fn report_range_rates(&self) {
+        for (sv, rate) in self.range_rates.iter() {
+            debug!("{} {} range rate={:.3}m/s", self.epoch, sv, rate);
+        }
+    }
+}
+
+impl Display for Pvt {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let (x, y, z) = self.position;
+        let (vel_x, vel_y, vel_z) = self.velocity;
+        write!(
+            f,
+            "{} x={}, y={}, z={}, vel_x={}, vel_y={}, vel_z={}, speed={:.3}, ",
+            self.epoch, x, y, z, vel_x, vel_y, vel_z, self.speed
+        )?;
+        match self.heading {
+            Some(heading) => write!(f, "heading={:.2}, ", heading)?,
+            None => write!(f, "heading=none, ")?,
+        }
+        write!(f, "dt={}", self.dt)?;
+        if let Some(drift) = self.drift {
+            write!(f, ", drift={:e}", drift)?;
+        }
+        if let Some((east, north, up)) = self.enu {
+            write!(f, ", east={:.4}, north={:.4}, up={:.4}", east, north, up)?;
+        }
+        if let Some(utm) = self.utm {
+            write!(
+                f,
+                ", utm={} easting={:.4} northing={:.4}",
+                utm, utm.easting, utm.northing
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Pvt {
+    /// Static solution at `position` (ECEF), for tests
+    pub fn fixture(epoch: Epoch, position: (f64, f64, f64)) -> Self {
+        Self {
+            epoch,
+            position,
+            geodetic: ecef_to_geodetic(position.0, position.1, position.2),
+            velocity: (0.0, 0.0, 0.0),
+            dt: 0.0,
+            drift: None,
+            nb_sv: 8,
+            sv: Vec::new(),
+            gdop: 1.9,
+            hdop: 0.9,
+            vdop: 1.4,
+            pdop: 1.7,
+            tdop: 0.8,
+            speed: 0.0,
+            heading: None,
+            range_rates: Vec::new(),
+            enu: None,
+            utm: None,
+            quality: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Constellation;
+
+    /// Asserts easting and northing (m) to the millimeter
+    fn assert_utm(utm: Utm, easting: f64, northing: f64) {
+        assert!((utm.easting - easting).abs() < 1.0E-3, "{:?}", utm);
+        assert!((utm.northing - northing).abs() < 1.0E-3, "{:?}", utm);
+    }
+
+    #[test]
+    fn utm_central_meridian() {
+        // on the central meridian: easting is the false easting,
+        // northing is the scaled meridian arc (4984944.378m at 45°)
+        assert_utm(Utm::from_geodetic(0.0, 3.0, None), 500_000.0, 0.0);
+        assert_utm(
+            Utm::from_geodetic(45.0, 9.0, None),
+            500_000.0,
+            4_982_950.400,
+        );
+        assert_utm(
+            Utm::from_geodetic(-45.0, -57.0, None),
+            500_000.0,
+            5_017_049.600,
+        );
+    }
+
+    #[test]
+    fn utm_reference_points() {
+        // 0°N 0°E
+        let utm = Utm::from_geodetic(0.0, 0.0, None);
+        assert_eq!(utm.to_string(), "31N");
+        assert_utm(utm, 166_021.443, 0.0);
+
+        // GeographicLib GeoConvert example: 33.3°N 44.4°E, published to the centimeter
+        let utm = Utm::from_geodetic(33.3, 44.4, None);
+        assert_eq!(utm.to_string(), "38N");
+        assert!((utm.easting - 444_140.54).abs() < 5.0E-3);
+        assert!((utm.northing - 3_684_706.36).abs() < 5.0E-3);
+
+        // forced zone: same point, west of its zone
+        let utm = Utm::from_geodetic(0.0, 0.0, Some(30));
+        assert_eq!(utm.to_string(), "30N");
+        assert_utm(utm, 833_978.557, 0.0);
+    }
+
+    #[test]
+    fn utm_zone_exceptions() {
+        assert_eq!(utm_zone(60.0, 5.0), 32);
+        assert_eq!(utm_zone(60.0, 2.0), 31);
+        assert_eq!(utm_zone(78.0, 10.0), 33);
+        assert_eq!(utm_zone(-33.9, 18.4), 34);
+        assert_eq!(utm_zone(0.0, 180.0), 1);
+        assert_eq!(utm_zone(0.0, -180.0), 1);
+    }
+
+    #[test]
+    fn cable_delay_shifts_clock_offset() {
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let mut pvt = Pvt::fixture(t, (4_201_575.0, 189_856.0, 4_779_066.0));
+        pvt.dt = 1.0E-6;
+
+        // 50ns cable: the clock is that much less late
+        let pvt = pvt.with_cable_delay(50.0E-9);
+        assert!((pvt.dt - 0.95E-6).abs() < 1.0E-15);
+        assert_eq!(pvt.position, (4_201_575.0, 189_856.0, 4_779_066.0));
+    }
+
+    #[test]
+    fn solved_from_solver_outputs() {
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let g02 = SV::new(Constellation::GPS, 2);
+        let g08 = SV::new(Constellation::GPS, 8);
+        let e11 = SV::new(Constellation::Galileo, 11);
+
+        // 100m above 0°N 0°E, moving 3m/s east and 4m/s north
+        let pvt = Pvt::solved(
+            t,
+            (WGS84_A + 100.0, 0.0, 0.0),
+            (0.0, 3.0, 4.0),
+            1.0E-6,
+            vec![(e11, 30.0, 270.0), (g08, 60.0, 45.0), (g02, 15.0, 180.0)],
+            (1.9, 0.9, 1.4, 1.7, 0.8),
+        );
+
+        assert_eq!(pvt.epoch, t);
+        assert_eq!(pvt.position, (WGS84_A + 100.0, 0.0, 0.0));
+        let (lat, lon, alt) = pvt.geodetic;
+        assert!(lat.abs() < 1.0E-9 && lon.abs() < 1.0E-9);
+        assert!((alt - 100.0).abs() < 1.0E-6);
+        assert_eq!(pvt.dt, 1.0E-6);
+
+        assert_eq!(pvt.nb_sv, 3);
+        assert_eq!(
+            pvt.sv,
+            vec![(g02, 15.0, 180.0), (g08, 60.0, 45.0), (e11, 30.0, 270.0)]
+        );
+
+        assert_eq!(pvt.gdop, 1.9);
+        assert_eq!(pvt.hdop, 0.9);
+        assert_eq!(pvt.vdop, 1.4);
+        assert_eq!(pvt.pdop, 1.7);
+        assert_eq!(pvt.tdop, 0.8);
+
+        assert!((pvt.speed - 5.0).abs() < 1.0E-9);
+        let heading = pvt.heading.unwrap();
+        assert!((heading - 3.0_f64.atan2(4.0).to_degrees()).abs() < 1.0E-9);
+    }
+
+    /// Asserts ENU coordinates to the millimeter
+    fn assert_enu(enu: (f64, f64, f64), expected: (f64, f64, f64)) {
+        assert!((enu.0 - expected.0).abs() < 1.0E-3, "{:?}", enu);
+        assert!((enu.1 - expected.1).abs() < 1.0E-3, "{:?}", enu);
+        assert!((enu.2 - expected.2).abs() < 1.0E-3, "{:?}", enu);
+    }
+
+    #[test]
+    fn enu_from_ecef() {
+        // on the equator: ECEF y points east, z north and x up
+        let origin = EnuOrigin::from_str("0,0,0").unwrap();
+        assert_enu(origin.enu(WGS84_A + 10.0, 5.0, 3.0), (5.0, 3.0, 10.0));
+
+        // 90°E: x points west
+        let origin = EnuOrigin::from_str("0, 90, 0").unwrap();
+        assert_enu(origin.enu(-2.0, WGS84_A, 0.0), (2.0, 0.0, 0.0));
+
+        // 100 m above the origin, along the ellipsoid normal
+        let origin = EnuOrigin::from_str("45,45,120").unwrap();
+        let (x, y, z) = geodetic_to_ecef(45.0, 45.0, 220.0);
+        assert_enu(origin.enu(x, y, z), (0.0, 0.0, 100.0));
+
+        for invalid in ["45,45", "91,0,0", "0,181,0", "a,b,c"] {
+            assert!(EnuOrigin::from_str(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Composite fix quality score
+use gnss_rtk::prelude::Epoch;
+use serde::Deserialize;
+
+use crate::{pvt::Pvt, Error};
+
+/// Relative weight of each input in the score. They need not sum to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct QualityWeights {
+    pub gdop: f64,
+    pub nb_sv: f64,
+    pub age: f64,
+}
+
+impl Default for QualityWeights {
+    fn default() -> Self {
+        Self {
+            gdop: 0.4,
+            nb_sv: 0.3,
+            age: 0.2,
+        }
+    }
+}
+
+/// Fix quality score configuration, as described in the configuration file (JSON).
+/// Each input scores from 0 to 1, linearly between its worst and best values:
+///   - GDOP: 1 is best, `max_gdop` is worst
+///   - number of SV: `good_nb_sv` is best, 4 is worst
+///   - fix age (time elapsed since previous fix): 0 is best, `max_age` is worst
+///
+/// The score is the weighted average of these, scaled to 0-100.
+/// Post fit residuals, covariance and the ambiguity fix status are not exposed by the solver,
+/// hence not part of the score.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct QualityConfig {
+    pub max_gdop: f64,
+    pub good_nb_sv: usize,
+    /// Seconds
+    pub max_age: f64,
+    /// [QualityWeights]
+    pub weights: QualityWeights,
+    /// Scores reaching this are rated good
+    pub good: u8,
+    /// Scores reaching this are rated fair, otherwise poor
+    pub fair: u8,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            max_gdop: 10.0,
+            good_nb_sv: 12,
+            max_age: 10.0,
+            weights: QualityWeights::default(),
+            good: 70,
+            fair: 40,
+        }
+    }
+}
+
+impl QualityConfig {
+    /// Verifies all parameters are within range
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.max_gdop <= 1.0 {
+            return Err(Error::InvalidConfig(format!(
+                "quality max_gdop {} should exceed 1",
+                self.max_gdop
+            )));
+        }
+        if self.good_nb_sv <= 4 {
+            return Err(Error::InvalidConfig(format!(
+                "quality good_nb_sv {} should exceed 4",
+                self.good_nb_sv
+            )));
+        }
+        if self.max_age <= 0.0 {
+            return Err(Error::InvalidConfig(format!(
+                "quality max_age {} should be positive",
+                self.max_age
+            )));
+        }
+        let w = &self.weights;
+        if [w.gdop, w.nb_sv, w.age].iter().any(|weight| *weight < 0.0)
+            || w.gdop + w.nb_sv + w.age <= 0.0
+        {
+            return Err(Error::InvalidConfig(
+                "quality weights should be positive".to_string(),
+            ));
+        }
+        if self.fair > self.good || self.good > 100 {
+            return Err(Error::InvalidConfig(format!(
+                "quality ratings {}/{} should be ordered, within 0-100",
+                self.fair, self.good
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Rating of a quality score
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rating {
+    Good,
+    Fair,
+    Poor,
+}
+
+/// Scores successive fixes
+#[derive(Debug)]
+pub struct Quality {
+    cfg: QualityConfig,
+    /// Previous fix [Epoch]
+    last: Option<Epoch>,
+}
+
+/// Scores `value` from 0 (at `worst`) to 1 (at `best`)
+fn linear(value: f64, worst: f64, best: f64) -> f64 {
+    ((value - worst) / (best - worst)).clamp(0.0, 1.0)
+}
+
+impl Quality {
+    pub fn new(cfg: QualityConfig) -> Self {
+        Self { cfg, last: None }
+    }
+
+    /// Scores new fix, from 0 to 100
+    pub fn score(&mut self, pvt: &Pvt) -> u8 {
+        let age = match self.last.replace(pvt.epoch) {
+            Some(last) => (pvt.epoch - last).to_seconds().max(0.0),
+            None => 0.0,
+        };
+        let w = &self.cfg.weights;
+        let scores = [
+            (w.gdop, linear(pvt.gdop, self.cfg.max_gdop, 1.0)),
+            (
+                w.nb_sv,
+                linear(pvt.nb_sv as f64, 4.0, self.cfg.good_nb_sv as f64),
+            ),
+            (w.age, linear(age, self.cfg.max_age, 0.0)),
+        ];
+        let total = scores.iter().map(|(weight, _)| weight).sum::<f64>();
+        let score = scores
+            .iter()
+            .map(|(weight, score)| weight * score)
+            .sum::<f64>()
+            / total;
+        (score * 100.0).round() as u8
+    }
+
+    /// Rates this score
+    pub fn rating(&self, score: u8) -> Rating {
+        if score >= self.cfg.good {
+            Rating::Good
+        } else if score >= self.cfg.fair {
+            Rating::Fair
+        } else {
+            Rating::Poor
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Duration;
+
+    /// Score of a fix, `age` seconds after the previous one
+    fn score(gdop: f64, nb_sv: usize, age: f64) -> u8 {
+        let mut quality = Quality::new(QualityConfig::default());
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let pvt = |t| Pvt {
+            gdop,
+            nb_sv,
+            ..Pvt::fixture(t, (4_201_575.0, 189_856.0, 4_779_066.0))
+        };
+        quality.score(&pvt(t0));
+        quality.score(&pvt(t0 + Duration::from_seconds(age)))
+    }
+
+    /// Asserts scores decrease, strictly while within the input range
+    fn assert_decreasing(scores: &[u8]) {
+        for pair in scores.windows(2) {
+            assert!(pair[1] < pair[0] || pair[1] == 0, "{:?}", scores);
+        }
+    }
+
+    #[test]
+    fn degraded_inputs_lower_the_score() {
+        assert_eq!(score(1.0, 12, 0.0), 100);
+
+        let gdop = [1.0, 2.0, 4.0, 8.0, 9.9]
+            .map(|gdop| score(gdop, 12, 0.0))
+            .to_vec();
+        assert_decreasing(&gdop);
+        // the other inputs still score
+        assert_eq!(score(12.0, 12, 0.0), 56);
+
+        let nb_sv = [12, 10, 8, 6, 5]
+            .map(|nb_sv| score(1.0, nb_sv, 0.0))
+            .to_vec();
+        assert_decreasing(&nb_sv);
+        assert_eq!(score(1.0, 4, 0.0), score(1.0, 3, 0.0));
+
+        let age = [0.0, 1.0, 3.0, 6.0, 9.0]
+            .map(|age| score(1.0, 12, age))
+            .to_vec();
+        assert_decreasing(&age);
+        assert_eq!(score(1.0, 12, 15.0), 78);
+    }
+
+    #[test]
+    fn ratings() {
+        let quality = Quality::new(QualityConfig::default());
+        assert_eq!(quality.rating(100), Rating::Good);
+        assert_eq!(quality.rating(70), Rating::Good);
+        assert_eq!(quality.rating(69), Rating::Fair);
+        assert_eq!(quality.rating(40), Rating::Fair);
+        assert_eq!(quality.rating(39), Rating::Poor);
+    }
+}
@@ -0,0 +1,151 @@
+//! Moving baseline (UBX-NAV-RELPOSNED), not supported by the ublox crate yet
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// UBX-NAV-RELPOSNED class
+pub const CLASS: u8 = 0x01;
+
+/// UBX-NAV-RELPOSNED ID
+pub const ID: u8 = 0x3C;
+
+/// Version 1 payload length (protocol 27 and later)
+const PAYLOAD_LEN: usize = 64;
+
+/// Carrier phase range solution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CarrierSolution {
+    None,
+    Float,
+    Fixed,
+}
+
+/// Relative position of the rover antenna, with respect to the (moving) base
+#[derive(Debug, Clone, Copy)]
+pub struct RelPos {
+    /// North component (m)
+    pub n: f64,
+    /// East component (m)
+    pub e: f64,
+    /// Down component (m)
+    pub d: f64,
+    /// Baseline length (m)
+    pub length: f64,
+    /// Baseline heading (degrees)
+    pub heading: f64,
+    /// Heading accuracy (degrees)
+    pub heading_acc: f64,
+    /// Relative position is valid
+    pub valid: bool,
+    /// Heading is valid
+    pub heading_valid: bool,
+    /// [CarrierSolution]
+    pub carrier: CarrierSolution,
+}
+
+fn i32_at(payload: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes([
+        payload[offset],
+        payload[offset + 1],
+        payload[offset + 2],
+        payload[offset + 3],
+    ])
+}
+
+fn u32_at(payload: &[u8], offset: usize) -> u32 {
+    i32_at(payload, offset) as u32
+}
+
+impl RelPos {
+    /// Decodes version 1 payload. Returns None on other versions.
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        if payload.len() != PAYLOAD_LEN || payload[0] != 0x01 {
+            return None;
+        }
+
+        // cm + 0.1 mm high precision component
+        let component = |offset: usize, hp: usize| -> f64 {
+            i32_at(payload, offset) as f64 * 1.0E-2 + (payload[hp] as i8) as f64 * 1.0E-4
+        };
+
+        let flags = u32_at(payload, 60);
+
+        Some(Self {
+            n: component(8, 32),
+            e: component(12, 33),
+            d: component(16, 34),
+            length: component(20, 35),
+            heading: i32_at(payload, 24) as f64 * 1.0E-5,
+            heading_acc: u32_at(payload, 52) as f64 * 1.0E-5,
+            valid: flags & 0x04 != 0,
+            heading_valid: flags & 0x100 != 0,
+            carrier: match (flags >> 3) & 0x03 {
+                1 => CarrierSolution::Float,
+                2 => CarrierSolution::Fixed,
+                _ => CarrierSolution::None,
+            },
+        })
+    }
+}
+
+impl Display for RelPos {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "n={:.4}m e={:.4}m d={:.4}m length={:.4}m heading={:.3}° (±{:.3}°) carrier={:?}",
+            self.n, self.e, self.d, self.length, self.heading, self.heading_acc, self.carrier
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(flags: u32) -> Vec<u8> {
+        let mut payload = vec![0; PAYLOAD_LEN];
+        payload[0] = 0x01;
+        // N, E, D, length (cm)
+        payload[8..12].copy_from_slice(&123_456_i32.to_le_bytes());
+        payload[12..16].copy_from_slice(&(-250_i32).to_le_bytes());
+        payload[16..20].copy_from_slice(&7_i32.to_le_bytes());
+        payload[20..24].copy_from_slice(&123_458_i32.to_le_bytes());
+        // heading (1e-5 deg)
+        payload[24..28].copy_from_slice(&4_500_000_i32.to_le_bytes());
+        // high precision components (0.1 mm)
+        payload[32..36].copy_from_slice(&[(-12_i8) as u8, 34, 0, 5]);
+        // heading accuracy (1e-5 deg)
+        payload[52..56].copy_from_slice(&25_000_u32.to_le_bytes());
+        payload[60..64].copy_from_slice(&flags.to_le_bytes());
+        payload
+    }
+
+    #[test]
+    fn decode_fixed_heading() {
+        // gnssFixOK, diffSoln, relPosValid, carrSoln fixed, relPosHeadingValid
+        let relpos = RelPos::decode(&payload(0x117)).unwrap();
+        assert!((relpos.n - 1234.5588).abs() < 1.0E-9);
+        assert!((relpos.e + 2.4966).abs() < 1.0E-9);
+        assert!((relpos.d - 0.07).abs() < 1.0E-9);
+        assert!((relpos.length - 1234.5805).abs() < 1.0E-9);
+        assert!((relpos.heading - 45.0).abs() < 1.0E-9);
+        assert!((relpos.heading_acc - 0.25).abs() < 1.0E-9);
+        assert!(relpos.valid);
+        assert!(relpos.heading_valid);
+        assert_eq!(relpos.carrier, CarrierSolution::Fixed);
+    }
+
+    #[test]
+    fn decode_float_without_heading() {
+        let relpos = RelPos::decode(&payload(0x0F)).unwrap();
+        assert!(relpos.valid);
+        assert!(!relpos.heading_valid);
+        assert_eq!(relpos.carrier, CarrierSolution::Float);
+    }
+
+    #[test]
+    fn other_versions_rejected() {
+        let mut version_0 = payload(0x117);
+        version_0[0] = 0x00;
+        assert!(RelPos::decode(&version_0).is_none());
+        assert!(RelPos::decode(&payload(0x117)[..40]).is_none());
+    }
+}
@@ -0,0 +1,114 @@
+//! Solver reset strategy
+use serde::Deserialize;
+
+/// When to reset the solver, discarding its filter state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetStrategy {
+    /// Preserve the solver state, whatever happens
+    #[default]
+    Never,
+    /// Reset on every resolution error
+    Always,
+    /// Reset on this many consecutive resolution errors
+    AfterNErrors(u32),
+    /// Reset when a fix lands further than this distance (m) from the previous one
+    OnBigJump(f64),
+}
+
+/// Applies the [ResetStrategy] to the resolution outcomes
+#[derive(Debug, Default)]
+pub struct SolverReset {
+    strategy: ResetStrategy,
+    /// Consecutive resolution errors
+    errors: u32,
+    /// Previous fix position (ECEF, m)
+    last: Option<(f64, f64, f64)>,
+}
+
+impl SolverReset {
+    pub fn new(strategy: ResetStrategy) -> Self {
+        Self {
+            strategy,
+            errors: 0,
+            last: None,
+        }
+    }
+
+    /// New resolution error: returns true if the solver should be reset
+    pub fn on_error(&mut self) -> bool {
+        self.errors += 1;
+        let reset = match self.strategy {
+            ResetStrategy::Always => true,
+            ResetStrategy::AfterNErrors(n) => self.errors >= n,
+            ResetStrategy::Never | ResetStrategy::OnBigJump(_) => false,
+        };
+        if reset {
+            self.clear();
+        }
+        reset
+    }
+
+    /// New fix (ECEF, m): returns true if the solver should be reset
+    pub fn on_fix(&mut self, position: (f64, f64, f64)) -> bool {
+        self.errors = 0;
+        let last = self.last.replace(position);
+        if let (ResetStrategy::OnBigJump(threshold), Some((x0, y0, z0))) = (self.strategy, last) {
+            let (x, y, z) = position;
+            let jump = ((x - x0).powi(2) + (y - y0).powi(2) + (z - z0).powi(2)).sqrt();
+            if jump > threshold {
+                warn!("fix jumped by {:.3}m", jump);
+                self.clear();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Forgets the error count and previous fix, once the solver is reset
+    fn clear(&mut self) {
+        self.errors = 0;
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POSITION: (f64, f64, f64) = (4_201_575.0, 189_856.0, 4_779_066.0);
+
+    #[test]
+    fn after_consecutive_errors() {
+        let mut reset = SolverReset::new(ResetStrategy::AfterNErrors(3));
+        assert!(!reset.on_error());
+        assert!(!reset.on_error());
+        assert!(reset.on_error());
+
+        // counted again from the reset
+        assert!(!reset.on_error());
+        assert!(!reset.on_error());
+        // not consecutive
+        assert!(!reset.on_fix(POSITION));
+        assert!(!reset.on_error());
+        assert!(!reset.on_error());
+        assert!(reset.on_error());
+    }
+
+    #[test]
+    fn on_big_jump() {
+        let mut reset = SolverReset::new(ResetStrategy::OnBigJump(10.0));
+        assert!(!reset.on_error());
+        assert!(!reset.on_fix(POSITION));
+        let (x, y, z) = POSITION;
+        assert!(!reset.on_fix((x + 6.0, y + 8.0, z)));
+        assert!(reset.on_fix((x + 6.0, y + 8.0, z + 10.1)));
+        // previous fix forgotten
+        assert!(!reset.on_fix(POSITION));
+
+        let mut reset = SolverReset::new(ResetStrategy::Never);
+        assert!(!reset.on_error());
+        assert!(!reset.on_fix(POSITION));
+        assert!(!reset.on_fix((0.0, 0.0, 0.0)));
+    }
+}
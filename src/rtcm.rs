@@ -0,0 +1,197 @@
+//! RTCM corrections relay
+use std::{fs::File, io::Write, path::PathBuf};
+
+use tokio::{io::AsyncReadExt, net::TcpStream, sync::mpsc::Sender};
+
+use crate::source::Command;
+
+/// RTCM3 frame preamble
+const PREAMBLE: u8 = 0xD3;
+
+/// Maximal RTCM3 frame size: header + payload + CRC
+const MAX_FRAME_LEN: usize = 3 + 1023 + 3;
+
+/// CRC-24Q, as used by RTCM3 frames
+fn crc24q(data: &[u8]) -> u32 {
+    let mut crc = 0_u32;
+    for byte in data {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864CFB;
+            }
+        }
+    }
+    crc & 0xFFFFFF
+}
+
+/// Splits a byte stream into CRC validated RTCM3 frames
+#[derive(Default)]
+pub struct Framer {
+    buf: Vec<u8>,
+}
+
+impl Framer {
+    /// Appends new bytes
+    pub fn consume(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Returns next valid frame, as is. Malformed frames are dropped.
+    pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let start = match self.buf.iter().position(|b| *b == PREAMBLE) {
+                Some(start) => start,
+                None => {
+                    self.buf.clear();
+                    return None;
+                },
+            };
+            self.buf.drain(..start);
+
+            if self.buf.len() < 3 {
+                return None;
+            }
+
+            let len = (((self.buf[1] & 0x03) as usize) << 8) | self.buf[2] as usize;
+            let frame_len = len + 6;
+            if self.buf[1] & 0xFC != 0 || frame_len > MAX_FRAME_LEN {
+                // not a frame start: resync
+                self.buf.drain(..1);
+                continue;
+            }
+            if self.buf.len() < frame_len {
+                return None;
+            }
+
+            let crc = ((self.buf[frame_len - 3] as u32) << 16)
+                | ((self.buf[frame_len - 2] as u32) << 8)
+                | self.buf[frame_len - 1] as u32;
+
+            if crc24q(&self.buf[..frame_len - 3]) == crc {
+                return Some(self.buf.drain(..frame_len).collect());
+            }
+
+            warn!("rtcm: dropped corrupt frame");
+            self.buf.drain(..1);
+        }
+    }
+}
+
+/// RTCM relay options
+pub struct RtcmOpts {
+    /// Correction stream (TCP) address
+    pub addr: String,
+    /// Forward frames to the GNSS receiver
+    pub forward: bool,
+    /// Optional log file
+    pub log: Option<PathBuf>,
+}
+
+/// Relays all valid RTCM frames, either to the GNSS receiver or a log file
+pub async fn relay(opts: RtcmOpts, tx: Sender<Command>) {
+    let mut stream = match TcpStream::connect(&opts.addr).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            error!("rtcm: failed to connect to {}: {}", opts.addr, e);
+            return;
+        },
+    };
+
+    let mut log = match &opts.log {
+        Some(path) => match File::create(path) {
+            Ok(fd) => Some(fd),
+            Err(e) => {
+                error!("rtcm: failed to create {}: {}", path.display(), e);
+                None
+            },
+        },
+        None => None,
+    };
+
+    info!("rtcm: connected to {}", opts.addr);
+
+    let mut framer = Framer::default();
+    let mut buf = [0; 1024];
+
+    loop {
+        let size = match stream.read(&mut buf).await {
+            Ok(0) => {
+                warn!("rtcm: connection closed");
+                return;
+            },
+            Ok(size) => size,
+            Err(e) => {
+                error!("rtcm: read error: {}", e);
+                return;
+            },
+        };
+
+        framer.consume(&buf[..size]);
+
+        while let Some(frame) = framer.next_frame() {
+            if let Some(fd) = &mut log {
+                if let Err(e) = fd.write_all(&frame) {
+                    error!("rtcm: failed to log frame: {}", e);
+                }
+            }
+            if opts.forward {
+                if let Err(e) = tx.send(Command::Corrections(frame)).await {
+                    error!("rtcm: failed to forward frame: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RTCM 1005 (station coordinates) frame
+    const FRAME_1005: [u8; 25] = [
+        0xD3, 0x00, 0x13, 0x3E, 0xD7, 0xD3, 0x02, 0x02, 0x98, 0x0E, 0xDE, 0xEF, 0x34, 0xB4, 0xBD,
+        0x62, 0xAC, 0x09, 0x41, 0x98, 0x6F, 0x33, 0x36, 0x0B, 0x98,
+    ];
+
+    #[test]
+    fn crc24q_check() {
+        assert_eq!(crc24q(b"123456789"), 0xCDE703);
+        assert_eq!(crc24q(&FRAME_1005[..22]), 0x360B98);
+    }
+
+    #[test]
+    fn valid_frame_forwarded_as_is() {
+        let mut framer = Framer::default();
+        // leading garbage, then the frame over two reads
+        framer.consume(&[0x00, 0x42]);
+        framer.consume(&FRAME_1005[..10]);
+        assert!(framer.next_frame().is_none());
+        framer.consume(&FRAME_1005[10..]);
+        assert_eq!(framer.next_frame().unwrap(), FRAME_1005);
+        assert!(framer.next_frame().is_none());
+    }
+
+    #[test]
+    fn corrupt_frame_dropped() {
+        let mut corrupt = FRAME_1005;
+        corrupt[24] ^= 0x01;
+
+        let mut framer = Framer::default();
+        framer.consume(&corrupt);
+        assert!(framer.next_frame().is_none());
+
+        // the corrupt frame holds a preamble, that may only be ruled out
+        // once enough bytes are received: the following frames all go through
+        for _ in 0..30 {
+            framer.consume(&FRAME_1005);
+        }
+        let mut frames = 0;
+        while let Some(frame) = framer.next_frame() {
+            assert_eq!(frame, FRAME_1005);
+            frames += 1;
+        }
+        assert_eq!(frames, 30);
+    }
+}
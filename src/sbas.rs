@@ -0,0 +1,330 @@
+//! SBAS messages (250 bits)
+use std::collections::HashMap;
+
+use gnss_rtk::prelude::Epoch;
+
+/// Rotating preambles
+const PREAMBLES: [u8; 3] = [0x53, 0x9A, 0xC6];
+
+/// Number of bits protected by the CRC
+const CRC_PROTECTED_BITS: usize = 226;
+
+/// u-blox pads the last word: the CRC sits in its 24 LSB
+const CRC_POSITION: usize = 232;
+
+/// SBAS formatted message
+#[derive(Debug, Clone)]
+pub struct Message {
+    /// Message type
+    pub mt: u8,
+    /// Raw bits, MSB first
+    bits: [u8; 32],
+}
+
+impl Message {
+    /// Decodes message from 8 RXM-SFRBX words: the first 226 bits
+    /// are stored MSB first, the CRC in the 24 LSB of the last word.
+    /// Returns None on invalid preamble or CRC.
+    pub fn decode<I: Iterator<Item = u32>>(words: I) -> Option<Self> {
+        let mut bits = [0_u8; 32];
+        let mut nb_words = 0;
+        for (i, word) in words.take(8).enumerate() {
+            bits[i * 4..(i + 1) * 4].copy_from_slice(&word.to_be_bytes());
+            nb_words += 1;
+        }
+        if nb_words != 8 {
+            return None;
+        }
+
+        let msg = Self { mt: 0, bits };
+
+        if !PREAMBLES.contains(&(msg.bitu(0, 8) as u8)) {
+            return None;
+        }
+
+        let mut crc = 0_u32;
+        for pos in 0..CRC_PROTECTED_BITS {
+            let top = ((crc >> 23) & 0x01) ^ msg.bitu(pos, 1);
+            crc = (crc << 1) & 0xFFFFFF;
+            if top != 0 {
+                crc ^= 0x864CFB;
+            }
+        }
+
+        if crc != msg.bitu(CRC_POSITION, 24) {
+            return None;
+        }
+
+        Some(Self {
+            mt: msg.bitu(8, 6) as u8,
+            bits,
+        })
+    }
+
+    /// Returns unsigned field of `len` bits at position `pos`
+    pub fn bitu(&self, pos: usize, len: usize) -> u32 {
+        let mut value = 0_u32;
+        for i in pos..pos + len {
+            value = (value << 1) | ((self.bits[i / 8] >> (7 - i % 8)) & 0x01) as u32;
+        }
+        value
+    }
+
+    /// Returns signed (two's complement) field of `len` bits at position `pos`
+    pub fn bits(&self, pos: usize, len: usize) -> i32 {
+        let value = self.bitu(pos, len);
+        if value & (1 << (len - 1)) != 0 {
+            value as i32 - (1 << len)
+        } else {
+            value as i32
+        }
+    }
+}
+
+/// Fast corrections are discarded past this delay (s)
+const FAST_CORRECTION_TIMEOUT_S: f64 = 12.0;
+
+/// UDREI >= 14: not monitored or do not use
+const UDREI_NOT_MONITORED: u32 = 14;
+
+/// Number of corrections per fast correction message
+const FAST_CORRECTIONS_PER_MESSAGE: usize = 13;
+
+/// Fast (pseudo range) corrections of GPS L1 C/A, decoded from SBAS messages types 1 to 5.
+/// Long term corrections (types 24 and 25) and the ionosphere grid (types 18 and 26)
+/// are not decoded.
+#[derive(Debug, Default)]
+pub struct FastCorrections {
+    /// SBAS vehicle we're locked to
+    source: Option<u8>,
+    /// Issue of Data PRN mask
+    iodp: Option<u32>,
+    /// PRN mask
+    mask: Vec<u8>,
+    /// Pseudo range corrections (m) and when they were received, per GPS PRN
+    prc: HashMap<u8, (f64, Epoch)>,
+}
+
+impl FastCorrections {
+    /// Updates from SBAS message broadcast by SBAS vehicle `sv_id`, received at `t` (GPST).
+    /// Corrections are timed against the receiver time, so they expire
+    /// at the right time when replaying, or processing buffered data.
+    pub fn update(&mut self, sv_id: u8, msg: &Message, t: Epoch) {
+        match self.source {
+            Some(source) if source != sv_id => return,
+            _ => {},
+        }
+        match msg.mt {
+            0 => {
+                warn!("S{} do not use SBAS for safety applications", sv_id);
+                self.prc.clear();
+            },
+            1 => {
+                self.source = Some(sv_id);
+                self.iodp = Some(msg.bitu(224, 2));
+                // 210 PRN slots; GPS occupies 1..=37
+                self.mask = (0..210)
+                    .filter(|slot| msg.bitu(14 + slot, 1) == 1)
+                    .map(|slot| (slot + 1) as u8)
+                    .collect();
+                debug!("S{} prn mask: {:?}", sv_id, self.mask);
+            },
+            2..=5 => {
+                if self.iodp != Some(msg.bitu(16, 2)) {
+                    // mask not received yet, or outdated
+                    return;
+                }
+                let offset = (msg.mt as usize - 2) * FAST_CORRECTIONS_PER_MESSAGE;
+                for i in 0..FAST_CORRECTIONS_PER_MESSAGE {
+                    let prn = match self.mask.get(offset + i) {
+                        Some(prn) if *prn <= 37 => *prn,
+                        _ => continue,
+                    };
+                    let udrei = msg.bitu(174 + i * 4, 4);
+                    if udrei >= UDREI_NOT_MONITORED {
+                        self.prc.remove(&prn);
+                        continue;
+                    }
+                    let prc = msg.bits(18 + i * 12, 12) as f64 * 0.125;
+                    self.prc.insert(prn, (prc, t));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Returns GPS pseudo range (m) measured at `t` (GPST),
+    /// corrected with latest fast correction, if any
+    pub fn correct_pseudo_range(&self, prn: u8, pr: f64, t: Epoch) -> f64 {
+        match self.prc.get(&prn) {
+            Some((prc, received)) if (t - *received).to_seconds() < FAST_CORRECTION_TIMEOUT_S => {
+                pr + prc
+            },
+            _ => pr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Duration;
+
+    /// Builds SBAS messages, as stored by RXM-SFRBX
+    #[derive(Default)]
+    struct Builder {
+        bits: [u8; 32],
+    }
+
+    impl Builder {
+        /// New message of type `mt`, with the first preamble
+        fn new(mt: u8) -> Self {
+            let mut builder = Self::default();
+            builder.set(0, 8, PREAMBLES[0] as u32);
+            builder.set(8, 6, mt as u32);
+            builder
+        }
+
+        /// Sets `len` bits at position `pos`, MSB first
+        fn set(&mut self, pos: usize, len: usize, value: u32) -> &mut Self {
+            for i in 0..len {
+                let bit = pos + i;
+                let mask = 0x80 >> (bit % 8);
+                if (value >> (len - 1 - i)) & 0x01 != 0 {
+                    self.bits[bit / 8] |= mask;
+                } else {
+                    self.bits[bit / 8] &= !mask;
+                }
+            }
+            self
+        }
+
+        /// Returns the 8 words, CRC included
+        fn words(&mut self) -> Vec<u32> {
+            let msg = Message {
+                mt: 0,
+                bits: self.bits,
+            };
+            let mut crc = 0_u32;
+            for pos in 0..CRC_PROTECTED_BITS {
+                let top = ((crc >> 23) & 0x01) ^ msg.bitu(pos, 1);
+                crc = (crc << 1) & 0xFFFFFF;
+                if top != 0 {
+                    crc ^= 0x864CFB;
+                }
+            }
+            self.set(CRC_POSITION, 24, crc);
+            self.bits
+                .chunks(4)
+                .map(|word| u32::from_be_bytes(word.try_into().unwrap()))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn decode_message() {
+        // unsigned and signed fields
+        let words = Builder::new(47)
+            .set(14, 10, 0x2AB)
+            .set(100, 12, (-37_i32 as u32) & 0xFFF)
+            .words();
+        let msg = Message::decode(words.into_iter()).unwrap();
+        assert_eq!(msg.mt, 47);
+        assert_eq!(msg.bitu(14, 10), 0x2AB);
+        assert_eq!(msg.bits(100, 12), -37);
+        assert_eq!(msg.bits(14, 10), 0x2AB - 0x400);
+    }
+
+    #[test]
+    fn reject_invalid_messages() {
+        let words = Builder::new(47).set(50, 16, 0xBEEF).words();
+
+        // any corrupt bit fails the CRC
+        for bit in [8, 60, 225, 240] {
+            let mut corrupt = words.clone();
+            corrupt[bit / 32] ^= 0x8000_0000 >> (bit % 32);
+            assert!(
+                Message::decode(corrupt.into_iter()).is_none(),
+                "bit {}",
+                bit
+            );
+        }
+
+        // bits between the protected bits and the CRC are padding
+        let mut padded = words;
+        padded[7] ^= 0x8000_0000 >> (228 % 32);
+        assert!(Message::decode(padded.into_iter()).is_some());
+
+        // unknown preamble, even with a valid CRC
+        let words = Builder::new(47).set(0, 8, 0x55).words();
+        assert!(Message::decode(words.into_iter()).is_none());
+
+        // incomplete
+        let words = Builder::new(47).words();
+        assert!(Message::decode(words.into_iter().take(7)).is_none());
+    }
+
+    /// PRN mask (type 1) of GPS PRN 3, 7 and 12
+    fn mask(iodp: u32) -> Message {
+        let words = Builder::new(1)
+            .set(14 + 2, 1, 1)
+            .set(14 + 6, 1, 1)
+            .set(14 + 11, 1, 1)
+            .set(224, 2, iodp)
+            .words();
+        Message::decode(words.into_iter()).unwrap()
+    }
+
+    /// Fast corrections (type 2) of the first mask slots: correction (m) and UDREI
+    fn fast_corrections(iodp: u32, corrections: &[(f64, u32)]) -> Message {
+        let mut builder = Builder::new(2);
+        builder.set(16, 2, iodp);
+        for (i, (prc, udrei)) in corrections.iter().enumerate() {
+            builder.set(18 + i * 12, 12, ((prc / 0.125) as i32 as u32) & 0xFFF);
+            builder.set(174 + i * 4, 4, *udrei);
+        }
+        Message::decode(builder.words().into_iter()).unwrap()
+    }
+
+    #[test]
+    fn fast_corrections_type_2() {
+        let t0 = Epoch::from_gpst_seconds(1_000_000.0);
+        let t = t0 + Duration::from_seconds(6.0);
+        let pr = 21_000_000.0;
+
+        let mut fast = FastCorrections::default();
+        // mask not received yet
+        fast.update(120, &fast_corrections(2, &[(1.5, 2)]), t0);
+        assert_eq!(fast.correct_pseudo_range(3, pr, t), pr);
+
+        fast.update(120, &mask(2), t0);
+        fast.update(
+            120,
+            &fast_corrections(2, &[(1.5, 2), (-2.25, 5), (4.0, 15)]),
+            t0,
+        );
+        assert_eq!(fast.correct_pseudo_range(3, pr, t), pr + 1.5);
+        assert_eq!(fast.correct_pseudo_range(7, pr, t), pr - 2.25);
+        // not monitored
+        assert_eq!(fast.correct_pseudo_range(12, pr, t), pr);
+        // not in the mask
+        assert_eq!(fast.correct_pseudo_range(5, pr, t), pr);
+
+        // other SBAS vehicle, or outdated mask: ignored
+        fast.update(126, &fast_corrections(2, &[(10.0, 2)]), t0);
+        fast.update(120, &fast_corrections(1, &[(10.0, 2)]), t0);
+        assert_eq!(fast.correct_pseudo_range(3, pr, t), pr + 1.5);
+
+        // expired
+        let t = t0 + Duration::from_seconds(FAST_CORRECTION_TIMEOUT_S + 1.0);
+        assert_eq!(fast.correct_pseudo_range(3, pr, t), pr);
+
+        // do not use
+        fast.update(
+            120,
+            &Message::decode(Builder::new(0).words().into_iter()).unwrap(),
+            t0,
+        );
+        assert_eq!(fast.correct_pseudo_range(7, pr, t0), pr);
+    }
+}
@@ -0,0 +1,153 @@
+//! Candidate selection, when more satellites are available than needed
+use gnss_rtk::prelude::{Candidate, Constellation, SV};
+
+/// Number of distinct [SV] among these candidates
+pub fn nb_sv(candidates: &[Candidate]) -> usize {
+    let mut sv = candidates.iter().map(|cd| cd.sv).collect::<Vec<_>>();
+    sv.sort();
+    sv.dedup();
+    sv.len()
+}
+
+/// Keeps the candidates of at most `max_sv` SV: preferred constellations first,
+/// then highest elevation first, within each constellation.
+#[derive(Debug, Clone)]
+pub struct Selection {
+    /// Maximal number of SV proposed to the solver
+    pub max_sv: usize,
+    /// Constellations, most preferred first. Others come last.
+    pub priority: Vec<Constellation>,
+}
+
+impl Selection {
+    /// Rank of this [Constellation], lowest is preferred
+    fn rank(&self, constellation: Constellation) -> usize {
+        self.priority
+            .iter()
+            .position(|c| *c == constellation)
+            .unwrap_or(self.priority.len())
+    }
+
+    /// Selects the candidates to propose, given the elevation (deg)
+    /// of the SV that contributed to the latest fix. SV of unknown elevation
+    /// (not used yet) come after all SV of known elevation, within their constellation.
+    pub fn select(&self, candidates: &mut Vec<Candidate>, geometry: &[(SV, f64, f64)]) {
+        if nb_sv(candidates) <= self.max_sv {
+            return;
+        }
+        let elevation = |sv: SV| {
+            geometry
+                .iter()
+                .find(|(used, _, _)| *used == sv)
+                .map(|(_, elev, _)| *elev)
+                .unwrap_or(f64::NEG_INFINITY)
+        };
+        candidates.sort_by(|a, b| {
+            self.rank(a.sv.constellation)
+                .cmp(&self.rank(b.sv.constellation))
+                .then(elevation(b.sv).total_cmp(&elevation(a.sv)))
+                .then(a.sv.cmp(&b.sv))
+        });
+        // all candidates of the first `max_sv` SV are kept
+        let mut selected = Vec::<SV>::with_capacity(self.max_sv + 1);
+        let split = candidates
+            .iter()
+            .position(|cd| {
+                if !selected.contains(&cd.sv) {
+                    selected.push(cd.sv);
+                }
+                selected.len() > self.max_sv
+            })
+            .unwrap_or(candidates.len());
+        let dropped = candidates.split_off(split);
+        debug!(
+            "selected {} candidates, dropped {:?}",
+            candidates.len(),
+            dropped.iter().map(|cd| cd.sv).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Carrier, Duration, Epoch, PseudoRange};
+
+    fn candidates(sv: &[SV]) -> Vec<Candidate> {
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        sv.iter()
+            .map(|sv| {
+                let pr = PseudoRange {
+                    carrier: Carrier::L1,
+                    value: 2.2E7,
+                    snr: None,
+                };
+                Candidate::new(*sv, t, Duration::default(), None, vec![pr], vec![])
+            })
+            .collect()
+    }
+
+    fn svs(candidates: &[Candidate]) -> Vec<SV> {
+        candidates.iter().map(|cd| cd.sv).collect()
+    }
+
+    #[test]
+    fn priority_then_elevation() {
+        let g01 = SV::new(Constellation::GPS, 1);
+        let g02 = SV::new(Constellation::GPS, 2);
+        let e01 = SV::new(Constellation::Galileo, 1);
+        let e02 = SV::new(Constellation::Galileo, 2);
+        let j01 = SV::new(Constellation::QZSS, 1);
+        let geometry = [
+            (e01, 20.0, 0.0),
+            (e02, 60.0, 90.0),
+            (g01, 70.0, 180.0),
+            (j01, 80.0, 270.0),
+        ];
+        let selection = Selection {
+            max_sv: 4,
+            priority: vec![Constellation::Galileo, Constellation::GPS],
+        };
+
+        // G02 elevation is unknown, QZSS is not preferred
+        let mut cd = candidates(&[j01, g02, g01, e01, e02]);
+        selection.select(&mut cd, &geometry);
+        assert_eq!(svs(&cd), [e02, e01, g01, g02]);
+
+        // enough room: untouched
+        let mut cd = candidates(&[j01, g02, g01, e01]);
+        selection.select(&mut cd, &geometry);
+        assert_eq!(svs(&cd), [j01, g02, g01, e01]);
+    }
+
+    #[test]
+    fn counts_distinct_sv() {
+        let g01 = SV::new(Constellation::GPS, 1);
+        let g02 = SV::new(Constellation::GPS, 2);
+        let g03 = SV::new(Constellation::GPS, 3);
+        let e01 = SV::new(Constellation::Galileo, 1);
+        let e02 = SV::new(Constellation::Galileo, 2);
+        let geometry = [
+            (g01, 70.0, 0.0),
+            (g02, 60.0, 90.0),
+            (e01, 50.0, 180.0),
+            (g03, 10.0, 270.0),
+        ];
+        let selection = Selection {
+            max_sv: 4,
+            priority: vec![],
+        };
+
+        // one candidate per signal: 4 SV fit
+        let mut cd = candidates(&[g01, g01, g02, g02, e01, e02, e02]);
+        assert_eq!(nb_sv(&cd), 4);
+        selection.select(&mut cd, &geometry);
+        assert_eq!(cd.len(), 7);
+
+        // 5 SV: E02 (unknown elevation) goes, with all its signals
+        let mut cd = candidates(&[e02, g03, g01, g02, g01, e01, e02, g02]);
+        selection.select(&mut cd, &geometry);
+        assert_eq!(nb_sv(&cd), 4);
+        assert_eq!(svs(&cd), [g01, g01, g02, g02, e01, g03]);
+    }
+}
@@ -0,0 +1,110 @@
+//! Per SV sky log (CSV), for obstruction and multipath analysis
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Result as IoResult, Write},
+    path::Path,
+};
+
+use gnss_rtk::prelude::{Epoch, SV};
+
+use crate::pvt::Pvt;
+
+/// CSV header
+const HEADER: &str = "epoch,sv,azimuth,elevation,cno,used";
+
+/// Logs one row per tracked SV and epoch: azimuth and elevation (degrees),
+/// C/N0 (dB.Hz) and whether it contributed to the fix.
+/// Azimuth and elevation are those of the latest fix the SV contributed to,
+/// and are left empty until it contributed to one.
+pub struct SkyLog {
+    writer: BufWriter<File>,
+    /// Latest elevation and azimuth (degrees), per SV
+    sky: HashMap<SV, (f64, f64)>,
+}
+
+impl SkyLog {
+    pub fn new(path: &Path) -> IoResult<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", HEADER)?;
+        Ok(Self {
+            writer,
+            sky: HashMap::new(),
+        })
+    }
+
+    /// Logs epoch `t`: C/N0 of each tracked SV, and the resulting fix if any
+    pub fn log(&mut self, t: Epoch, cno: &[(SV, f64)], fix: Option<&Pvt>) {
+        let used = match fix {
+            Some(pvt) => {
+                for (sv, elev, azim) in pvt.sv.iter() {
+                    self.sky.insert(*sv, (*elev, *azim));
+                }
+                pvt.sv.iter().map(|(sv, _, _)| *sv).collect::<Vec<_>>()
+            },
+            None => Vec::new(),
+        };
+        for (sv, cno) in cno.iter() {
+            let (elev, azim) = match self.sky.get(sv) {
+                Some((elev, azim)) => (elev.to_string(), azim.to_string()),
+                None => (String::new(), String::new()),
+            };
+            if let Err(e) = writeln!(
+                self.writer,
+                "{},{},{},{},{},{}",
+                t,
+                sv,
+                azim,
+                elev,
+                cno,
+                used.contains(sv)
+            ) {
+                error!("failed to write sky log: {}", e);
+                return;
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            error!("failed to flush sky log: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Constellation, Duration};
+
+    #[test]
+    fn one_row_per_sv() {
+        let path = std::env::temp_dir().join("rt-navi-skylog-test.csv");
+        let g01 = SV::new(Constellation::GPS, 1);
+        let e11 = SV::new(Constellation::Galileo, 11);
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let t1 = t0 + Duration::from_seconds(1.0);
+        let pvt = Pvt {
+            sv: vec![(g01, 45.5, 120.25)],
+            ..Pvt::fixture(t1, (4_201_575.0, 189_856.0, 4_779_066.0))
+        };
+
+        let mut sky_log = SkyLog::new(&path).unwrap();
+        sky_log.log(t0, &[(g01, 42.0), (e11, 35.5)], None);
+        sky_log.log(t1, &[(g01, 43.0), (e11, 36.5)], Some(&pvt));
+        sky_log.flush();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(
+            lines[1..],
+            [
+                format!("{},G01,,,42,false", t0),
+                format!("{},E11,,,35.5,false", t0),
+                format!("{},G01,120.25,45.5,43,true", t1),
+                format!("{},E11,,,36.5,false", t1),
+            ]
+        );
+    }
+}
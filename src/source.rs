@@ -0,0 +1,64 @@
+//! Measurement sources, feeding the navigation loop
+use gnss_rtk::prelude::{Candidate, Epoch, SV};
+
+#[cfg(feature = "osnma")]
+use crate::osnma::Authentication;
+use crate::{antenna::AntennaState, esf::EsfIns, ublox::ParserStats};
+
+/// Command sent to the [Source]
+#[derive(Debug, Clone)]
+pub enum Command {
+    AbortCandidates,
+    /// Correction data (RTCM frame, SPARTN message..) to forward to the device
+    Corrections(Vec<u8>),
+    /// Latest fix latitude and longitude (ddeg),
+    /// with elevation and azimuth (deg) of each contributing SV
+    Geometry((f64, f64, Vec<(SV, f64, f64)>)),
+}
+
+/// Message produced by the [Source]
+#[derive(Debug, Clone)]
+pub enum Message {
+    Candidates((Epoch, Vec<Candidate>)),
+    /// Range rates (m/s) from Doppler, per SV, sent prior the epoch's candidates
+    RangeRates((Epoch, Vec<(SV, f64)>)),
+    /// C/N0 (dB.Hz) of each tracked SV, sent prior the epoch's candidates
+    Cno((Epoch, Vec<(SV, f64)>)),
+    /// Receiver own high precision position, as reference
+    HpPosition(ReceiverPosition),
+    /// Receiver own fix (NAV-PVT), None while the receiver has no fix
+    ReceiverFix(Option<ReceiverPosition>),
+    /// Antenna state (MON-HW), sent on every change
+    Antenna(AntennaState),
+    /// Malformed packets and resync losses, sent on change (rate limited)
+    ParserStats(ParserStats),
+    /// Vehicle dynamics (ESF-INS)
+    Ins(EsfIns),
+    /// Galileo navigation data authentication status of one SV, sent on every change
+    #[cfg(feature = "osnma")]
+    Osnma((SV, Authentication)),
+}
+
+/// Geodetic position, resolved by the receiver
+#[derive(Debug, Clone, Copy)]
+pub struct ReceiverPosition {
+    /// Latitude (ddeg)
+    pub lat: f64,
+    /// Longitude (ddeg)
+    pub lon: f64,
+    /// Altitude above ellipsoid (m)
+    pub alt: f64,
+    /// Horizontal accuracy (m)
+    pub h_acc: f64,
+    /// Vertical accuracy (m)
+    pub v_acc: f64,
+}
+
+/// Anything that produces [Message]s. Sources block on their I/O, so they run on their
+/// own thread and exchange with the navigation loop through the [Command] and [Message]
+/// channels they were built with. Only [crate::ublox::Ublox] implements it, for both the
+/// serial receiver and replayed UBX streams.
+pub trait Source: Send {
+    /// Runs until the stream ends, or the navigation loop goes away
+    fn run(&mut self);
+}
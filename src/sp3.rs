@@ -0,0 +1,239 @@
+//! Precise orbits, loaded from SP3 (c/d) files
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    str::FromStr,
+};
+
+use gnss_rtk::prelude::{Epoch, InterpolationResult, TimeScale, SV};
+
+use crate::Error;
+
+/// Tabulated SV positions (ECEF, m), sorted by [Epoch]
+type Track = Vec<(Epoch, (f64, f64, f64))>;
+
+/// Precise orbits source, interpolating SP3 records
+#[derive(Debug, Default)]
+pub struct Sp3OrbitSource {
+    tracks: HashMap<SV, Track>,
+}
+
+/// Parses SP3 epoch descriptor: "*  2024  1 15  0  5  0.00000000"
+fn parse_epoch(line: &str, ts: TimeScale) -> Option<Epoch> {
+    let mut items = line[1..].split_ascii_whitespace();
+    let y = items.next()?.parse::<i32>().ok()?;
+    let m = items.next()?.parse::<u8>().ok()?;
+    let d = items.next()?.parse::<u8>().ok()?;
+    let hh = items.next()?.parse::<u8>().ok()?;
+    let mm = items.next()?.parse::<u8>().ok()?;
+    let secs = items.next()?.parse::<f64>().ok()?;
+    let nanos = (secs.fract() * 1.0E9).round() as u32;
+    Some(Epoch::from_gregorian(
+        y,
+        m,
+        d,
+        hh,
+        mm,
+        secs.trunc() as u8,
+        nanos,
+        ts,
+    ))
+}
+
+/// Parses SP3 position record: "PG01  x y z clk", coordinates in km
+fn parse_position(line: &str) -> Option<(SV, (f64, f64, f64))> {
+    let sv = SV::from_str(line.get(1..4)?.trim()).ok()?;
+    let mut items = line.get(4..)?.split_ascii_whitespace();
+    let x = items.next()?.parse::<f64>().ok()?;
+    let y = items.next()?.parse::<f64>().ok()?;
+    let z = items.next()?.parse::<f64>().ok()?;
+    // missing positions are tabulated as 0.0
+    if x == 0.0 && y == 0.0 && z == 0.0 {
+        return None;
+    }
+    Some((sv, (x * 1.0E3, y * 1.0E3, z * 1.0E3)))
+}
+
+impl Sp3OrbitSource {
+    /// Loads all position records from SP3 file
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let fd = File::open(path)
+            .map_err(|e| Error::InvalidSp3(format!("{}: {}", path.display(), e)))?;
+
+        let mut ts = TimeScale::GPST;
+        let mut epoch = None;
+        let mut tracks = HashMap::<SV, Track>::new();
+
+        for line in BufReader::new(fd).lines() {
+            let line = line.map_err(|e| Error::InvalidSp3(format!("{}: {}", path.display(), e)))?;
+            if line.starts_with("%c") && epoch.is_none() {
+                // first %c descriptor specifies the time system
+                match line.get(9..12).map(|s| s.trim()) {
+                    Some("GAL") => ts = TimeScale::GST,
+                    Some("BDT") => ts = TimeScale::BDT,
+                    Some("UTC") => ts = TimeScale::UTC,
+                    Some("TAI") => ts = TimeScale::TAI,
+                    _ => {},
+                }
+            } else if line.starts_with('*') {
+                epoch = Some(parse_epoch(&line, ts).ok_or_else(|| {
+                    Error::InvalidSp3(format!("{}: invalid epoch \"{}\"", path.display(), line))
+                })?);
+            } else if line.starts_with('P') {
+                if let (Some(t), Some((sv, position))) = (epoch, parse_position(&line)) {
+                    tracks.entry(sv).or_default().push((t, position));
+                }
+            }
+        }
+
+        if tracks.is_empty() {
+            return Err(Error::InvalidSp3(format!(
+                "{}: no position record",
+                path.display()
+            )));
+        }
+        for track in tracks.values_mut() {
+            track.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        info!(
+            "{}: precise orbits for {} satellites",
+            path.display(),
+            tracks.len()
+        );
+        Ok(Self { tracks })
+    }
+
+    /// Interpolates [SV] position at `t`, using a Lagrange polynomial of given `order`.
+    /// Returns None when `t` is not surrounded by enough records.
+    pub fn position(&self, t: Epoch, sv: SV, order: usize) -> Option<(f64, f64, f64)> {
+        let track = self.tracks.get(&sv)?;
+        let (t0, _) = track.first()?;
+        let t = t.to_time_scale(t0.time_scale);
+
+        // centered window of order + 1 records
+        let nb = order + 1;
+        let after = track.partition_point(|(ti, _)| *ti <= t);
+        if after == 0 {
+            return None;
+        }
+        let start = after
+            .checked_sub(nb / 2)
+            .filter(|s| s + nb <= track.len())?;
+        let window = &track[start..start + nb];
+
+        let mut position = (0.0, 0.0, 0.0);
+        for (i, (ti, pi)) in window.iter().enumerate() {
+            let mut li = 1.0;
+            for (j, (tj, _)) in window.iter().enumerate() {
+                if i != j {
+                    li *= (t - *tj).to_seconds() / (*ti - *tj).to_seconds();
+                }
+            }
+            position.0 += li * pi.0;
+            position.1 += li * pi.1;
+            position.2 += li * pi.2;
+        }
+        Some(position)
+    }
+
+    /// Solver orbit interface
+    pub fn interpolate(&self, t: Epoch, sv: SV, order: usize) -> Option<InterpolationResult> {
+        let position = self.position(t, sv, order)?;
+        Some(InterpolationResult::from_position(position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Constellation, Duration};
+    use std::f64::consts::PI;
+
+    /// Circular GPS like orbit: radius (m), period (s), inclination (rad)
+    const RADIUS: f64 = 26_560.0E3;
+    const PERIOD: f64 = 43_082.0;
+    const INCLINATION: f64 = 55.0 * PI / 180.0;
+
+    /// Records interval (s)
+    const INTERVAL: f64 = 900.0;
+
+    /// Orbit position (ECEF, m), `dt` seconds past the first record
+    fn orbit(dt: f64) -> (f64, f64, f64) {
+        let angle = 2.0 * PI * dt / PERIOD;
+        (
+            RADIUS * angle.cos(),
+            RADIUS * angle.sin() * INCLINATION.cos(),
+            RADIUS * angle.sin() * INCLINATION.sin(),
+        )
+    }
+
+    /// Writes 3 hours of G01 records
+    fn sp3_file(name: &str) -> std::path::PathBuf {
+        let mut content =
+            "#dP2024  1 15  0  0  0.00000000      13 ORBIT IGS20 HLM  IGS\n".to_string();
+        content.push_str("%c G  cc GPS ccc cccc cccc cccc cccc ccccc ccccc ccccc ccccc\n");
+        for i in 0..13 {
+            let (hh, mm) = (i * 15 / 60, i * 15 % 60);
+            let (x, y, z) = orbit(i as f64 * INTERVAL);
+            content.push_str(&format!("*  2024  1 15 {:2} {:2}  0.00000000\n", hh, mm));
+            content.push_str(&format!(
+                "PG01{:14.6}{:14.6}{:14.6}    123.456789\n",
+                x * 1.0E-3,
+                y * 1.0E-3,
+                z * 1.0E-3
+            ));
+            // missing position
+            content.push_str("PG02      0.000000      0.000000      0.000000 999999.999999\n");
+        }
+        content.push_str("EOF\n");
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn interpolation_within_centimeters() {
+        let sp3 = Sp3OrbitSource::from_file(&sp3_file("rt-navi-interpolation.sp3")).unwrap();
+        let g01 = SV::new(Constellation::GPS, 1);
+        let t0 = Epoch::from_gregorian(2024, 1, 15, 0, 0, 0, 0, TimeScale::GPST);
+
+        // tabulated epoch, then between records
+        for dt in [3600.0, 4050.0, 4321.5] {
+            let t = t0 + Duration::from_seconds(dt);
+            let (x, y, z) = sp3.position(t, g01, 9).unwrap();
+            let (x0, y0, z0) = orbit(dt);
+            let error = ((x - x0).powi(2) + (y - y0).powi(2) + (z - z0).powi(2)).sqrt();
+            assert!(error < 1.0E-2, "error={}m at {}s", error, dt);
+        }
+
+        // same epoch, another timescale
+        let t = (t0 + Duration::from_seconds(3600.0)).to_time_scale(TimeScale::UTC);
+        let (x, _, _) = sp3.position(t, g01, 9).unwrap();
+        assert!((x - orbit(3600.0).0).abs() < 1.0E-2);
+    }
+
+    #[test]
+    fn uncovered_epochs_and_sv() {
+        let sp3 = Sp3OrbitSource::from_file(&sp3_file("rt-navi-coverage.sp3")).unwrap();
+        let g01 = SV::new(Constellation::GPS, 1);
+        let t0 = Epoch::from_gregorian(2024, 1, 15, 0, 0, 0, 0, TimeScale::GPST);
+
+        // not enough records to center the window
+        assert!(sp3
+            .position(t0 + Duration::from_seconds(600.0), g01, 9)
+            .is_none());
+        assert!(sp3
+            .position(t0 - Duration::from_seconds(600.0), g01, 9)
+            .is_none());
+        // missing positions are not tabulated
+        assert!(sp3
+            .position(
+                t0 + Duration::from_seconds(4050.0),
+                SV::new(Constellation::GPS, 2),
+                9
+            )
+            .is_none());
+    }
+}
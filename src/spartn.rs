@@ -0,0 +1,298 @@
+//! SPARTN (v2) transport layer: frames and message identification.
+//! Correction blocks are not decoded: they are forwarded to the receiver as is.
+
+/// SPARTN frame preamble
+const PREAMBLE: u8 = 0x73;
+
+/// Frame start: preamble, then message type, payload length,
+/// encryption flag, CRC type and frame CRC (24 bits)
+const FRAME_START_LEN: usize = 4;
+
+/// Embedded authentication data length (bytes), per length indicator
+const AUTH_LEN: [usize; 5] = [8, 12, 16, 32, 64];
+
+/// Message CRC length (bytes), per CRC type
+const CRC_LEN: [usize; 4] = [1, 2, 3, 4];
+
+/// Reads big endian bit fields
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Position (bits)
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Reads the next `n` bits (up to 32), if available
+    fn read(&mut self, n: usize) -> Option<u32> {
+        if self.pos + n > self.data.len() * 8 {
+            return None;
+        }
+        let mut value = 0_u32;
+        for _ in 0..n {
+            let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 0x01;
+            value = (value << 1) | bit as u32;
+            self.pos += 1;
+        }
+        Some(value)
+    }
+
+    /// Position, in whole bytes
+    fn bytes(&self) -> usize {
+        self.pos.div_ceil(8)
+    }
+}
+
+/// SPARTN message type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageType {
+    /// Orbit, clock and bias corrections
+    Ocb,
+    /// High precision atmosphere corrections
+    Hpac,
+    /// Geographic area definition
+    Gad,
+    /// Basic precision atmosphere corrections
+    Bpac,
+    /// Encryption and authentication support
+    Eas,
+    /// Proprietary
+    Proprietary,
+    Unknown(u8),
+}
+
+impl From<u8> for MessageType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Ocb,
+            1 => Self::Hpac,
+            2 => Self::Gad,
+            3 => Self::Bpac,
+            4 => Self::Eas,
+            120 => Self::Proprietary,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+/// SPARTN message header
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Header {
+    /// [MessageType]
+    pub msg_type: MessageType,
+    /// Message subtype: the constellation, for OCB and HPAC messages
+    /// (0: GPS, 1: Glonass, 2: Galileo, 3: BeiDou, 4: QZSS)
+    pub subtype: u8,
+    /// GNSS time tag (s)
+    pub time_tag: u32,
+    /// Time tag is the complete 32 bit tag, otherwise its truncated 16 bit form
+    pub full_time_tag: bool,
+    /// Solution identifier
+    pub solution_id: u8,
+    /// Solution processor identifier
+    pub processor_id: u8,
+    /// Payload is encrypted
+    pub encrypted: bool,
+    /// Payload length (bytes)
+    pub payload_len: usize,
+}
+
+impl Header {
+    /// Constellation the corrections apply to, for OCB and HPAC messages
+    pub fn constellation(&self) -> Option<&'static str> {
+        match self.msg_type {
+            MessageType::Ocb | MessageType::Hpac => match self.subtype {
+                0 => Some("GPS"),
+                1 => Some("Glonass"),
+                2 => Some("Galileo"),
+                3 => Some("BeiDou"),
+                4 => Some("QZSS"),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Decodes the [Header] of the SPARTN frame `data` starts with,
+/// and returns it with the complete frame length (bytes).
+/// Returns None when `data` does not start with a complete frame.
+/// Frame and message CRCs are not verified.
+pub fn decode(data: &[u8]) -> Option<(Header, usize)> {
+    if data.len() < FRAME_START_LEN || data[0] != PREAMBLE {
+        return None;
+    }
+    let mut bits = BitReader::new(&data[1..]);
+    let msg_type = bits.read(7)? as u8;
+    let payload_len = bits.read(10)? as usize;
+    let encrypted = bits.read(1)? == 1;
+    let crc_type = bits.read(2)? as usize;
+    let _frame_crc = bits.read(4)?;
+
+    let subtype = bits.read(4)? as u8;
+    let full_time_tag = bits.read(1)? == 1;
+    let time_tag = bits.read(if full_time_tag { 32 } else { 16 })?;
+    let solution_id = bits.read(7)? as u8;
+    let processor_id = bits.read(4)? as u8;
+
+    let mut auth_len = 0;
+    if encrypted {
+        let _encryption_id = bits.read(4)?;
+        let _sequence = bits.read(6)?;
+        let auth_indicator = bits.read(3)?;
+        let auth_len_indicator = bits.read(3)? as usize;
+        if auth_indicator > 1 {
+            auth_len = *AUTH_LEN.get(auth_len_indicator)?;
+        }
+    }
+
+    let frame_len = 1 + bits.bytes() + payload_len + auth_len + CRC_LEN[crc_type];
+    if data.len() < frame_len {
+        return None;
+    }
+
+    let header = Header {
+        msg_type: msg_type.into(),
+        subtype,
+        time_tag,
+        full_time_tag,
+        solution_id,
+        processor_id,
+        encrypted,
+        payload_len,
+    };
+    Some((header, frame_len))
+}
+
+/// Reports the SPARTN messages a correction message is made of
+pub fn report(data: &[u8]) {
+    let mut offset = 0;
+    while offset < data.len() {
+        match decode(&data[offset..]) {
+            Some((header, len)) => {
+                debug!(
+                    "spartn: {:?} {} time_tag={}s solution={}/{} {} bytes{}",
+                    header.msg_type,
+                    header.constellation().unwrap_or_default(),
+                    header.time_tag,
+                    header.solution_id,
+                    header.processor_id,
+                    header.payload_len,
+                    if header.encrypted { " (encrypted)" } else { "" },
+                );
+                offset += len;
+            },
+            None => {
+                debug!("spartn: {} trailing bytes", data.len() - offset);
+                return;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packs big endian bit fields
+    fn pack(fields: &[(u32, usize)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut pos = 0;
+        for &(value, n) in fields {
+            for i in (0..n).rev() {
+                if pos % 8 == 0 {
+                    bytes.push(0);
+                }
+                bytes[pos / 8] |= (((value >> i) & 0x01) as u8) << (7 - pos % 8);
+                pos += 1;
+            }
+        }
+        bytes
+    }
+
+    /// Frame of given header fields, with a dummy payload
+    fn frame(header: &[(u32, usize)], payload_len: usize, crc_len: usize) -> Vec<u8> {
+        let mut frame = vec![PREAMBLE];
+        frame.extend(pack(header));
+        frame.extend(vec![0xA5; payload_len + crc_len]);
+        frame
+    }
+
+    #[test]
+    fn frame_headers() {
+        // OCB Galileo, 20 bytes, 24 bit CRC, full time tag
+        let ocb = frame(
+            &[
+                (0, 7),
+                (20, 10),
+                (0, 1),
+                (2, 2),
+                (0x0F, 4),
+                (2, 4),
+                (1, 1),
+                (123_456_789, 32),
+                (5, 7),
+                (1, 4),
+            ],
+            20,
+            3,
+        );
+        // HPAC GPS, 40 bytes, encrypted with 12 bytes of authentication, 8 bit CRC
+        let hpac = frame(
+            &[
+                (1, 7),
+                (40, 10),
+                (1, 1),
+                (0, 2),
+                (0x0F, 4),
+                (0, 4),
+                (0, 1),
+                (3600, 16),
+                (5, 7),
+                (1, 4),
+                (3, 4),
+                (10, 6),
+                (2, 3),
+                (1, 3),
+            ],
+            40 + 12,
+            1,
+        );
+
+        let (header, len) = decode(&ocb).unwrap();
+        assert_eq!(len, 33);
+        assert_eq!(len, ocb.len());
+        assert_eq!(
+            header,
+            Header {
+                msg_type: MessageType::Ocb,
+                subtype: 2,
+                time_tag: 123_456_789,
+                full_time_tag: true,
+                solution_id: 5,
+                processor_id: 1,
+                encrypted: false,
+                payload_len: 20,
+            }
+        );
+        assert_eq!(header.constellation(), Some("Galileo"));
+
+        // frames walked through, one after the other
+        let data = [ocb.clone(), hpac.clone()].concat();
+        let (header, len) = decode(&data[33..]).unwrap();
+        assert_eq!(len, hpac.len());
+        assert_eq!(header.msg_type, MessageType::Hpac);
+        assert_eq!(header.constellation(), Some("GPS"));
+        assert_eq!(header.time_tag, 3600);
+        assert!(!header.full_time_tag);
+        assert!(header.encrypted);
+        assert_eq!(header.payload_len, 40);
+
+        // incomplete frame, or no frame at all
+        assert!(decode(&ocb[..32]).is_none());
+        assert!(decode(&ocb[1..]).is_none());
+    }
+}
@@ -0,0 +1,293 @@
+//! JSON status snapshot, for monitoring
+use std::{fs::File, io::Result as IoResult, path::Path, time::Instant};
+
+use gnss_rtk::prelude::{Config, SV};
+use serde::Serialize;
+
+use crate::{antenna::AntennaState, pvt::Pvt, ublox::ParserStats};
+
+/// Snapshot schema version, increased on every breaking change
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Current fix
+#[derive(Debug, Serialize)]
+struct Fix {
+    epoch: String,
+    x: f64,
+    y: f64,
+    z: f64,
+    lat: f64,
+    lon: f64,
+    alt: f64,
+    dt: f64,
+    speed: f64,
+    heading: Option<f64>,
+    nb_sv: usize,
+    gdop: f64,
+    hdop: f64,
+    vdop: f64,
+    pdop: f64,
+    tdop: f64,
+    /// Time elapsed since this fix was obtained (s)
+    age: f64,
+    /// Time to first fix (s)
+    ttff: Option<f64>,
+    /// Composite quality score (0-100)
+    quality: Option<u8>,
+}
+
+/// Difference to the receiver's own fix
+#[derive(Debug, Serialize)]
+struct ReceiverOffset {
+    horizontal: f64,
+    vertical: f64,
+}
+
+/// Stream integrity
+#[derive(Debug, Serialize)]
+struct Parser {
+    malformed: u64,
+    dropped_bytes: u64,
+}
+
+/// Tracked SV
+#[derive(Debug, Serialize)]
+struct Satellite {
+    sv: String,
+    /// Contributed to the current fix
+    used: bool,
+    elevation: Option<f64>,
+    azimuth: Option<f64>,
+}
+
+/// Active solver configuration
+#[derive(Debug, Serialize)]
+struct Configuration {
+    method: String,
+    filter: String,
+    min_sv_elev: Option<f64>,
+    min_snr: Option<f64>,
+    fixed_altitude: Option<f64>,
+    gdop_threshold: Option<f64>,
+    tdop_threshold: Option<f64>,
+}
+
+/// Complete snapshot
+#[derive(Debug, Serialize)]
+struct Snapshot {
+    schema: u32,
+    fix: Option<Fix>,
+    satellites: Vec<Satellite>,
+    receiver_offset: Option<ReceiverOffset>,
+    antenna: String,
+    parser: Parser,
+    /// Solutions averaged while held stationary, None unless held
+    hold: Option<usize>,
+    config: Configuration,
+}
+
+/// Navigation state, as needed by the snapshot
+#[derive(Debug, Default)]
+pub struct Status {
+    /// Latest [Pvt], and when it was obtained
+    fix: Option<(Pvt, Instant)>,
+    /// Time to first fix (s)
+    ttff: Option<f64>,
+    /// SV proposed to the solver, for the latest epoch
+    tracked: Vec<SV>,
+    /// Horizontal and vertical difference to the receiver fix, for the latest epoch
+    receiver_offset: Option<(f64, f64)>,
+    /// Latest antenna state
+    antenna: AntennaState,
+    /// Latest parser statistics
+    parser: ParserStats,
+    /// Solutions averaged while held stationary
+    hold: Option<usize>,
+}
+
+impl Status {
+    /// Stores SV proposed to the solver
+    pub fn on_candidates(&mut self, sv: Vec<SV>) {
+        self.tracked = sv;
+    }
+
+    /// Stores latest [Pvt], and time to first fix
+    pub fn on_fix(&mut self, pvt: &Pvt, ttff: Option<f64>) {
+        self.fix = Some((pvt.clone(), Instant::now()));
+        self.ttff = ttff;
+    }
+
+    /// Stores latest difference to the receiver fix
+    pub fn on_comparison(&mut self, offset: Option<(f64, f64)>) {
+        self.receiver_offset = offset;
+    }
+
+    /// Stores latest antenna state
+    pub fn on_antenna(&mut self, state: AntennaState) {
+        self.antenna = state;
+    }
+
+    /// Stores latest parser statistics
+    pub fn on_parser_stats(&mut self, stats: ParserStats) {
+        self.parser = stats;
+    }
+
+    /// Stores stationary hold state: solutions averaged so far, None unless held
+    pub fn on_hold(&mut self, hold: Option<usize>) {
+        self.hold = hold;
+    }
+
+    /// Builds [Snapshot] of current state and active [Config]
+    fn snapshot(&self, cfg: &Config) -> Snapshot {
+        let fix = self.fix.as_ref().map(|(pvt, t)| {
+            let (x, y, z) = pvt.position;
+            let (lat, lon, alt) = pvt.geodetic;
+            Fix {
+                epoch: pvt.epoch.to_string(),
+                x,
+                y,
+                z,
+                lat,
+                lon,
+                alt,
+                dt: pvt.dt,
+                speed: pvt.speed,
+                heading: pvt.heading,
+                nb_sv: pvt.nb_sv,
+                gdop: pvt.gdop,
+                hdop: pvt.hdop,
+                vdop: pvt.vdop,
+                pdop: pvt.pdop,
+                tdop: pvt.tdop,
+                age: t.elapsed().as_secs_f64(),
+                ttff: self.ttff,
+                quality: pvt.quality,
+            }
+        });
+
+        let satellites = self
+            .tracked
+            .iter()
+            .map(|sv| {
+                let used = self
+                    .fix
+                    .as_ref()
+                    .and_then(|(pvt, _)| pvt.sv.iter().find(|(used, _, _)| used == sv));
+                Satellite {
+                    sv: sv.to_string(),
+                    used: used.is_some(),
+                    elevation: used.map(|(_, elev, _)| *elev),
+                    azimuth: used.map(|(_, _, azim)| *azim),
+                }
+            })
+            .collect();
+
+        Snapshot {
+            schema: SCHEMA_VERSION,
+            fix,
+            satellites,
+            receiver_offset: self
+                .receiver_offset
+                .map(|(horizontal, vertical)| ReceiverOffset {
+                    horizontal,
+                    vertical,
+                }),
+            antenna: format!("{:?}", self.antenna),
+            parser: Parser {
+                malformed: self.parser.malformed,
+                dropped_bytes: self.parser.dropped_bytes,
+            },
+            hold: self.hold,
+            config: Configuration {
+                method: format!("{:?}", cfg.method),
+                filter: format!("{:?}", cfg.solver.filter),
+                min_sv_elev: cfg.min_sv_elev,
+                min_snr: cfg.min_snr,
+                fixed_altitude: cfg.fixed_altitude,
+                gdop_threshold: cfg.solver.gdop_threshold,
+                tdop_threshold: cfg.solver.tdop_threshold,
+            },
+        }
+    }
+
+    /// Writes JSON snapshot to given file
+    pub fn dump(&self, cfg: &Config, path: &Path) -> IoResult<()> {
+        let fd = File::create(path)?;
+        serde_json::to_writer_pretty(fd, &self.snapshot(cfg))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Constellation, Epoch, Method};
+    use serde_json::Value;
+
+    #[test]
+    fn populated_snapshot() {
+        let (g01, g07, e11) = (
+            SV::new(Constellation::GPS, 1),
+            SV::new(Constellation::GPS, 7),
+            SV::new(Constellation::Galileo, 11),
+        );
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let mut pvt = Pvt::fixture(t, (4_201_575.0, 189_856.0, 4_779_066.0));
+        pvt.sv = vec![(g01, 45.0, 120.0), (e11, 30.5, 250.0)];
+        pvt.quality = Some(87);
+
+        let mut status = Status::default();
+        status.on_candidates(vec![g01, g07, e11]);
+        status.on_fix(&pvt, Some(31.5));
+        status.on_comparison(Some((0.75, 1.5)));
+        status.on_antenna(AntennaState::Ok);
+        status.on_parser_stats(ParserStats {
+            malformed: 2,
+            dropped_bytes: 120,
+        });
+        status.on_hold(Some(12));
+
+        let path = std::env::temp_dir().join("rt-navi-status.json");
+        let cfg = Config::static_preset(Method::SPP);
+        status.dump(&cfg, &path).unwrap();
+        let snapshot: Value = serde_json::from_reader(File::open(&path).unwrap()).unwrap();
+
+        assert_eq!(snapshot["schema"], SCHEMA_VERSION);
+        let fix = &snapshot["fix"];
+        assert_eq!(fix["epoch"], t.to_string());
+        assert_eq!(fix["x"], 4_201_575.0);
+        assert!((fix["lat"].as_f64().unwrap() - pvt.geodetic.0).abs() < 1.0E-12);
+        assert_eq!(fix["nb_sv"], 8);
+        assert!((fix["gdop"].as_f64().unwrap() - 1.9).abs() < 1.0E-12);
+        assert_eq!(fix["ttff"], 31.5);
+        assert_eq!(fix["quality"], 87);
+        assert!(fix["age"].as_f64().unwrap() >= 0.0);
+        assert!(fix["heading"].is_null());
+
+        let satellites = snapshot["satellites"].as_array().unwrap();
+        assert_eq!(satellites.len(), 3);
+        assert_eq!(satellites[0]["sv"], g01.to_string());
+        assert_eq!(satellites[0]["used"], true);
+        assert_eq!(satellites[0]["elevation"], 45.0);
+        assert_eq!(satellites[1]["used"], false);
+        assert!(satellites[1]["azimuth"].is_null());
+        assert_eq!(satellites[2]["azimuth"], 250.0);
+
+        assert_eq!(snapshot["receiver_offset"]["vertical"], 1.5);
+        assert_eq!(snapshot["antenna"], "Ok");
+        assert_eq!(snapshot["parser"]["dropped_bytes"], 120);
+        assert_eq!(snapshot["hold"], 12);
+        assert_eq!(snapshot["config"]["method"], "SPP");
+        assert!(snapshot["config"].get("fixed_altitude").is_some());
+    }
+
+    #[test]
+    fn empty_snapshot() {
+        let cfg = Config::static_preset(Method::SPP);
+        let snapshot = serde_json::to_value(Status::default().snapshot(&cfg)).unwrap();
+        assert_eq!(snapshot["schema"], SCHEMA_VERSION);
+        assert!(snapshot["fix"].is_null());
+        assert!(snapshot["hold"].is_null());
+        assert_eq!(snapshot["satellites"], Value::Array(Vec::new()));
+    }
+}
@@ -0,0 +1,261 @@
+//! Session summary, written on exit
+use std::{
+    io::{Result as IoResult, Write},
+    time::Instant,
+};
+
+use gnss_rtk::prelude::{Constellation, Epoch};
+
+use crate::{coords::ecef_to_geodetic, pvt::Pvt, ublox::ParserStats};
+
+/// Fixes are considered static, and their mean position reported,
+/// below this 3D standard deviation (m)
+const STATIC_SIGMA_M: f64 = 5.0;
+
+/// Session counters, aggregated while running
+#[derive(Debug)]
+pub struct Summary {
+    /// Processing start
+    started: Instant,
+    /// First and latest measurement [Epoch]
+    span: Option<(Epoch, Epoch)>,
+    /// Epochs processed (candidates proposed)
+    epochs: usize,
+    /// Fixes produced
+    fixes: usize,
+    /// First fix (ECEF): positions are accumulated as offsets to it,
+    /// so the sums of squares keep their precision
+    origin: Option<(f64, f64, f64)>,
+    /// Position offsets sums and sums of squares, for mean and standard deviation
+    sum: (f64, f64, f64),
+    sum_sq: (f64, f64, f64),
+    /// GDOP sum, for the mean GDOP
+    gdop: f64,
+    /// Constellations that contributed to a fix
+    constellations: Vec<Constellation>,
+    /// Latest parser statistics
+    parser: ParserStats,
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            span: None,
+            epochs: 0,
+            fixes: 0,
+            origin: None,
+            sum: (0.0, 0.0, 0.0),
+            sum_sq: (0.0, 0.0, 0.0),
+            gdop: 0.0,
+            constellations: Vec::new(),
+            parser: ParserStats::default(),
+        }
+    }
+}
+
+impl Summary {
+    /// New epoch processed
+    pub fn on_epoch(&mut self, t: Epoch) {
+        self.epochs += 1;
+        self.span = match self.span {
+            Some((first, _)) => Some((first, t)),
+            None => Some((t, t)),
+        };
+    }
+
+    /// New fix
+    pub fn on_fix(&mut self, pvt: &Pvt) {
+        let (x0, y0, z0) = *self.origin.get_or_insert(pvt.position);
+        let (x, y, z) = (
+            pvt.position.0 - x0,
+            pvt.position.1 - y0,
+            pvt.position.2 - z0,
+        );
+        self.fixes += 1;
+        self.sum = (self.sum.0 + x, self.sum.1 + y, self.sum.2 + z);
+        self.sum_sq = (
+            self.sum_sq.0 + x.powi(2),
+            self.sum_sq.1 + y.powi(2),
+            self.sum_sq.2 + z.powi(2),
+        );
+        self.gdop += pvt.gdop;
+        for (sv, _, _) in pvt.sv.iter() {
+            if !self.constellations.contains(&sv.constellation) {
+                self.constellations.push(sv.constellation);
+            }
+        }
+    }
+
+    /// Latest parser statistics
+    pub fn on_parser_stats(&mut self, stats: ParserStats) {
+        self.parser = stats;
+    }
+
+    /// Returns mean offset to the first fix
+    fn mean_offset(&self) -> (f64, f64, f64) {
+        let n = self.fixes as f64;
+        (self.sum.0 / n, self.sum.1 / n, self.sum.2 / n)
+    }
+
+    /// Returns mean position (ECEF)
+    fn mean(&self) -> (f64, f64, f64) {
+        let (x0, y0, z0) = self.origin.unwrap_or_default();
+        let (dx, dy, dz) = self.mean_offset();
+        (x0 + dx, y0 + dy, z0 + dz)
+    }
+
+    /// Returns standard deviation (m) on each ECEF axis
+    fn sigma(&self) -> (f64, f64, f64) {
+        let n = self.fixes as f64;
+        let (x, y, z) = self.mean_offset();
+        (
+            (self.sum_sq.0 / n - x.powi(2)).max(0.0).sqrt(),
+            (self.sum_sq.1 / n - y.powi(2)).max(0.0).sqrt(),
+            (self.sum_sq.2 / n - z.powi(2)).max(0.0).sqrt(),
+        )
+    }
+
+    /// Writes the summary, given the time to first fix (s), if obtained
+    pub fn write<W: Write>(&self, w: &mut W, ttff: Option<f64>) -> IoResult<()> {
+        writeln!(w, "duration={:.1}s", self.started.elapsed().as_secs_f64())?;
+        if let Some((first, last)) = self.span {
+            writeln!(w, "first_epoch={} last_epoch={}", first, last)?;
+        }
+        writeln!(w, "epochs={} fixes={}", self.epochs, self.fixes)?;
+        if self.epochs > 0 {
+            writeln!(
+                w,
+                "fix_rate={:.1}%",
+                self.fixes as f64 / self.epochs as f64 * 100.0
+            )?;
+        }
+        match ttff {
+            Some(ttff) => writeln!(w, "ttff={:.1}s", ttff)?,
+            None => writeln!(w, "ttff=none")?,
+        }
+        if self.fixes > 0 {
+            writeln!(w, "mean_gdop={:.2}", self.gdop / self.fixes as f64)?;
+            let constellations = self
+                .constellations
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>();
+            writeln!(w, "constellations={}", constellations.join(","))?;
+
+            let (sigma_x, sigma_y, sigma_z) = self.sigma();
+            writeln!(
+                w,
+                "sigma_x={:.4} sigma_y={:.4} sigma_z={:.4}",
+                sigma_x, sigma_y, sigma_z
+            )?;
+            let sigma = (sigma_x.powi(2) + sigma_y.powi(2) + sigma_z.powi(2)).sqrt();
+            if sigma < STATIC_SIGMA_M {
+                let (x, y, z) = self.mean();
+                let (lat, lon, alt) = ecef_to_geodetic(x, y, z);
+                writeln!(w, "x={:.4} y={:.4} z={:.4}", x, y, z)?;
+                writeln!(w, "lat={:.9} lon={:.9} alt={:.4}", lat, lon, alt)?;
+            }
+        }
+        writeln!(
+            w,
+            "malformed_packets={} dropped_bytes={}",
+            self.parser.malformed, self.parser.dropped_bytes
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Duration, SV};
+
+    /// Written summary, without its (wall clock) duration
+    fn written(summary: &Summary, ttff: Option<f64>) -> Vec<String> {
+        let mut buf = Vec::new();
+        summary.write(&mut buf, ttff).unwrap();
+        String::from_utf8(buf)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn scripted_session() {
+        let g01 = SV::new(Constellation::GPS, 1);
+        let e11 = SV::new(Constellation::Galileo, 11);
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let t = |s: f64| t0 + Duration::from_seconds(s);
+        let mut summary = Summary::default();
+
+        // 4 epochs, the first one without a fix
+        summary.on_epoch(t0);
+        for (i, (dx, gdop, sv)) in [
+            (-1.0, 1.5, vec![g01]),
+            (0.0, 2.0, vec![g01]),
+            (1.0, 2.5, vec![g01, e11]),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let epoch = t(i as f64 + 1.0);
+            summary.on_epoch(epoch);
+            summary.on_fix(&Pvt {
+                gdop,
+                sv: sv.into_iter().map(|sv| (sv, 45.0, 90.0)).collect(),
+                ..Pvt::fixture(epoch, (4_201_575.0 + dx, 189_856.0, 4_779_066.0))
+            });
+        }
+        summary.on_parser_stats(ParserStats {
+            malformed: 2,
+            dropped_bytes: 17,
+        });
+
+        let (lat, lon, alt) = ecef_to_geodetic(4_201_575.0, 189_856.0, 4_779_066.0);
+        assert_eq!(
+            written(&summary, Some(12.34)),
+            vec![
+                format!("first_epoch={} last_epoch={}", t0, t(3.0)),
+                "epochs=4 fixes=3".to_string(),
+                "fix_rate=75.0%".to_string(),
+                "ttff=12.3s".to_string(),
+                "mean_gdop=2.00".to_string(),
+                format!(
+                    "constellations={},{}",
+                    Constellation::GPS,
+                    Constellation::Galileo
+                ),
+                "sigma_x=0.8165 sigma_y=0.0000 sigma_z=0.0000".to_string(),
+                "x=4201575.0000 y=189856.0000 z=4779066.0000".to_string(),
+                format!("lat={:.9} lon={:.9} alt={:.4}", lat, lon, alt),
+                "malformed_packets=2 dropped_bytes=17".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn moving_session() {
+        let t0 = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let mut summary = Summary::default();
+        assert_eq!(
+            written(&summary, None),
+            vec![
+                "epochs=0 fixes=0".to_string(),
+                "ttff=none".to_string(),
+                "malformed_packets=0 dropped_bytes=0".to_string(),
+            ]
+        );
+
+        for (i, dx) in [0.0, 10.0, 20.0].into_iter().enumerate() {
+            let t = t0 + Duration::from_seconds(i as f64);
+            summary.on_epoch(t);
+            summary.on_fix(&Pvt::fixture(t, (4_201_575.0 + dx, 189_856.0, 4_779_066.0)));
+        }
+        // no mean position: not static
+        let lines = written(&summary, Some(1.0));
+        assert!(lines.contains(&"sigma_x=8.1650 sigma_y=0.0000 sigma_z=0.0000".to_string()));
+        assert!(!lines.iter().any(|line| line.starts_with("x=")));
+    }
+}
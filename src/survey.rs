@@ -0,0 +1,222 @@
+//! Static survey
+use std::{
+    fs::File,
+    io::{Result as IoResult, Write},
+    path::PathBuf,
+};
+
+use gnss_rtk::prelude::{Duration, Epoch};
+
+use crate::{coords::ecef_to_geodetic, output::SolutionSink, pvt::Pvt};
+
+/// Survey options
+pub struct SurveyOpts {
+    /// Survey duration
+    pub duration: Duration,
+    /// Optional marker file, written on completion
+    pub marker: Option<PathBuf>,
+}
+
+/// Averages all solutions (ECEF) over the survey window
+pub struct Survey {
+    opts: SurveyOpts,
+    start: Option<Epoch>,
+    count: usize,
+    sum: (f64, f64, f64),
+    sum_sq: (f64, f64, f64),
+    completed: bool,
+}
+
+impl Survey {
+    pub fn new(opts: SurveyOpts) -> Self {
+        Self {
+            opts,
+            start: None,
+            count: 0,
+            sum: (0.0, 0.0, 0.0),
+            sum_sq: (0.0, 0.0, 0.0),
+            completed: false,
+        }
+    }
+
+    /// Returns mean position (ECEF)
+    fn mean(&self) -> (f64, f64, f64) {
+        let n = self.count as f64;
+        (self.sum.0 / n, self.sum.1 / n, self.sum.2 / n)
+    }
+
+    /// Returns standard deviation (m) on each ECEF axis
+    fn sigma(&self) -> (f64, f64, f64) {
+        let n = self.count as f64;
+        let (x, y, z) = self.mean();
+        (
+            (self.sum_sq.0 / n - x.powi(2)).max(0.0).sqrt(),
+            (self.sum_sq.1 / n - y.powi(2)).max(0.0).sqrt(),
+            (self.sum_sq.2 / n - z.powi(2)).max(0.0).sqrt(),
+        )
+    }
+
+    /// Reports survey results
+    fn report(&mut self) {
+        self.completed = true;
+        if self.count == 0 {
+            warn!("survey: no solution collected");
+            return;
+        }
+
+        let (x, y, z) = self.mean();
+        let (lat, lon, alt) = ecef_to_geodetic(x, y, z);
+        let (sigma_x, sigma_y, sigma_z) = self.sigma();
+
+        info!(
+            "survey completed: {} solutions lat={:.8} lon={:.8} alt={:.3}m sigma=({:.3}, {:.3}, {:.3})m",
+            self.count, lat, lon, alt, sigma_x, sigma_y, sigma_z
+        );
+
+        if let Some(path) = &self.opts.marker {
+            let written = File::create(path).and_then(|mut fd| {
+                writeln!(fd, "count={}", self.count)?;
+                writeln!(fd, "x={:.4} y={:.4} z={:.4}", x, y, z)?;
+                writeln!(fd, "lat={:.9} lon={:.9} alt={:.4}", lat, lon, alt)?;
+                writeln!(
+                    fd,
+                    "sigma_x={:.4} sigma_y={:.4} sigma_z={:.4}",
+                    sigma_x, sigma_y, sigma_z
+                )
+            });
+            match written {
+                Ok(_) => info!("survey: {} generated", path.display()),
+                Err(e) => error!("survey: failed to write {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+impl SolutionSink for Survey {
+    fn on_fix(&mut self, pvt: &Pvt) -> IoResult<()> {
+        if self.completed {
+            return Ok(());
+        }
+
+        let start = *self.start.get_or_insert(pvt.epoch);
+        if pvt.epoch - start > self.opts.duration {
+            self.report();
+            return Ok(());
+        }
+
+        let (x, y, z) = pvt.position;
+        self.count += 1;
+        self.sum = (self.sum.0 + x, self.sum.1 + y, self.sum.2 + z);
+        self.sum_sq = (
+            self.sum_sq.0 + x.powi(2),
+            self.sum_sq.1 + y.powi(2),
+            self.sum_sq.2 + z.powi(2),
+        );
+
+        let (sigma_x, sigma_y, sigma_z) = self.sigma();
+        info!(
+            "survey: {} solutions, sigma=({:.3}, {:.3}, {:.3})m",
+            self.count, sigma_x, sigma_y, sigma_z
+        );
+        Ok(())
+    }
+    fn reset(&mut self) {
+        if self.completed {
+            return;
+        }
+        info!("survey: restarted");
+        self.start = None;
+        self.count = 0;
+        self.sum = (0.0, 0.0, 0.0);
+        self.sum_sq = (0.0, 0.0, 0.0);
+    }
+    fn flush(&mut self) {
+        if !self.completed {
+            warn!("survey: interrupted");
+            self.report();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+
+    const TRUTH: (f64, f64, f64) = (4_027_893.0, 307_045.0, 4_919_475.0);
+
+    /// Deterministic noise, uniform within +/- 2 m
+    fn noise(seed: &mut u64) -> f64 {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((*seed >> 11) as f64 / (1_u64 << 53) as f64 - 0.5) * 4.0
+    }
+
+    fn survey(marker: Option<PathBuf>) -> Survey {
+        Survey::new(SurveyOpts {
+            duration: Duration::from_seconds(60.0),
+            marker,
+        })
+    }
+
+    #[test]
+    fn static_average_converges() {
+        let marker = std::env::temp_dir().join("rt-navi-survey-test.txt");
+        let mut survey = survey(Some(marker.clone()));
+        let t0 = Epoch::from_gpst_seconds(1_000_000.0);
+        let mut seed = 1;
+
+        // one solution per second, one past the survey window
+        for i in 0..=61 {
+            let position = (
+                TRUTH.0 + noise(&mut seed),
+                TRUTH.1 + noise(&mut seed),
+                TRUTH.2 + noise(&mut seed),
+            );
+            let t = t0 + Duration::from_seconds(i as f64);
+            survey.on_fix(&Pvt::fixture(t, position)).unwrap();
+        }
+        assert!(survey.completed);
+        assert_eq!(survey.count, 61);
+
+        // within the reported sigma, which is that of uniform noise (1.15 m)
+        let (x, y, z) = survey.mean();
+        let (sigma_x, sigma_y, sigma_z) = survey.sigma();
+        for (mean, truth, sigma) in [
+            (x, TRUTH.0, sigma_x),
+            (y, TRUTH.1, sigma_y),
+            (z, TRUTH.2, sigma_z),
+        ] {
+            assert!((sigma - 1.15).abs() < 0.3, "sigma={}", sigma);
+            assert!((mean - truth).abs() < sigma, "error={}", mean - truth);
+        }
+
+        let marker = read_to_string(marker).unwrap();
+        assert!(marker.starts_with("count=61\n"));
+
+        // completed: further solutions are ignored
+        survey.on_fix(&Pvt::fixture(t0, (0.0, 0.0, 0.0))).unwrap();
+        assert_eq!(survey.count, 61);
+    }
+
+    #[test]
+    fn reset_restarts() {
+        let mut survey = survey(None);
+        let t0 = Epoch::from_gpst_seconds(1_000_000.0);
+        for i in 0..10 {
+            let t = t0 + Duration::from_seconds(i as f64);
+            survey.on_fix(&Pvt::fixture(t, (0.0, 0.0, 0.0))).unwrap();
+        }
+        survey.reset();
+        assert_eq!(survey.count, 0);
+
+        // new window, from the next solution
+        for i in 10..60 {
+            let t = t0 + Duration::from_seconds(i as f64);
+            survey.on_fix(&Pvt::fixture(t, TRUTH)).unwrap();
+        }
+        assert!(!survey.completed);
+        assert_eq!(survey.mean(), TRUTH);
+    }
+}
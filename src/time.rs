@@ -0,0 +1,193 @@
+//! Time system and leap seconds
+use std::str::FromStr;
+
+use gnss_rtk::prelude::{Epoch, TimeScale};
+
+use crate::Error;
+
+/// TAI - GPST offset (s)
+const GPST_TAI_OFFSET: i32 = 19;
+
+/// Returns GPST - UTC leap seconds at `t`, according to the leap second table
+pub fn table_leap_seconds(t: Epoch) -> Option<i32> {
+    let tai_utc = t.leap_seconds(true)?;
+    Some(tai_utc as i32 - GPST_TAI_OFFSET)
+}
+
+/// Tracks the GPST - UTC leap seconds broadcast in the navigation message
+#[derive(Debug, Default)]
+pub struct LeapSeconds {
+    /// Latest broadcast value
+    broadcast: Option<i32>,
+}
+
+impl LeapSeconds {
+    /// Updates with broadcast value, decoded at `t`.
+    /// Changes are reported, mismatches with our table are flagged:
+    /// returns the table value when a new broadcast value differs from it.
+    pub fn update(&mut self, t: Epoch, broadcast: i32) -> Option<i32> {
+        if self.broadcast == Some(broadcast) {
+            return None;
+        }
+        self.broadcast = Some(broadcast);
+        info!("{} time system: GPST, GPST-UTC={}s", t, broadcast);
+        match table_leap_seconds(t) {
+            Some(table) if table != broadcast => {
+                warn!(
+                    "{} broadcast GPST-UTC={}s differs from leap second table ({}s)",
+                    t, broadcast, table
+                );
+                Some(table)
+            },
+            Some(_) => None,
+            None => {
+                warn!("{} leap second table does not cover this epoch", t);
+                None
+            },
+        }
+    }
+}
+
+/// Time the receiver is currently reported with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeSource {
+    /// NAV-PVT UTC time, once the receiver resolved it
+    NavPvt,
+    /// RAWX measurement time (GPST), when NAV-PVT time is not valid
+    Rawx,
+}
+
+/// How solution epochs are rendered by the JSON and CSV outputs
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TimestampFormat {
+    /// Epoch, expressed in the output timescale
+    #[default]
+    Epoch,
+    /// ISO8601 UTC, for example 2024-03-01T12:00:00.000000000Z
+    Iso,
+    /// GPS week and time of week (s)
+    Week,
+    /// Nanoseconds since the Unix epoch (UTC)
+    Unix,
+}
+
+impl FromStr for TimestampFormat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "epoch" => Ok(Self::Epoch),
+            "iso" => Ok(Self::Iso),
+            "week" => Ok(Self::Week),
+            "unix" => Ok(Self::Unix),
+            _ => Err(Error::InvalidTimestamp(s.to_string())),
+        }
+    }
+}
+
+impl TimestampFormat {
+    /// Renders `epoch` as field values: the epoch format is expressed in `timescale`
+    fn values(&self, epoch: Epoch, timescale: TimeScale) -> Vec<String> {
+        match self {
+            Self::Epoch => vec![epoch.to_time_scale(timescale).to_string()],
+            Self::Iso => {
+                let (y, m, d, hh, mm, ss, nanos) = epoch.to_gregorian_utc();
+                vec![format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+                    y, m, d, hh, mm, ss, nanos
+                )]
+            },
+            Self::Week => {
+                let (week, tow_ns) = epoch.to_time_scale(TimeScale::GPST).to_time_of_week();
+                vec![week.to_string(), format!("{:.9}", tow_ns as f64 * 1.0E-9)]
+            },
+            Self::Unix => vec![epoch.to_unix_duration().total_nanoseconds().to_string()],
+        }
+    }
+
+    /// Field names
+    fn names(&self) -> &'static [&'static str] {
+        match self {
+            Self::Epoch | Self::Iso => &["epoch"],
+            Self::Week => &["week", "tow"],
+            Self::Unix => &["unix_ns"],
+        }
+    }
+
+    /// CSV header columns
+    pub fn csv_header(&self) -> String {
+        self.names().join(",")
+    }
+
+    /// CSV columns of `epoch`
+    pub fn csv(&self, epoch: Epoch, timescale: TimeScale) -> String {
+        self.values(epoch, timescale).join(",")
+    }
+
+    /// JSON members of `epoch`: text formats are quoted, numeric ones are not
+    pub fn json(&self, epoch: Epoch, timescale: TimeScale) -> String {
+        let quote = matches!(self, Self::Epoch | Self::Iso);
+        self.names()
+            .iter()
+            .zip(self.values(epoch, timescale))
+            .map(|(name, value)| {
+                if quote {
+                    format!("\"{}\":\"{}\"", name, value)
+                } else {
+                    format!("\"{}\":{}", name, value)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broadcast_mismatch_flagged() {
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        assert_eq!(table_leap_seconds(t), Some(18));
+
+        let mut leap_seconds = LeapSeconds::default();
+        assert_eq!(leap_seconds.update(t, 18), None);
+        // broadcast dtLS differs from the table
+        assert_eq!(leap_seconds.update(t, 17), Some(18));
+        // flagged once
+        assert_eq!(leap_seconds.update(t, 17), None);
+        assert_eq!(leap_seconds.update(t, 18), None);
+    }
+
+    #[test]
+    fn timestamp_formats() {
+        let t = Epoch::from_gregorian_utc(2024, 3, 1, 12, 0, 0, 0);
+
+        let iso = TimestampFormat::Iso;
+        assert_eq!(iso.csv_header(), "epoch");
+        assert_eq!(
+            iso.json(t, TimeScale::GPST),
+            "\"epoch\":\"2024-03-01T12:00:00.000000000Z\""
+        );
+
+        // GPST is 18 s ahead of UTC: friday 12:00:18
+        let week = TimestampFormat::Week;
+        assert_eq!(week.csv_header(), "week,tow");
+        assert_eq!(week.csv(t, TimeScale::UTC), "2303,475218.000000000");
+        assert_eq!(
+            week.json(t, TimeScale::UTC),
+            "\"week\":2303,\"tow\":475218.000000000"
+        );
+
+        let unix = TimestampFormat::Unix;
+        assert_eq!(unix.csv_header(), "unix_ns");
+        assert_eq!(unix.csv(t, TimeScale::GPST), "1709294400000000000");
+        assert_eq!(
+            unix.json(t, TimeScale::GPST),
+            "\"unix_ns\":1709294400000000000"
+        );
+
+        assert_eq!(TimestampFormat::from_str("ISO").unwrap(), iso);
+        assert!(TimestampFormat::from_str("rfc2822").is_err());
+    }
+}
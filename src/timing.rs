@@ -0,0 +1,152 @@
+//! Time only navigation, from a known position
+use std::str::FromStr;
+
+use gnss_rtk::prelude::{AprioriPosition, Config, Epoch, PVTSolutionType, Vector3};
+
+use crate::{coords::geodetic_to_ecef, Error};
+
+/// Minimal number of SV this solver [Config] resolves its unknowns with:
+/// 4, 3 with fixed altitude, only the clock in time only navigation
+pub fn min_sv(cfg: &Config) -> usize {
+    if matches!(cfg.sol_type, PVTSolutionType::TimeOnly) {
+        1
+    } else if cfg.fixed_altitude.is_some() {
+        3
+    } else {
+        4
+    }
+}
+
+/// Skips resolutions until enough satellites are proposed
+#[derive(Debug)]
+pub struct SvThreshold {
+    /// Minimal number of SV requested (--min-sv), if more than the unknowns
+    user_min_sv: usize,
+    /// Skipping resolutions
+    waiting: bool,
+}
+
+impl SvThreshold {
+    pub fn new(user_min_sv: usize) -> Self {
+        Self {
+            user_min_sv,
+            waiting: false,
+        }
+    }
+
+    /// Returns true when `nb_sv` distinct SV are enough to resolve at `t` with this [Config].
+    /// "waiting for satellites" is reported once, then when enough are back.
+    /// The solver estimates a single clock, whatever the constellations.
+    pub fn ready(&mut self, t: Epoch, cfg: &Config, nb_sv: usize) -> bool {
+        let min_sv = min_sv(cfg).max(self.user_min_sv);
+        if nb_sv < min_sv {
+            if self.waiting {
+                debug!("{} waiting for satellites: {}/{}", t, nb_sv, min_sv);
+            } else {
+                warn!("{} waiting for satellites: {}/{}", t, nb_sv, min_sv);
+                self.waiting = true;
+            }
+            return false;
+        }
+        if self.waiting {
+            info!("{} {} satellites: resolving", t, nb_sv);
+            self.waiting = false;
+        }
+        true
+    }
+}
+
+/// Antenna position, known beforehand (surveyed)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KnownPosition {
+    /// Latitude (ddeg)
+    pub lat: f64,
+    /// Longitude (ddeg)
+    pub lon: f64,
+    /// Altitude above ellipsoid (m)
+    pub alt: f64,
+}
+
+impl FromStr for KnownPosition {
+    type Err = Error;
+    /// Parses "lat,lon,alt" (ddeg, ddeg, m)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let items = s
+            .split(',')
+            .map(|item| item.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Error::InvalidPosition(s.to_string()))?;
+        match items[..] {
+            [lat, lon, alt] if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => {
+                Ok(Self { lat, lon, alt })
+            },
+            _ => Err(Error::InvalidPosition(s.to_string())),
+        }
+    }
+}
+
+impl KnownPosition {
+    /// Switches solver [Config] to time only navigation: the position is held,
+    /// only the clock is resolved, which a single SV is enough for.
+    pub fn time_only(&self, cfg: &mut Config) {
+        cfg.sol_type = PVTSolutionType::TimeOnly;
+        cfg.fixed_altitude = Some(self.alt);
+    }
+
+    /// Returns this position as the solver [AprioriPosition]
+    pub fn apriori(&self) -> AprioriPosition {
+        let (x, y, z) = geodetic_to_ecef(self.lat, self.lon, self.alt);
+        AprioriPosition::from_ecef(Vector3::new(x, y, z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::Method;
+
+    #[test]
+    fn clock_only_from_known_position() {
+        let known = KnownPosition::from_str("45.0, 5.0, 102.5").unwrap();
+        let mut cfg = Config::static_preset(Method::SPP);
+        assert_eq!(min_sv(&cfg), 4);
+
+        // position held: two SV are more than enough
+        known.time_only(&mut cfg);
+        assert!(matches!(cfg.sol_type, PVTSolutionType::TimeOnly));
+        assert_eq!(cfg.fixed_altitude, Some(102.5));
+        assert_eq!(min_sv(&cfg), 1);
+
+        assert!(KnownPosition::from_str("45.0,5.0").is_err());
+        assert!(KnownPosition::from_str("95.0,5.0,0.0").is_err());
+    }
+
+    #[test]
+    fn no_resolution_below_threshold() {
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        let cfg = Config::static_preset(Method::SPP);
+        let mut threshold = SvThreshold::new(6);
+
+        let mut resolved = Vec::new();
+        for nb_sv in [3, 5, 6, 7, 4, 6] {
+            if threshold.ready(t, &cfg, nb_sv) {
+                resolved.push(nb_sv);
+            }
+        }
+        assert_eq!(resolved, vec![6, 7, 6]);
+
+        // the geometric minimum applies, when above the requested one
+        let mut threshold = SvThreshold::new(2);
+        assert!(!threshold.ready(t, &cfg, 3));
+        assert!(threshold.waiting);
+        assert!(threshold.ready(t, &cfg, 4));
+        assert!(!threshold.waiting);
+    }
+
+    #[test]
+    fn fixed_altitude_needs_three_sv() {
+        let mut cfg = Config::static_preset(Method::SPP);
+        cfg.fixed_altitude = Some(51.2);
+        assert_eq!(min_sv(&cfg), 3);
+    }
+}
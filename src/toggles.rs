@@ -0,0 +1,196 @@
+//! Runtime constellation and SV toggles
+use std::{collections::HashSet, str::FromStr};
+
+use gnss_rtk::prelude::{Candidate, Constellation, SV};
+
+/// Constellation toggled by each key
+const KEYS: [(&str, Constellation); 6] = [
+    ("1", Constellation::GPS),
+    ("2", Constellation::Galileo),
+    ("3", Constellation::BeiDou),
+    ("4", Constellation::QZSS),
+    ("5", Constellation::Glonass),
+    ("6", Constellation::SBAS),
+];
+
+/// Toggles the altitude constraint (2D navigation)
+pub const ALTITUDE_KEY: &str = "a";
+
+/// Holds the receiver stationary (static navigation and averaging), or releases it
+pub const HOLD_KEY: &str = "h";
+
+/// Switches the navigation method between SPP and PPP, resetting the solver
+pub const METHOD_KEY: &str = "m";
+
+/// Writes the status snapshot
+pub const STATUS_KEY: &str = "s";
+
+/// Clears tracks, survey statistics and history (the solver is preserved)
+pub const RESET_KEY: &str = "r";
+
+/// Browses history, one epoch back
+pub const OLDER_KEY: &str = "<";
+
+/// Browses history, one epoch forward
+pub const NEWER_KEY: &str = ">";
+
+/// Constellations and SV the user excluded from navigation
+#[derive(Debug, Default)]
+pub struct Toggles {
+    disabled: HashSet<Constellation>,
+    /// Excluded SV
+    excluded: HashSet<SV>,
+    /// Only SV allowed, when defined
+    included: Option<HashSet<SV>>,
+}
+
+impl Toggles {
+    /// Builds [Toggles] from the SV exclusion list and inclusion list (whitelist)
+    pub fn new(excluded: Vec<SV>, included: Option<Vec<SV>>) -> Self {
+        Self {
+            disabled: HashSet::new(),
+            excluded: excluded.into_iter().collect(),
+            included: included.map(|included| included.into_iter().collect()),
+        }
+    }
+
+    /// True unless this SV is excluded, or missing from the inclusion list
+    fn is_allowed(&self, sv: SV) -> bool {
+        !self.excluded.contains(&sv)
+            && self
+                .included
+                .as_ref()
+                .map_or(true, |included| included.contains(&sv))
+    }
+
+    /// Logs available keys
+    pub fn help(&self) {
+        for (key, constellation) in KEYS.iter() {
+            info!("press {} + enter to toggle {}", key, constellation);
+        }
+        info!(
+            "press {} + enter to toggle altitude constraint",
+            ALTITUDE_KEY
+        );
+        info!(
+            "press {} + enter to clear tracks, survey and history",
+            RESET_KEY
+        );
+        info!("press {} + enter to hold / release stationary", HOLD_KEY);
+        info!("press {} + enter to switch between SPP and PPP", METHOD_KEY);
+        info!("type a satellite (for example G07) + enter to toggle it");
+    }
+
+    /// Toggles the constellation associated to this key, or the SV it designates, if any
+    pub fn toggle(&mut self, key: &str) {
+        let constellation = match KEYS.iter().find(|(k, _)| *k == key.trim()) {
+            Some((_, constellation)) => *constellation,
+            None => {
+                if let Ok(sv) = SV::from_str(key.trim()) {
+                    self.toggle_sv(sv);
+                }
+                return;
+            },
+        };
+        if self.disabled.remove(&constellation) {
+            info!("{} enabled", constellation);
+        } else {
+            self.disabled.insert(constellation);
+            info!("{} disabled", constellation);
+        }
+    }
+
+    /// Excludes this SV, or enables it again (adding it to the inclusion list, if any)
+    fn toggle_sv(&mut self, sv: SV) {
+        if self.is_allowed(sv) {
+            self.excluded.insert(sv);
+            info!("{} disabled", sv);
+        } else {
+            self.excluded.remove(&sv);
+            if let Some(included) = self.included.as_mut() {
+                included.insert(sv);
+            }
+            if self.disabled.contains(&sv.constellation) {
+                info!("{} enabled, once {} is", sv, sv.constellation);
+            } else {
+                info!("{} enabled", sv);
+            }
+        }
+    }
+
+    /// Removes candidates of all disabled constellations and SV
+    pub fn retain(&self, candidates: &mut Vec<Candidate>) {
+        candidates
+            .retain(|cd| !self.disabled.contains(&cd.sv.constellation) && self.is_allowed(cd.sv));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::{Carrier, Duration, Epoch, PseudoRange};
+
+    fn candidates(sv: &[SV]) -> Vec<Candidate> {
+        let t = Epoch::from_gregorian_utc_at_midnight(2024, 3, 1);
+        sv.iter()
+            .map(|sv| {
+                let pr = PseudoRange {
+                    carrier: Carrier::L1,
+                    value: 2.2E7,
+                    snr: None,
+                };
+                Candidate::new(*sv, t, Duration::default(), None, vec![pr], vec![])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn disabled_constellation_removed() {
+        let g01 = SV::new(Constellation::GPS, 1);
+        let e11 = SV::new(Constellation::Galileo, 11);
+        let c20 = SV::new(Constellation::BeiDou, 20);
+        let mut toggles = Toggles::default();
+
+        toggles.toggle("2");
+        let mut cd = candidates(&[g01, e11, c20]);
+        toggles.retain(&mut cd);
+        assert_eq!(cd.iter().map(|cd| cd.sv).collect::<Vec<_>>(), [g01, c20]);
+
+        // enabled again
+        toggles.toggle(" 2 ");
+        let mut cd = candidates(&[g01, e11, c20]);
+        toggles.retain(&mut cd);
+        assert_eq!(cd.len(), 3);
+    }
+
+    #[test]
+    fn excluded_sv_never_proposed() {
+        let g01 = SV::new(Constellation::GPS, 1);
+        let g07 = SV::new(Constellation::GPS, 7);
+        let e05 = SV::new(Constellation::Galileo, 5);
+        let e11 = SV::new(Constellation::Galileo, 11);
+        let proposed = |toggles: &Toggles| {
+            let mut cd = candidates(&[g01, g07, e05, e11]);
+            toggles.retain(&mut cd);
+            cd.iter().map(|cd| cd.sv).collect::<Vec<_>>()
+        };
+
+        let mut toggles = Toggles::new(vec![g07, e11], None);
+        for _ in 0..3 {
+            assert_eq!(proposed(&toggles), [g01, e05]);
+        }
+
+        // toggled while running
+        toggles.toggle("G07");
+        toggles.toggle("E05");
+        assert_eq!(proposed(&toggles), [g01, g07]);
+
+        // excluded, even when whitelisted
+        let mut toggles = Toggles::new(vec![g07], Some(vec![g01, g07]));
+        assert_eq!(proposed(&toggles), [g01]);
+
+        // enabled again: added to the whitelist
+        toggles.toggle("E11");
+        assert_eq!(proposed(&toggles), [g01, e11]);
+    }
+}
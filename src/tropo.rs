@@ -0,0 +1,150 @@
+//! Blind troposphere models
+use std::{f64::consts::PI, str::FromStr};
+
+use crate::Error;
+
+/// Troposphere model
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TropoModel {
+    /// Niell model, built in the solver
+    #[default]
+    Niell,
+    /// UNB3m zenith delays, from latitude, altitude and day of year
+    Unb3m,
+}
+
+impl FromStr for TropoModel {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "niell" => Ok(Self::Niell),
+            "unb3m" => Ok(Self::Unb3m),
+            _ => Err(Error::InvalidTropo(s.to_string())),
+        }
+    }
+}
+
+/// UNB3m average meteorological parameters, every 15° of latitude:
+/// latitude, pressure (hPa), temperature (K), relative humidity (%),
+/// temperature lapse rate (K/m) and water vapour lapse rate
+const UNB3M_AVG: [[f64; 6]; 5] = [
+    [15.0, 1013.25, 299.65, 75.00, 6.30E-3, 2.77],
+    [30.0, 1017.25, 294.15, 80.00, 6.05E-3, 3.15],
+    [45.0, 1015.75, 283.15, 76.00, 5.58E-3, 2.57],
+    [60.0, 1011.75, 272.15, 77.50, 5.39E-3, 1.81],
+    [75.0, 1013.00, 263.65, 82.50, 4.53E-3, 1.55],
+];
+
+/// UNB3m seasonal amplitudes, same layout as [UNB3M_AVG]
+const UNB3M_AMP: [[f64; 6]; 5] = [
+    [15.0, 0.00, 0.00, 0.00, 0.00E-3, 0.00],
+    [30.0, -3.75, 7.00, 0.00, 0.25E-3, 0.33],
+    [45.0, -2.25, 11.00, -1.00, 0.32E-3, 0.46],
+    [60.0, -1.75, 15.00, -2.50, 0.81E-3, 0.74],
+    [75.0, -0.50, 14.50, 2.50, 0.62E-3, 0.30],
+];
+
+/// Dry air gas constant (J/kg/K)
+const RD: f64 = 287.054;
+/// Standard gravity (m/s²)
+const G: f64 = 9.80665;
+/// Refractivity constants (K/hPa, K/hPa, K²/hPa)
+const K1: f64 = 77.604;
+const K2_PRIME: f64 = 16.6;
+const K3: f64 = 377600.0;
+
+/// Interpolates table row at given latitude (ddeg, absolute)
+fn interpolate(table: &[[f64; 6]; 5], lat: f64) -> [f64; 5] {
+    let mut row = [0.0; 5];
+    let i = (((lat - 15.0) / 15.0).floor().max(0.0) as usize).min(3);
+    let ratio = ((lat - table[i][0]) / 15.0).clamp(0.0, 1.0);
+    for (k, value) in row.iter_mut().enumerate() {
+        *value = table[i][k + 1] + (table[i + 1][k + 1] - table[i][k + 1]) * ratio;
+    }
+    row
+}
+
+/// UNB3m zenith wet and dry (hydrostatic) delays (m), for given latitude (ddeg),
+/// altitude (m) and day of year
+pub fn unb3m(lat: f64, alt: f64, doy: f64) -> (f64, f64) {
+    // seasons are reversed in the southern hemisphere
+    let doy = if lat < 0.0 { doy + 365.25 / 2.0 } else { doy };
+    let cos_phs = ((doy - 28.0) * 2.0 * PI / 365.25).cos();
+
+    let avg = interpolate(&UNB3M_AVG, lat.abs());
+    let amp = interpolate(&UNB3M_AMP, lat.abs());
+    let [p0, t0, rh, beta, lambda] = [0, 1, 2, 3, 4].map(|k| avg[k] - amp[k] * cos_phs);
+
+    // relative humidity to water vapour pressure (hPa), at sea level
+    let es = 0.01
+        * (1.2378847E-5 * t0.powi(2) - 1.9121316E-2 * t0 + 33.93711047 - 6.3431645E3 / t0).exp();
+    let fw = 1.00062 + 3.14E-6 * p0 + 5.6E-7 * (t0 - 273.15).powi(2);
+    let e0 = rh / 100.0 * es * fw;
+
+    // altitude corrections
+    let ep = G / RD / beta;
+    let t = t0 - beta * alt;
+    let p = p0 * (1.0 - beta * alt / t0).powf(ep);
+    let lambda1 = lambda + 1.0;
+    let e = e0 * (1.0 - beta * alt / t0).powf(ep * lambda1);
+
+    // local gravity
+    let gm = 9.784 * (1.0 - 2.66E-3 * (2.0 * lat.to_radians()).cos() - 2.8E-7 * alt);
+
+    let tm = t * (1.0 - beta * RD / (gm * lambda1));
+
+    let zdd = 1.0E-6 * K1 * RD * p / gm;
+    // Askne & Nordius
+    let zwd = 1.0E-6 * (K2_PRIME + K3 / tm) * RD / (gm * lambda1 - beta * RD) * e;
+    (zwd, zdd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saastamoinen zenith hydrostatic delay (m), for pressure (hPa),
+    /// latitude (ddeg) and altitude (m)
+    fn saastamoinen(p: f64, lat: f64, alt: f64) -> f64 {
+        0.0022768 * p / (1.0 - 0.00266 * (2.0 * lat.to_radians()).cos() - 0.28E-6 * alt)
+    }
+
+    #[test]
+    fn unb3m_hydrostatic_delay() {
+        // 45°N, day 28: tabulated pressure minus amplitude (1018 hPa) at sea level
+        let (zwd, zdd) = unb3m(45.0, 0.0, 28.0);
+        assert!(
+            (zdd - saastamoinen(1018.0, 45.0, 0.0)).abs() < 1.0E-3,
+            "{}",
+            zdd
+        );
+        assert!((zdd - 2.3178).abs() < 1.0E-4, "{}", zdd);
+        assert!((0.05..0.3).contains(&zwd), "{}", zwd);
+
+        // 1000 m up: 896.8 hPa
+        let (zwd_1000, zdd) = unb3m(45.0, 1000.0, 28.0);
+        assert!(
+            (zdd - saastamoinen(896.8, 45.0, 1000.0)).abs() < 1.0E-3,
+            "{}",
+            zdd
+        );
+        assert!(zwd_1000 < zwd);
+    }
+
+    #[test]
+    fn unb3m_seasons() {
+        // wetter summer
+        let (winter, _) = unb3m(45.0, 0.0, 28.0);
+        let (summer, _) = unb3m(45.0, 0.0, 28.0 + 365.25 / 2.0);
+        assert!(summer > winter);
+
+        // reversed in the southern hemisphere
+        let (zwd_north, zdd_north) = unb3m(45.0, 100.0, 28.0);
+        let (zwd_south, zdd_south) = unb3m(-45.0, 100.0, 28.0 + 365.25 / 2.0);
+        assert!((zwd_north - zwd_south).abs() < 1.0E-9);
+        assert!((zdd_north - zdd_south).abs() < 1.0E-9);
+
+        // no seasonal variation at the equator
+        assert_eq!(unb3m(10.0, 0.0, 28.0), unb3m(10.0, 0.0, 200.0));
+    }
+}
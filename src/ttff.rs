@@ -0,0 +1,101 @@
+//! Time to first fix
+use std::time::Instant;
+
+use gnss_rtk::prelude::{Duration, Epoch};
+
+use crate::ublox::StartType;
+
+/// Measures time to first fix, from the moment processing started
+#[derive(Debug)]
+pub struct Ttff {
+    /// Receiver restart, if any
+    start: Option<StartType>,
+    /// Processing start
+    started: Instant,
+    /// First measurement [Epoch]
+    first_epoch: Option<Epoch>,
+    /// Time to first fix (s), once obtained
+    ttff: Option<f64>,
+}
+
+impl Ttff {
+    /// Starts measuring, right after given receiver restart
+    pub fn new(start: Option<StartType>) -> Self {
+        Self {
+            start,
+            started: Instant::now(),
+            first_epoch: None,
+            ttff: None,
+        }
+    }
+
+    /// New measurement [Epoch]
+    pub fn on_epoch(&mut self, t: Epoch) {
+        self.first_epoch.get_or_insert(t);
+    }
+
+    /// New fix at `t`: reports time to first fix, the first time only.
+    /// Replayed streams are better described by the measurement time span
+    /// to first fix, which is reported as well, and returned.
+    pub fn on_fix(&mut self, t: Epoch) -> Option<Duration> {
+        if self.ttff.is_some() {
+            return None;
+        }
+        let ttff = self.started.elapsed().as_secs_f64();
+        let start = match self.start {
+            Some(start) => format!("{:?} start", start).to_lowercase(),
+            None => "no restart".to_string(),
+        };
+        match self.first_epoch {
+            Some(first) => info!(
+                "{} TTFF: {:.1}s ({}), {} after first measurement",
+                t,
+                ttff,
+                start,
+                t - first
+            ),
+            None => info!("{} TTFF: {:.1}s ({})", t, ttff, start),
+        }
+        self.ttff = Some(ttff);
+        self.first_epoch.map(|first| t - first)
+    }
+
+    /// Time to first fix (s), once obtained
+    pub fn ttff(&self) -> Option<f64> {
+        self.ttff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gnss_rtk::prelude::TimeScale;
+
+    #[test]
+    fn first_fix_after_first_measurement() {
+        let t0 = Epoch::from_gregorian(2024, 3, 3, 0, 0, 1, 0, TimeScale::GPST);
+        let mut ttff = Ttff::new(Some(StartType::Cold));
+        assert!(ttff.ttff().is_none());
+
+        for dt in 0..30 {
+            ttff.on_epoch(t0 + Duration::from_seconds(dt as f64));
+        }
+        let span = ttff.on_fix(t0 + Duration::from_seconds(29.0));
+        assert_eq!(span, Some(Duration::from_seconds(29.0)));
+        let first = ttff.ttff().unwrap();
+        assert!(first >= 0.0);
+
+        // later fixes do not count
+        ttff.on_epoch(t0 + Duration::from_seconds(30.0));
+        assert!(ttff.on_fix(t0 + Duration::from_seconds(30.0)).is_none());
+        assert_eq!(ttff.ttff(), Some(first));
+    }
+
+    #[test]
+    fn fix_without_measurement() {
+        let t0 = Epoch::from_gregorian(2024, 3, 3, 0, 0, 1, 0, TimeScale::GPST);
+        let mut ttff = Ttff::new(None);
+        assert!(ttff.on_fix(t0).is_none());
+        assert!(ttff.ttff().is_some());
+    }
+}
@@ -1,18 +1,48 @@
-use crate::Error;
+#[cfg(feature = "osnma")]
+use crate::osnma::Osnma;
+use crate::{
+    anomaly::{AnomalyDetector, AnomalyOpts},
+    antenna::{AntennaPower, AntennaState},
+    bias::BiasTable,
+    carrier::{carrier_frequency_hz, cycles_to_meters, wavelength},
+    clk::PreciseClockSource,
+    combination::Combiner,
+    esf::{self, EsfIns},
+    ionex::IonexMap,
+    klobuchar::Klobuchar,
+    lock::LockTracker,
+    relpos::{self, RelPos},
+    sbas::{FastCorrections, Message as SbasMessage},
+    source::{Command, Message, ReceiverPosition, Source},
+    time::{LeapSeconds, TimeSource},
+    Error,
+};
 use chrono::prelude::*;
-use std::time::Duration as StdDuration;
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration as StdDuration, Instant},
+};
 
 use ublox::{
-    CfgMsgAllPorts, CfgMsgAllPortsBuilder, GpsFix, MgaGloEph, MgaGpsEph, MgaGpsIono, NavEoe,
-    NavHpPosEcef, NavPvt, PacketRef as UbxPacketRef, Parser as UbxParser, Position as UbxPosition,
-    RxmRawx, UbxPacketMeta, Velocity as UbxVelocity,
+    CfgAnt, CfgAntBuilder, CfgMsgAllPorts, CfgMsgAllPortsBuilder, CfgNav5, CfgNav5Builder,
+    CfgNav5DynModel, CfgNav5Params, CfgRstBuilder, CfgTp5, CfgTp5Builder, CfgTp5Flags,
+    CfgTp5TimePulseMode, CfgValSet, GpsFix, MgaGloEph, MgaGpsEph, MgaGpsIono, MonHw, MonVer,
+    NavBbrPredefinedMask, NavEoe, NavHpPosEcef, NavHpPosLlh, NavPvt, NavPvtRef,
+    PacketRef as UbxPacketRef, Parser as UbxParser, Position as UbxPosition, RecStatFlags,
+    ResetMode, RxmRawx, RxmRawxRef, RxmSfrbx, TimTp, UbxPacketMeta, UbxPacketRequest,
+    Velocity as UbxVelocity,
 };
 
-use std::io::{ErrorKind as IoErrorKind, Result as IoResult};
+use std::{
+    fs::File,
+    io::{BufWriter, Error as IoError, ErrorKind as IoErrorKind, Read, Result as IoResult, Write},
+    path::Path,
+};
 
 use serialport::{
     DataBits as SerialDataBits, FlowControl as SerialFlowControl, Parity as SerialParity,
-    SerialPort, StopBits as SerialStopBits,
+    StopBits as SerialStopBits,
 };
 
 use tokio::sync::mpsc::{Receiver, Sender};
@@ -21,96 +51,1079 @@ use gnss_rtk::prelude::{
     Candidate, Carrier, Constellation, Duration, Epoch, PhaseRange, PseudoRange, TimeScale, SV,
 };
 
-#[derive(Debug, Clone)]
-pub enum Command {
-    AbortCandidates,
+pub struct SerialOpts {
+    pub port: String,
+    pub baud: u32,
 }
 
-#[derive(Debug, Clone)]
-pub enum Message {
-    Candidates((Epoch, Vec<Candidate>)),
+/// Measurements processing options
+#[derive(Debug, Clone, Default)]
+pub struct Opts {
+    /// Receiver code biases
+    pub biases: BiasTable,
+    /// Apply SBAS fast corrections
+    pub sbas: bool,
+    /// Extended signals to enable
+    pub signals: Vec<ExtendedSignal>,
+    /// Restart receiver prior processing
+    pub start: Option<StartType>,
+    /// Time pulse frequency (Hz)
+    pub timepulse: Option<u32>,
+    /// Antenna cable delay (ns)
+    pub cable_delay_ns: Option<f64>,
+    /// Moving baseline heading (NAV-RELPOSNED)
+    pub heading: bool,
+    /// Propose carrier phases (m) to the solver, next to pseudo ranges
+    pub use_phase: bool,
+    /// Longest measurement gap, past which a reacquired signal gets a new phase ambiguity
+    pub max_gap: Duration,
+    /// Report widelane / narrowlane / multipath combinations of dual frequency SV
+    pub combinations: bool,
+    /// Vehicle dynamics (ESF-INS), for dead reckoning
+    pub esf: bool,
+    /// Galileo OSNMA status, from E1-B I/NAV pages (RXM-SFRBX)
+    pub osnma: bool,
+    /// ACK timeout, per attempt
+    pub ack_timeout: StdDuration,
+    /// Number of retries, when ACK times out
+    pub ack_retries: u8,
+    /// Read back applied configuration
+    pub verify_config: bool,
+    /// Also store signal configuration in battery backed RAM and flash
+    pub persist: bool,
+    /// Precise SV clocks, overriding broadcast clock corrections
+    pub clocks: Option<Arc<PreciseClockSource>>,
+    /// Withhold SV lacking a clock correction, rather than proposing them uncorrected
+    pub strict_clocks: bool,
+    /// Ionosphere maps, removing the slant ionosphere delay from pseudo ranges
+    pub ionex: Option<Arc<IonexMap>>,
+    /// Spoofing / jamming / multipath indicators
+    pub anomalies: Option<AnomalyOpts>,
+    /// Maximal pseudo range standard deviation (m)
+    pub max_pr_stdev: Option<f64>,
+    /// Maximal carrier phase standard deviation (cycles)
+    pub max_cp_stdev: Option<f64>,
+    /// Physically possible pseudo ranges (m): minimum and maximum
+    pub pr_window: (f64, f64),
+    /// Receiver dynamic platform model
+    pub dyn_model: Option<DynModel>,
+    /// Ports messages are enabled on
+    pub ports: MessagePorts,
+    /// Active antenna supply control
+    pub ant_power: Option<AntennaPower>,
+    /// Delay after opening the port, prior any configuration
+    pub warmup: Option<StdDuration>,
+    /// Only enable fixes (NAV-PVT): no raw measurements, for receivers that do not navigate
+    pub fix_only: bool,
 }
 
-pub struct SerialOpts {
-    pub port: String,
-    pub baud: u32,
+impl Opts {
+    /// Options of a secondary receiver, only reporting its fixes:
+    /// no restart, no raw measurements, no signal or platform configuration
+    pub fn secondary(&self) -> Self {
+        Self {
+            fix_only: true,
+            ack_timeout: self.ack_timeout,
+            ack_retries: self.ack_retries,
+            ports: self.ports,
+            warmup: self.warmup,
+            ..Default::default()
+        }
+    }
+}
+
+/// Receiver ports, in CFG-MSG order
+const PORTS: [&str; 5] = ["i2c", "uart1", "uart2", "usb", "spi"];
+
+/// Receiver ports that messages are enabled on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MessagePorts([bool; 5]);
+
+impl Default for MessagePorts {
+    /// UART1, UART2 and USB
+    fn default() -> Self {
+        Self([false, true, true, true, false])
+    }
+}
+
+impl FromStr for MessagePorts {
+    type Err = Error;
+    /// Parses comma separated port names, for example "uart1,usb"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut enabled = [false; 5];
+        for name in s.split(',') {
+            let name = name.trim().to_lowercase();
+            match PORTS.iter().position(|port| *port == name) {
+                Some(index) => enabled[index] = true,
+                None => return Err(Error::InvalidPort(name)),
+            }
+        }
+        Ok(Self(enabled))
+    }
+}
+
+impl MessagePorts {
+    /// CFG-MSG rates: once per solution on every enabled port
+    fn rates(&self) -> [u8; 6] {
+        let mut rates = [0; 6];
+        for (rate, enabled) in rates.iter_mut().zip(self.0) {
+            *rate = u8::from(enabled);
+        }
+        rates
+    }
+}
+
+/// Receiver (re)start type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StartType {
+    /// Clears all navigation data: full acquisition
+    Cold,
+    /// Clears ephemeris only
+    Warm,
+    /// Keeps all navigation data: fast restart
+    Hot,
+}
+
+impl FromStr for StartType {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "cold" => Ok(Self::Cold),
+            "warm" => Ok(Self::Warm),
+            "hot" => Ok(Self::Hot),
+            _ => Err(Error::InvalidStartType(s.to_string())),
+        }
+    }
+}
+
+impl StartType {
+    /// Battery backed RAM sections to clear
+    fn mask(&self) -> NavBbrPredefinedMask {
+        match self {
+            Self::Cold => NavBbrPredefinedMask::COLD_START,
+            Self::Warm => NavBbrPredefinedMask::WARM_START,
+            Self::Hot => NavBbrPredefinedMask::HOT_START,
+        }
+    }
+
+    /// Delay until GNSS is restarted and accepts configuration again
+    fn settling_time(&self) -> StdDuration {
+        match self {
+            Self::Cold => StdDuration::from_secs(2),
+            Self::Warm | Self::Hot => StdDuration::from_millis(500),
+        }
+    }
+}
+
+/// Receiver dynamic platform model (CFG-NAV5), which only affects the receiver's own fix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DynModel {
+    Portable,
+    Stationary,
+    Pedestrian,
+    Automotive,
+    Sea,
+    /// Airborne, < 1g acceleration
+    Airborne1g,
+    /// Airborne, < 2g acceleration
+    Airborne2g,
+    /// Airborne, < 4g acceleration
+    Airborne4g,
+    Wrist,
+    Bike,
+}
+
+impl FromStr for DynModel {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "portable" => Ok(Self::Portable),
+            "stationary" => Ok(Self::Stationary),
+            "pedestrian" => Ok(Self::Pedestrian),
+            "automotive" => Ok(Self::Automotive),
+            "sea" => Ok(Self::Sea),
+            "airborne1g" => Ok(Self::Airborne1g),
+            "airborne2g" => Ok(Self::Airborne2g),
+            "airborne4g" => Ok(Self::Airborne4g),
+            "wrist" => Ok(Self::Wrist),
+            "bike" => Ok(Self::Bike),
+            _ => Err(Error::InvalidDynModel(s.to_string())),
+        }
+    }
+}
+
+impl From<DynModel> for CfgNav5DynModel {
+    fn from(model: DynModel) -> Self {
+        match model {
+            DynModel::Portable => Self::Portable,
+            DynModel::Stationary => Self::Stationary,
+            DynModel::Pedestrian => Self::Pedestrian,
+            DynModel::Automotive => Self::Automotive,
+            DynModel::Sea => Self::Sea,
+            DynModel::Airborne1g => Self::AirborneWithLess1gAcceleration,
+            DynModel::Airborne2g => Self::AirborneWithLess2gAcceleration,
+            DynModel::Airborne4g => Self::AirborneWith4gAcceleration,
+            DynModel::Wrist => Self::WristWornWatch,
+            DynModel::Bike => Self::Bike,
+        }
+    }
+}
+
+/// Builds CFG-NAV5 frame that only applies the dynamic platform model
+fn dyn_model_frame(model: DynModel) -> [u8; 44] {
+    CfgNav5Builder {
+        mask: CfgNav5Params::DYN,
+        dyn_model: model.into(),
+        ..Default::default()
+    }
+    .into_packet_bytes()
+}
+
+/// Newer signals, that only recent receivers may track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtendedSignal {
+    /// GPS L5
+    GpsL5,
+    /// Galileo E5a
+    GalE5a,
+    /// Galileo E6
+    GalE6,
+    /// BeiDou B1C
+    BdsB1c,
+    /// BeiDou B2a
+    BdsB2a,
+}
+
+impl FromStr for ExtendedSignal {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "L5" => Ok(Self::GpsL5),
+            "E5A" => Ok(Self::GalE5a),
+            "E6" => Ok(Self::GalE6),
+            "B1C" => Ok(Self::BdsB1c),
+            "B2A" => Ok(Self::BdsB2a),
+            _ => Err(Error::InvalidSignal(s.to_string())),
+        }
+    }
+}
+
+impl ExtendedSignal {
+    /// CFG-SIGNAL-* enable key. These are not known to the ublox crate yet.
+    fn key(&self) -> u32 {
+        match self {
+            Self::GpsL5 => 0x10310004,
+            Self::GalE5a => 0x10310009,
+            Self::GalE6 => 0x1031000b,
+            Self::BdsB1c => 0x1031000f,
+            Self::BdsB2a => 0x10310028,
+        }
+    }
+
+    /// Returns the [Carrier] this signal is tracked on
+    pub fn carrier(&self) -> Carrier {
+        match self {
+            Self::GpsL5 => Carrier::L5,
+            Self::GalE5a => Carrier::E5A,
+            Self::GalE6 => Carrier::E6,
+            Self::BdsB1c => Carrier::B1aB1c,
+            Self::BdsB2a => Carrier::B2A,
+        }
+    }
+
+    /// Builds CFG-VALSET frame that enables this signal in given `layers`
+    fn valset_frame(&self, layers: u8) -> Vec<u8> {
+        // version, layers, reserved
+        let mut payload = vec![0x00, layers, 0x00, 0x00];
+        payload.extend_from_slice(&self.key().to_le_bytes());
+        payload.push(1);
+        ubx_frame(CFG_CLASS, CFG_VALSET_ID, &payload)
+    }
+
+    /// Builds CFG-VALGET frame that polls this signal state, in RAM
+    fn valget_frame(&self) -> Vec<u8> {
+        // version, layer (RAM), position
+        let mut payload = vec![0x00, 0x00, 0x00, 0x00];
+        payload.extend_from_slice(&self.key().to_le_bytes());
+        ubx_frame(CFG_CLASS, CFG_VALGET_ID, &payload)
+    }
+}
+
+/// Frames UBX `payload`
+fn ubx_frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![UBX_SYNC[0], UBX_SYNC[1], class, id];
+    frame.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    frame.extend_from_slice(payload);
+    let (ck_a, ck_b) = ubx_checksum(&frame[2..]);
+    frame.push(ck_a);
+    frame.push(ck_b);
+    frame
+}
+
+/// UBX 8 bit Fletcher checksum, of class, id, length and payload
+fn ubx_checksum(data: &[u8]) -> (u8, u8) {
+    let (mut ck_a, mut ck_b) = (0_u8, 0_u8);
+    for byte in data {
+        ck_a = ck_a.wrapping_add(*byte);
+        ck_b = ck_b.wrapping_add(ck_a);
+    }
+    (ck_a, ck_b)
+}
+
+/// Reads the 1 byte value of `key`, from CFG-VALGET response payload
+fn valget_value(payload: &[u8], key: u32) -> Option<u8> {
+    // version, layer, position, then key/value pairs
+    let mut offset = 4;
+    while offset + 5 <= payload.len() {
+        let k = u32::from_le_bytes([
+            payload[offset],
+            payload[offset + 1],
+            payload[offset + 2],
+            payload[offset + 3],
+        ]);
+        if k == key {
+            return Some(payload[offset + 4]);
+        }
+        // value size is encoded in the key
+        offset += 4 + match (k >> 28) & 0x07 {
+            0x01 | 0x02 => 1,
+            0x03 => 2,
+            0x04 => 4,
+            _ => 8,
+        };
+    }
+    None
+}
+
+/// UBX-CFG class
+const CFG_CLASS: u8 = 0x06;
+
+/// UBX-CFG-VALSET ID
+const CFG_VALSET_ID: u8 = 0x8A;
+
+/// UBX-CFG-VALGET ID, not supported by the ublox crate yet
+const CFG_VALGET_ID: u8 = 0x8B;
+
+/// CFG-VALSET layers: RAM, applied immediately
+const LAYER_RAM: u8 = 0x01;
+
+/// CFG-VALSET layers: battery backed RAM and flash, surviving restarts and power cycles
+const LAYER_PERSISTENT: u8 = 0x02 | 0x04;
+
+/// CFG-VALGET polling timeout
+const VALGET_TIMEOUT: StdDuration = StdDuration::from_secs(2);
+
+/// CFG-VALSET requires protocol 23.01 or later
+const MIN_VALSET_PROTOCOL: f64 = 23.01;
+
+/// MON-VER polling timeout
+const MON_VER_TIMEOUT: StdDuration = StdDuration::from_secs(2);
+
+/// UBX sync chars
+const UBX_SYNC: [u8; 2] = [0xB5, 0x62];
+
+/// UBX framing: sync chars, class, id, length and checksum
+const UBX_FRAMING_LEN: usize = 8;
+
+/// Largest frame we may receive: RAWX with 255 measurements
+const MAX_FRAME_LEN: usize = RxmRawx::MAX_PAYLOAD_LEN as usize + UBX_FRAMING_LEN;
+
+/// Serial port read size. Frames spanning several reads are reassembled by the parser.
+const READ_CHUNK_LEN: usize = 1240;
+
+/// Parser buffer limit: one complete frame plus one pending read.
+/// Exceeding it means we lost sync, the buffer is then discarded.
+const MAX_PARSER_BUFFER_LEN: usize = MAX_FRAME_LEN + READ_CHUNK_LEN;
+
+/// Parser errors are reported at most once per this interval
+const PARSER_REPORT_INTERVAL: StdDuration = StdDuration::from_secs(10);
+
+/// Malformed packets and resync losses, since start
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ParserStats {
+    /// Packets dropped because malformed (invalid checksum, length or payload)
+    pub malformed: u64,
+    /// Bytes discarded when sync was lost
+    pub dropped_bytes: u64,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+/// Accumulates [ParserStats], reporting changes at a limited rate
+#[derive(Debug)]
+struct ParserMonitor {
+    stats: ParserStats,
+    /// Latest reported [ParserStats], and when
+    reported: (ParserStats, Instant),
+}
+
+impl Default for ParserMonitor {
+    fn default() -> Self {
+        Self {
+            stats: ParserStats::default(),
+            reported: (ParserStats::default(), Instant::now()),
+        }
+    }
+}
+
+impl ParserMonitor {
+    /// Returns [ParserStats] when they changed since the latest report,
+    /// and the report interval elapsed
+    fn report(&mut self) -> Option<ParserStats> {
+        let (reported, t) = self.reported;
+        if self.stats == reported || t.elapsed() < PARSER_REPORT_INTERVAL {
+            return None;
+        }
+        warn!(
+            "ubx: {} malformed packets (+{}), {} bytes dropped on resync (+{})",
+            self.stats.malformed,
+            self.stats.malformed - reported.malformed,
+            self.stats.dropped_bytes,
+            self.stats.dropped_bytes - reported.dropped_bytes,
+        );
+        self.reported = (self.stats, Instant::now());
+        Some(self.stats)
+    }
+}
+
+/// Measurements of one epoch, grouped per SV: all signals of one SV are proposed
+/// within a single [Candidate], as dual frequency navigation (CPP, PPP) expects
+#[derive(Debug, Default)]
+struct Observations {
+    /// SV, clock correction, pseudo ranges and phase ranges, in order of reception
+    sv: Vec<(SV, Duration, Vec<PseudoRange>, Vec<PhaseRange>)>,
+}
+
+impl Observations {
+    /// New signal of this SV. A second signal on the same carrier is dropped.
+    fn push(&mut self, sv: SV, clock_corr: Duration, code: PseudoRange, phase: Vec<PhaseRange>) {
+        match self.sv.iter_mut().find(|(tracked, ..)| *tracked == sv) {
+            Some((_, _, codes, _)) if codes.iter().any(|pr| pr.carrier == code.carrier) => {
+                debug!("{} {:?} already measured", sv, code.carrier);
+            },
+            Some((_, _, codes, phases)) => {
+                codes.push(code);
+                phases.extend(phase);
+            },
+            None => self.sv.push((sv, clock_corr, vec![code], phase)),
+        }
+    }
+
+    /// Number of SV
+    fn len(&self) -> usize {
+        self.sv.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.sv.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.sv.clear();
+    }
+
+    /// Returns one [Candidate] per SV, measured at `t`, and clears the measurements
+    fn candidates(&mut self, t: Epoch) -> Vec<Candidate> {
+        self.sv
+            .drain(..)
+            .map(|(sv, clock_corr, code, phase)| {
+                Candidate::new(sv, t, clock_corr, None, code, phase)
+            })
+            .collect()
+    }
+}
+
+/// Week first: ordered in time, across week rollovers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 struct Tow {
-    pub tow: u32,
     pub week: u32,
+    pub tow: u32,
 }
 
 impl Tow {
+    /// RAWX measurement time
+    fn from_rawx(rawx: &RxmRawxRef) -> Self {
+        Self {
+            week: rawx.week() as u32,
+            tow: (rawx.rcv_tow() * 1.0E3).round() as u32,
+        }
+    }
+
     fn epoch(&self, ts: TimeScale) -> Epoch {
         Epoch::from_time_of_week(self.week, self.tow as u64 * 1_000_000, ts)
     }
 }
 
+/// NAV-PVT time, when the fix resolves time and its date is valid
+fn pvt_time(sol: &NavPvtRef) -> Option<DateTime<Utc>> {
+    let has_time = sol.fix_type() == GpsFix::Fix3D
+        || sol.fix_type() == GpsFix::GPSPlusDeadReckoning
+        || sol.fix_type() == GpsFix::TimeOnlyFix;
+    if !has_time {
+        return None;
+    }
+    match sol.try_into() {
+        Ok(time) => Some(time),
+        Err(e) => {
+            debug!("invalid NAV-PVT time: {:?}", e);
+            None
+        },
+    }
+}
+
+/// Receiver time: NAV-PVT time when valid, otherwise the latest RAWX
+/// measurement time (GPST). None until the first RAWX.
+fn receiver_time(pvt_time: Option<DateTime<Utc>>, tow: Tow) -> Option<(Epoch, TimeSource)> {
+    match pvt_time {
+        Some(time) => Some((
+            Epoch::from_unix_milliseconds(time.timestamp_millis() as f64),
+            TimeSource::NavPvt,
+        )),
+        None if tow != Tow::default() => Some((tow.epoch(TimeScale::GPST), TimeSource::Rawx)),
+        None => None,
+    }
+}
+
+/// Records received UBX frames to a file that u-center (and --replay) can read.
+/// Only complete frames with a valid checksum are written, so the file starts
+/// and ends on frame boundaries. NMEA sentences and garbage are left out.
+pub struct Recorder {
+    fd: BufWriter<File>,
+    /// Bytes of the frame being received
+    buf: Vec<u8>,
+}
+
+impl Recorder {
+    pub fn new(path: &Path) -> IoResult<Self> {
+        info!("recording UBX stream to {}", path.display());
+        Ok(Self {
+            fd: BufWriter::new(File::create(path)?),
+            buf: Vec::with_capacity(MAX_FRAME_LEN),
+        })
+    }
+
+    /// Appends received bytes, writing out every frame they complete
+    fn consume(&mut self, data: &[u8]) -> IoResult<()> {
+        self.buf.extend_from_slice(data);
+        loop {
+            match self.buf.windows(2).position(|sync| sync == UBX_SYNC) {
+                Some(start) => {
+                    self.buf.drain(..start);
+                },
+                None => {
+                    // preserve a sync char that may complete on next read
+                    let keep = self.buf.last() == Some(&UBX_SYNC[0]);
+                    self.buf.drain(..self.buf.len() - keep as usize);
+                    return Ok(());
+                },
+            }
+            if self.buf.len() < 6 {
+                return Ok(());
+            }
+            let len = u16::from_le_bytes([self.buf[4], self.buf[5]]) as usize + UBX_FRAMING_LEN;
+            if len > MAX_FRAME_LEN {
+                // not an actual frame: resync past these sync chars
+                self.buf.drain(..2);
+                continue;
+            }
+            if self.buf.len() < len {
+                return Ok(());
+            }
+            if ubx_checksum(&self.buf[2..len - 2]) == (self.buf[len - 2], self.buf[len - 1]) {
+                self.fd.write_all(&self.buf[..len])?;
+                self.buf.drain(..len);
+            } else {
+                self.buf.drain(..2);
+            }
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.fd.flush() {
+            error!("failed to flush UBX record: {}", e);
+        }
+    }
+}
+
+/// Captured UBX stream, replayed instead of a serial port
+struct Replay(File);
+
+impl Read for Replay {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        match self.0.read(buf)? {
+            0 => Err(IoError::new(IoErrorKind::UnexpectedEof, "end of replay")),
+            size => Ok(size),
+        }
+    }
+}
+
+impl Write for Replay {
+    fn write(&mut self, _: &[u8]) -> IoResult<usize> {
+        Err(IoError::new(
+            IoErrorKind::Unsupported,
+            "can't write to replayed stream",
+        ))
+    }
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+}
+
+/// Byte stream we exchange UBX frames with
+trait Port: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Port for T {}
+
 pub struct Ublox {
     rx: Receiver<Command>,
     tx: Sender<Message>,
-    port: Box<dyn SerialPort>,
+    port: Box<dyn Port>,
     parser: UbxParser<Vec<u8>>,
+    opts: Opts,
+    recorder: Option<Recorder>,
+    parser_monitor: ParserMonitor,
 }
 
 fn gnss_rtk_id(gnss_id: u8) -> Result<Constellation, Error> {
     match gnss_id {
         0 => Ok(Constellation::GPS),
-        1 => Ok(Constellation::Galileo),
+        2 => Ok(Constellation::Galileo),
+        5 => Ok(Constellation::QZSS),
         id => Err(Error::NonSupportedGnss(id)),
     }
 }
 
-fn freq_rtk_id(freq_id: u8) -> Result<Carrier, Error> {
-    match freq_id {
-        0 => Ok(Carrier::default()),
-        id => Err(Error::NonSupportedSignal(id)),
+/// QZSS PRN offset: PRN 193-202 are identified as J01-J10
+const QZSS_PRN_OFFSET: u8 = 192;
+
+/// Builds [SV] from UBX gnssId and svId. QZSS is identified 1-10 in UBX messages,
+/// but 193-202 (PRN) in other conventions: both are mapped to J01-J10,
+/// so RAWX and SFRBX refer to the same [SV].
+fn sv_rtk_id(gnss_id: u8, sv_id: u8) -> Result<SV, Error> {
+    let constellation = gnss_rtk_id(gnss_id)?;
+    match constellation {
+        Constellation::QZSS if sv_id > QZSS_PRN_OFFSET => {
+            Ok(SV::new(constellation, sv_id - QZSS_PRN_OFFSET))
+        },
+        _ => Ok(SV::new(constellation, sv_id)),
     }
 }
 
+/// RAWX pseudo range standard deviation (m), from its 4 bit index
+fn pr_stdev(index: u8) -> f64 {
+    0.01 * 2.0_f64.powi((index & 0x0f) as i32)
+}
+
+/// RAWX carrier phase standard deviation (cycles), from its 4 bit index
+fn cp_stdev(index: u8) -> f64 {
+    0.004 * (index & 0x0f) as f64
+}
+
+/// RAWX trkStat: carrier phase valid
+const TRK_STAT_CP_VALID: u8 = 0x02;
+
+/// RAWX trkStat: half cycle ambiguity resolved.
+/// Once resolved, the receiver already subtracted the half cycle (subHalfCyc).
+const TRK_STAT_HALF_CYC: u8 = 0x04;
+
+/// Carrier phases are withheld until continuously tracked for this long (s):
+/// freshly acquired signals are the most likely to slip
+const MIN_LOCK_TIME_S: f64 = 1.0;
+
+/// Valid carrier phase (cycles), as proposed to the solver (m),
+/// once the signal has been tracked for [MIN_LOCK_TIME_S]
+fn phase_range(carrier: Carrier, cp_mes: f64, tracked: Duration) -> Vec<PhaseRange> {
+    if tracked.to_seconds() < MIN_LOCK_TIME_S {
+        return vec![];
+    }
+    vec![PhaseRange {
+        carrier,
+        value: cycles_to_meters(cp_mes, carrier),
+        snr: None,       //TODO
+        ambiguity: None, //TODO ?
+    }]
+}
+
+/// Builds [Carrier] from UBX gnssId and sigId. Measurements used to be labelled
+/// from freqId (the Glonass frequency slot), so L2 and L5 signals were processed as L1.
+fn signal_rtk_id(gnss_id: u8, sig_id: u8) -> Result<Carrier, Error> {
+    match (gnss_id, sig_id) {
+        // GPS L1C/A, QZSS L1C/A. QZSS L1S shares the L1 carrier with L1C/A:
+        // it is not supported, and would be mistaken for it.
+        (0, 0) | (5, 0) => Ok(Carrier::L1),
+        // GPS L2CL and L2CM, QZSS L2CM and L2CL
+        (0, 3) | (0, 4) | (5, 4) | (5, 5) => Ok(Carrier::L2),
+        // GPS L5I and L5Q, QZSS L5I and L5Q
+        (0, 6) | (0, 7) | (5, 8) | (5, 9) => Ok(Carrier::L5),
+        // Galileo E1C and E1B
+        (2, 0) | (2, 1) => Ok(Carrier::E1),
+        // Galileo E5aI and E5aQ
+        (2, 3) | (2, 4) => Ok(Carrier::E5A),
+        // Galileo E5bI and E5bQ
+        (2, 5) | (2, 6) => Ok(Carrier::E5B),
+        // Galileo E6B and E6C
+        (2, 8) | (2, 9) => Ok(Carrier::E6),
+        (_, id) => Err(Error::NonSupportedSignal(id)),
+    }
+}
+
+/// Builds CFG-TP5 frame: TIMEPULSE at `freq` Hz, 50% duty cycle,
+/// rising edge aligned to top of second once GNSS time is valid.
+/// The receiver compensates the antenna `cable_delay_ns`.
+fn timepulse_frame(freq: u32, cable_delay_ns: f64) -> [u8; 40] {
+    let pulse_len_us = 500_000.0 / freq as f64;
+    CfgTp5Builder {
+        tp_idx: CfgTp5TimePulseMode::TimePulse,
+        version: 0x01,
+        ant_cable_delay: cable_delay_ns.round() as f32,
+        freq_period: freq as f64,
+        freq_period_lock: freq as f64,
+        pulse_len_ratio: pulse_len_us,
+        pulse_len_ratio_lock: pulse_len_us,
+        flags: CfgTp5Flags::ACTIVE
+            | CfgTp5Flags::LOCK_GNSS_FREQ
+            | CfgTp5Flags::LOCKED_OTHER_SET
+            | CfgTp5Flags::IS_FREQ
+            | CfgTp5Flags::IS_LENGTH
+            | CfgTp5Flags::ALIGN_TO_TOW
+            | CfgTp5Flags::POLARITY,
+        ..Default::default()
+    }
+    .into_packet_bytes()
+}
+
 impl Ublox {
     /// Builds new Ublox device
-    pub fn new(opts: SerialOpts, rx: Receiver<Command>, tx: Sender<Message>) -> Self {
-        let port = opts.port.clone();
-        let port = serialport::new(opts.port, opts.baud)
+    pub fn new(
+        serial: SerialOpts,
+        opts: Opts,
+        rx: Receiver<Command>,
+        tx: Sender<Message>,
+    ) -> Result<Self, Error> {
+        let name = serial.port.clone();
+        let port = serialport::new(serial.port, serial.baud)
             .stop_bits(SerialStopBits::One)
             .data_bits(SerialDataBits::Eight)
             .timeout(StdDuration::from_millis(10))
             .parity(SerialParity::Even)
             .flow_control(SerialFlowControl::None)
             .open()
-            .unwrap_or_else(|e| {
-                panic!("failed to open port {}: {}", port, e);
-            });
-        Self {
+            .map_err(|e| Error::Port(name, e))?;
+        Ok(Self {
             rx,
             tx,
-            port,
+            port: Box::new(port),
+            opts,
             parser: Default::default(),
-        }
+            recorder: None,
+            parser_monitor: ParserMonitor::default(),
+        })
+    }
+
+    /// Builds Ublox device that replays a captured UBX stream (offline).
+    /// Replayed streams are not initialized, they should contain
+    /// all the required messages.
+    pub fn replay(
+        path: &Path,
+        opts: Opts,
+        rx: Receiver<Command>,
+        tx: Sender<Message>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            rx,
+            tx,
+            port: Box::new(Replay(File::open(path)?)),
+            opts,
+            parser: Default::default(),
+            recorder: None,
+            parser_monitor: ParserMonitor::default(),
+        })
+    }
+
+    /// Records the received UBX stream
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
     }
 
     /// Initialize hardware device
-    pub fn init(&mut self) {
+    pub fn init(&mut self) -> Result<(), Error> {
+        if let Some(warmup) = self.opts.warmup {
+            self.warmup(warmup)?;
+        }
+
+        if let Some(start) = self.opts.start {
+            self.restart(start);
+        }
+
+        if let Some(model) = self.opts.dyn_model {
+            self.write_acked(CfgNav5, &dyn_model_frame(model))
+                .map_err(|e| Error::Configuration("CfgNav5", e))?;
+            info!("dynamic platform model: {:?}", model);
+        }
+
         self.write_acked(
             CfgMsgAllPorts,
-            &CfgMsgAllPortsBuilder::set_rate_for::<NavPvt>([0, 1, 1, 1, 0, 0]).into_packet_bytes(),
+            &CfgMsgAllPortsBuilder::set_rate_for::<NavPvt>(self.opts.ports.rates())
+                .into_packet_bytes(),
         )
-        .unwrap_or_else(|e| panic!("failed to activate NavPvt msg: {}", e));
+        .map_err(|e| Error::Configuration("NavPvt", e))?;
+
+        if self.opts.fix_only {
+            return Ok(());
+        }
+
+        // high precision receivers only
+        if let Err(e) = self.write_acked(
+            CfgMsgAllPorts,
+            &CfgMsgAllPortsBuilder::set_rate_for::<NavHpPosLlh>(self.opts.ports.rates())
+                .into_packet_bytes(),
+        ) {
+            debug!("NavHpPosLlh not supported: {}", e);
+        }
 
         self.write_acked(
             CfgMsgAllPorts,
-            &CfgMsgAllPortsBuilder::set_rate_for::<NavEoe>([0, 1, 1, 1, 0, 0]).into_packet_bytes(),
+            &CfgMsgAllPortsBuilder::set_rate_for::<NavEoe>(self.opts.ports.rates())
+                .into_packet_bytes(),
         )
-        .unwrap_or_else(|e| panic!("failed to activate NavEoe msg: {}", e));
+        .map_err(|e| Error::Configuration("NavEoe", e))?;
 
         self.write_acked(
             CfgMsgAllPorts,
-            &CfgMsgAllPortsBuilder::set_rate_for::<RxmRawx>([0, 1, 1, 1, 0, 0]).into_packet_bytes(),
+            &CfgMsgAllPortsBuilder::set_rate_for::<RxmRawx>(self.opts.ports.rates())
+                .into_packet_bytes(),
         )
-        .unwrap_or_else(|e| panic!("failed to activate RxmRawx msg: {}", e));
+        .map_err(|e| Error::Configuration("RxmRawx", e))?;
+
+        if self.opts.sbas || self.opts.osnma || self.opts.ionex.is_some() {
+            self.write_acked(
+                CfgMsgAllPorts,
+                &CfgMsgAllPortsBuilder::set_rate_for::<RxmSfrbx>(self.opts.ports.rates())
+                    .into_packet_bytes(),
+            )
+            .map_err(|e| Error::Configuration("RxmSfrbx", e))?;
+        }
+
+        if !self.opts.signals.is_empty() {
+            self.enable_signals();
+        }
+
+        if self.opts.heading {
+            self.write_acked(
+                CfgMsgAllPorts,
+                &CfgMsgAllPortsBuilder {
+                    msg_class: relpos::CLASS,
+                    msg_id: relpos::ID,
+                    rates: self.opts.ports.rates(),
+                }
+                .into_packet_bytes(),
+            )
+            .map_err(|e| Error::Configuration("NavRelPosNed", e))?;
+        }
+
+        if self.opts.esf {
+            self.write_acked(
+                CfgMsgAllPorts,
+                &CfgMsgAllPortsBuilder {
+                    msg_class: esf::CLASS,
+                    msg_id: esf::ID,
+                    rates: self.opts.ports.rates(),
+                }
+                .into_packet_bytes(),
+            )
+            .map_err(|e| Error::Configuration("EsfIns", e))?;
+        }
+
+        if let Some(power) = self.opts.ant_power {
+            self.write_acked(
+                CfgAnt,
+                &CfgAntBuilder {
+                    flags: power.flags(),
+                    pins: 0,
+                }
+                .into_packet_bytes(),
+            )
+            .map_err(|e| Error::Configuration("CfgAnt", e))?;
+            info!("antenna power: {:?}", power);
+        }
+
+        // MON-HW: jamming and antenna status
+        if self.opts.anomalies.is_some() || self.opts.ant_power.is_some() {
+            self.write_acked(
+                CfgMsgAllPorts,
+                &CfgMsgAllPortsBuilder::set_rate_for::<MonHw>(self.opts.ports.rates())
+                    .into_packet_bytes(),
+            )
+            .map_err(|e| Error::Configuration("MonHw", e))?;
+        }
+
+        if let Some(freq) = self.opts.timepulse {
+            let cable_delay_ns = self.opts.cable_delay_ns.unwrap_or_default();
+            self.write_acked(CfgTp5, &timepulse_frame(freq, cable_delay_ns))
+                .map_err(|e| Error::Configuration("CfgTp5", e))?;
+
+            self.write_acked(
+                CfgMsgAllPorts,
+                &CfgMsgAllPortsBuilder::set_rate_for::<TimTp>(self.opts.ports.rates())
+                    .into_packet_bytes(),
+            )
+            .map_err(|e| Error::Configuration("TimTp", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Lets the receiver boot for `delay`, then drains whatever it sent meanwhile,
+    /// so the first configuration frames are not lost, nor their ACK mistaken
+    fn warmup(&mut self, delay: StdDuration) -> Result<(), Error> {
+        info!("receiver warmup: {}ms", delay.as_millis());
+        std::thread::sleep(delay);
+        self.update(|packet| trace!("boot: {:?}", packet))?;
+        self.parser = UbxParser::default();
+        Ok(())
+    }
+
+    /// Restarts GNSS (controlled software reset, GNSS only). CFG-RST is not acknowledged:
+    /// we wait for the receiver to settle and discard whatever was buffered.
+    fn restart(&mut self, start: StartType) {
+        let frame = CfgRstBuilder {
+            nav_bbr_mask: start.mask().into(),
+            reset_mode: ResetMode::ControlledSoftwareResetGpsOnly,
+            reserved1: 0,
+        }
+        .into_packet_bytes();
+
+        if let Err(e) = self.write_all(&frame) {
+            error!("failed to restart receiver: {}", e);
+            return;
+        }
+
+        info!("{:?} start..", start);
+        std::thread::sleep(start.settling_time());
+        self.parser = UbxParser::default();
+    }
+
+    /// Polls MON-VER and returns the protocol version, if reported
+    fn protocol_version(&mut self) -> Option<f64> {
+        let request = UbxPacketRequest::request_for::<MonVer>().into_packet_bytes();
+        if let Err(e) = self.write_all(&request) {
+            error!("failed to poll MON-VER: {}", e);
+            return None;
+        }
+        let mut version = None;
+        let mut found_packet = false;
+        let t0 = Instant::now();
+        while !found_packet && t0.elapsed() < MON_VER_TIMEOUT {
+            let ret = self.update(|packet| {
+                if let UbxPacketRef::MonVer(packet) = packet {
+                    found_packet = true;
+                    version = packet
+                        .extension()
+                        .filter_map(|ext| ext.strip_prefix("PROTVER"))
+                        .filter_map(|ver| ver.trim_start_matches(['=', ' ']).parse::<f64>().ok())
+                        .next();
+                }
+            });
+            if ret.is_err() {
+                break;
+            }
+        }
+        version
+    }
+
+    /// Enables extended signals, skipping the ones the receiver does not support
+    fn enable_signals(&mut self) {
+        match self.protocol_version() {
+            Some(version) if version < MIN_VALSET_PROTOCOL => {
+                warn!(
+                    "protocol {:.2} does not support signal configuration: extended signals skipped",
+                    version
+                );
+                return;
+            },
+            Some(version) => debug!("protocol version {:.2}", version),
+            None => warn!("unknown protocol version"),
+        }
+        let layers = if self.opts.persist {
+            LAYER_RAM | LAYER_PERSISTENT
+        } else {
+            LAYER_RAM
+        };
+        for signal in self.opts.signals.clone() {
+            let frame = signal.valset_frame(layers);
+            match self.write_acked(CfgValSet, &frame) {
+                Ok(_) => info!("{:?} ({:?}) enabled", signal, signal.carrier()),
+                Err(e) => warn!("{:?} not supported by receiver, skipped: {}", signal, e),
+            }
+        }
+        if self.opts.verify_config {
+            self.verify_signals();
+        }
+    }
+
+    /// Reads back signals state, reporting the ones the receiver did not apply.
+    /// Returns the signals not applied, or that could not be verified.
+    fn verify_signals(&mut self) -> Vec<ExtendedSignal> {
+        let mut applied = 0;
+        let mut mismatches = Vec::new();
+        let signals = self.opts.signals.clone();
+        for signal in signals.iter() {
+            match self.valget(signal) {
+                Ok(Some(1)) => {
+                    applied += 1;
+                    continue;
+                },
+                Ok(Some(value)) => {
+                    warn!("{:?} not applied: requested 1, read back {}", signal, value)
+                },
+                Ok(None) => warn!("{:?} not applied: key missing from read back", signal),
+                Err(e) => warn!("{:?} could not be verified: {}", signal, e),
+            }
+            mismatches.push(*signal);
+        }
+        if applied == signals.len() {
+            info!("configuration verified: {} signal(s) applied", applied);
+        } else {
+            warn!(
+                "configuration mismatch: {}/{} signal(s) applied",
+                applied,
+                signals.len()
+            );
+        }
+        mismatches
+    }
+
+    /// Polls [ExtendedSignal] state with CFG-VALGET
+    fn valget(&mut self, signal: &ExtendedSignal) -> IoResult<Option<u8>> {
+        let key = signal.key();
+        self.write_all(&signal.valget_frame())?;
+        let mut value = None;
+        let mut found_packet = false;
+        let t0 = Instant::now();
+        while !found_packet {
+            if t0.elapsed() > VALGET_TIMEOUT {
+                return Err(IoError::new(
+                    IoErrorKind::TimedOut,
+                    "no CFG-VALGET response",
+                ));
+            }
+            self.update(|packet| match packet {
+                UbxPacketRef::Unknown(packet)
+                    if packet.class == CFG_CLASS && packet.msg_id == CFG_VALGET_ID =>
+                {
+                    found_packet = true;
+                    value = valget_value(packet.payload, key);
+                },
+                UbxPacketRef::AckNak(nak)
+                    if nak.class() == CFG_CLASS && nak.msg_id() == CFG_VALGET_ID =>
+                {
+                    found_packet = true;
+                },
+                _ => {},
+            })?;
+        }
+        Ok(value)
     }
 
     /// Writes all bytes to device
@@ -118,24 +1131,55 @@ impl Ublox {
         self.port.write_all(data)
     }
 
-    /// Writes message and waits for ack
+    /// Writes message and waits for ack. The message is written again
+    /// each time the ACK times out, up to the configured number of retries.
     pub fn write_acked<M: UbxPacketMeta>(&mut self, msg: M, data: &[u8]) -> IoResult<()> {
-        self.port.write_all(data)?;
-        self.wait_for_ack::<M>()
+        let mut attempt = 0;
+        loop {
+            self.port.write_all(data)?;
+            match self.wait_for_ack::<M>() {
+                Err(e) if e.kind() == IoErrorKind::TimedOut && attempt < self.opts.ack_retries => {
+                    attempt += 1;
+                    warn!(
+                        "{:02x}:{:02x} ack timeout: retry {}/{}",
+                        M::CLASS,
+                        M::ID,
+                        attempt,
+                        self.opts.ack_retries
+                    );
+                },
+                ret => return ret,
+            }
+        }
     }
 
-    /// Wait for ACK from device
+    /// Wait for ACK from device. Returns an error on NAK or timeout.
     pub fn wait_for_ack<T: UbxPacketMeta>(&mut self) -> std::io::Result<()> {
         let mut found_packet = false;
+        let mut rejected = false;
+        let t0 = Instant::now();
         while !found_packet {
-            self.update(|packet| {
-                if let UbxPacketRef::AckAck(ack) = packet {
+            if t0.elapsed() > self.opts.ack_timeout {
+                return Err(IoError::new(IoErrorKind::TimedOut, "ack timeout"));
+            }
+            self.update(|packet| match packet {
+                UbxPacketRef::AckAck(ack) => {
                     if ack.class() == T::CLASS && ack.msg_id() == T::ID {
                         found_packet = true;
                     }
-                }
+                },
+                UbxPacketRef::AckNak(nak) => {
+                    if nak.class() == T::CLASS && nak.msg_id() == T::ID {
+                        found_packet = true;
+                        rejected = true;
+                    }
+                },
+                _ => {},
             })?;
         }
+        if rejected {
+            return Err(IoError::new(IoErrorKind::Other, "rejected by receiver"));
+        }
         Ok(())
     }
 
@@ -155,13 +1199,19 @@ impl Ublox {
 
     pub fn update<T: FnMut(UbxPacketRef)>(&mut self, mut cb: T) -> IoResult<()> {
         loop {
-            const MAX_PAYLOAD_LEN: usize = 1240;
-            let mut local_buf = [0; MAX_PAYLOAD_LEN];
+            let mut local_buf = [0; READ_CHUNK_LEN];
             let nbytes = self.read_port(&mut local_buf)?;
             if nbytes == 0 {
                 break;
             }
 
+            if let Some(recorder) = self.recorder.as_mut() {
+                if let Err(e) = recorder.consume(&local_buf[..nbytes]) {
+                    error!("failed to record UBX stream: {}", e);
+                    self.recorder = None;
+                }
+            }
+
             // parser.consume adds the buffer to its internal buffer, and
             // returns an iterator-like object we can use to process the packets
             let mut it = self.parser.consume(&local_buf[..nbytes]);
@@ -170,8 +1220,9 @@ impl Ublox {
                     Some(Ok(packet)) => {
                         cb(packet);
                     },
-                    Some(Err(_)) => {
-                        // Received a malformed packet, ignore it
+                    Some(Err(e)) => {
+                        trace!("ubx: malformed packet: {:?}", e);
+                        self.parser_monitor.stats.malformed += 1;
                     },
                     None => {
                         // We've eaten all the packets we have
@@ -179,24 +1230,61 @@ impl Ublox {
                     },
                 }
             }
+
+            if self.parser.buffer_len() > MAX_PARSER_BUFFER_LEN {
+                debug!(
+                    "ubx: lost sync, dropping {} buffered bytes",
+                    self.parser.buffer_len()
+                );
+                self.parser_monitor.stats.dropped_bytes += self.parser.buffer_len() as u64;
+                self.parser = UbxParser::default();
+            }
+        }
+        if let Some(stats) = self.parser_monitor.report() {
+            if let Err(e) = self.tx.try_send(Message::ParserStats(stats)) {
+                debug!("failed to report parser statistics: {}", e);
+            }
         }
         Ok(())
     }
 
     /// Main tasklet
-    pub fn tasklet(&mut self) {
+    fn tasklet(&mut self) {
         let mut sv = SV::default();
         let mut tow = Tow::default();
         let mut carrier = Carrier::default();
         let mut gnss = Constellation::default();
-        let mut candidates = Vec::<Candidate>::with_capacity(16);
+        let mut observations = Observations::default();
+        let mut range_rates = Vec::<(SV, f64)>::with_capacity(16);
+        let mut cnos = Vec::<(SV, f64)>::with_capacity(16);
+        let mut combiner = Combiner::default();
+        let mut locks = LockTracker::new(self.opts.max_gap);
+        #[cfg(feature = "osnma")]
+        let mut osnma = Osnma::default();
+        let opts = self.opts.clone();
+        let mut fast_corrections = FastCorrections::default();
+        let mut leap_seconds = LeapSeconds::default();
+        let mut time_source = None;
+        let mut anomalies = opts.anomalies.map(AnomalyDetector::new);
+        let mut antenna = None;
+        let mut geometry = None;
+        let mut klobuchar: Option<Klobuchar> = None;
+        let tx = self.tx.clone();
         loop {
             while let Ok(cmd) = self.rx.try_recv() {
                 match cmd {
                     Command::AbortCandidates => {
-                        info!("cancelled {} candidates", candidates.len());
-                        candidates.clear();
+                        info!("cancelled {} candidates", observations.len());
+                        observations.clear();
+                        range_rates.clear();
+                        cnos.clear();
+                    },
+                    Command::Corrections(frame) => {
+                        if let Err(e) = self.write_all(&frame) {
+                            error!("failed to forward corrections: {}", e);
+                        }
                     },
+                    Command::Geometry(latest) => geometry = Some(latest),
                 }
             }
             match self.update(|packet| match packet {
@@ -208,53 +1296,306 @@ impl Ublox {
                         packet.extension().collect::<Vec<&str>>()
                     );
                 },
-                UbxPacketRef::NavEoe(_) => {},
+                UbxPacketRef::NavHpPosLlh(hp) => {
+                    // ublox scales the height high precision component
+                    // and the accuracies to mm
+                    let position = ReceiverPosition {
+                        lat: hp.lat_degrees() + hp.lat_hp_degrees(),
+                        lon: hp.lon_degrees() + hp.lon_hp_degrees(),
+                        alt: hp.height_meters() + hp.height_hp_meters() * 1.0E-3,
+                        h_acc: hp.horizontal_accuracy() * 1.0E-3,
+                        v_acc: hp.vertical_accuracy() * 1.0E-3,
+                    };
+                    if let Err(e) = tx.blocking_send(Message::HpPosition(position)) {
+                        error!("failed to report hp position: {}", e);
+                    }
+                },
+                UbxPacketRef::Unknown(packet)
+                    if packet.class == relpos::CLASS && packet.msg_id == relpos::ID =>
+                {
+                    match RelPos::decode(packet.payload) {
+                        Some(relpos) if relpos.valid && relpos.heading_valid => {
+                            info!("relpos {}", relpos);
+                        },
+                        Some(relpos) if relpos.valid => {
+                            info!("relpos {} (heading invalid)", relpos);
+                        },
+                        Some(_) => debug!("relpos invalid"),
+                        None => debug!("non supported relpos version"),
+                    }
+                },
+                UbxPacketRef::Unknown(packet)
+                    if packet.class == esf::CLASS && packet.msg_id == esf::ID =>
+                {
+                    match EsfIns::decode(packet.payload) {
+                        Some(ins) => {
+                            if let Err(e) = tx.blocking_send(Message::Ins(ins)) {
+                                error!("failed to report vehicle dynamics: {}", e);
+                            }
+                        },
+                        None => debug!("esf-ins invalid"),
+                    }
+                },
+                UbxPacketRef::TimTp(tp) => {
+                    if tp.flags().q_err_valid() {
+                        info!(
+                            "timepulse week={} tow={}ms qErr={}ps",
+                            tp.week(),
+                            tp.tow_ms(),
+                            tp.q_err()
+                        );
+                    } else {
+                        debug!("timepulse week={} tow={}ms", tp.week(), tp.tow_ms());
+                    }
+                },
+                UbxPacketRef::MonHw(hw) => {
+                    if let Some(anomalies) = anomalies.as_mut() {
+                        // jamming state: flags bits 2-3
+                        anomalies.on_jamming((hw.flags() >> 2) & 0x03, hw.jam_ind());
+                    }
+                    let state = AntennaState::new(hw.a_status(), hw.a_power());
+                    if antenna != Some(state) {
+                        antenna = Some(state);
+                        if let Err(e) = tx.blocking_send(Message::Antenna(state)) {
+                            error!("failed to report antenna state: {}", e);
+                        }
+                    }
+                },
+                UbxPacketRef::NavEoe(_) => {
+                    // end of epoch: all measurements have been collected
+                    if let Some(anomalies) = anomalies.as_mut() {
+                        anomalies.on_epoch(tow.epoch(TimeScale::GPST));
+                    }
+                    let cno = cnos.drain(..).collect::<Vec<_>>();
+                    if observations.is_empty() {
+                        return;
+                    }
+                    let t = tow.epoch(TimeScale::GPST);
+                    let rates = range_rates.drain(..).collect::<Vec<_>>();
+                    if let Err(e) = tx.blocking_send(Message::RangeRates((t, rates))) {
+                        error!("failed to report range rates: {}", e);
+                    }
+                    if let Err(e) = tx.blocking_send(Message::Cno((t, cno))) {
+                        error!("failed to report c/n0: {}", e);
+                    }
+                    let proposal = observations.candidates(t);
+                    debug!("{} proposing {} candidates", t, proposal.len());
+                    if let Err(e) = tx.blocking_send(Message::Candidates((t, proposal))) {
+                        error!("failed to propose candidates: {}", e);
+                    }
+                },
+                // repeated or late measurements would be proposed twice
+                UbxPacketRef::RxmRawx(rawx) if Tow::from_rawx(&rawx) <= tow => {
+                    let stale = Tow::from_rawx(&rawx);
+                    debug!(
+                        "skipped stale RAWX: week {} tow {}ms (latest: week {} tow {}ms)",
+                        stale.week, stale.tow, tow.week, tow.tow
+                    );
+                },
                 UbxPacketRef::RxmRawx(rawx) => {
                     debug!("{} new measurements", rawx.num_meas());
+                    tow = Tow::from_rawx(&rawx);
+                    if rawx.rec_stat().contains(RecStatFlags::LEAP_SEC) {
+                        leap_seconds.update(tow.epoch(TimeScale::GPST), rawx.leap_s() as i32);
+                    }
                     for meas in rawx.measurements() {
                         let cno = meas.cno();
-                        let freq_id = meas.freq_id();
+                        // sigId is named reserved2 by the ublox crate
+                        let sig_id = meas.reserved2();
                         let gnss_id = meas.gnss_id();
 
-                        if let Ok(c) = freq_rtk_id(freq_id) {
-                            carrier = c;
+                        if let Ok(s) = sv_rtk_id(gnss_id, meas.sv_id()) {
+                            sv = s;
+                            gnss = sv.constellation;
                         } else {
-                            error!("non supported signal: {}", freq_id);
+                            error!("non supported gnss: {}", gnss_id);
+                            continue;
                         }
 
-                        if let Ok(g) = gnss_rtk_id(gnss_id) {
-                            gnss = g;
+                        if let Ok(c) = signal_rtk_id(gnss_id, sig_id) {
+                            carrier = c;
                         } else {
-                            error!("non supported gnss: {}", gnss_id);
+                            error!("{} non supported signal: {}", sv, sig_id);
+                            continue;
+                        }
+
+                        if let Some(anomalies) = anomalies.as_mut() {
+                            anomalies.on_cno(sv, cno as f64);
+                        }
+                        // one value per SV: its first signal
+                        if !cnos.iter().any(|(tracked, _)| *tracked == sv) {
+                            cnos.push((sv, cno as f64));
+                        }
+
+                        let pr_std = pr_stdev(meas.pr_stdev().bits());
+                        if let Some(max) = opts.max_pr_stdev {
+                            if pr_std > max {
+                                debug!("{} rejected: pseudo range stdev {}m", sv, pr_std);
+                                continue;
+                            }
                         }
 
                         let cp_mes = meas.cp_mes();
                         let do_mes = meas.do_mes();
-                        let pr_mes = meas.pr_mes();
+                        let mut pr_mes = meas.pr_mes();
+
+                        let (min_pr, max_pr) = opts.pr_window;
+                        if !(min_pr..=max_pr).contains(&pr_mes) {
+                            warn!("{} rejected: pseudo range {:.3}km", sv, pr_mes * 1.0E-3);
+                            continue;
+                        }
+
+                        // new ambiguity on loss of lock, or on reacquisition after a gap
+                        let tracked =
+                            locks.update(sv, carrier, tow.epoch(TimeScale::GPST), meas.lock_time());
+
+                        // noisy or ambiguous phase is dropped, pseudo range is preserved
+                        let trk_stat = meas.trk_stat().bits();
+                        let cp_std = cp_stdev(meas.cp_stdev().bits());
+                        let phase_valid = if trk_stat & TRK_STAT_CP_VALID == 0 {
+                            false
+                        } else if trk_stat & TRK_STAT_HALF_CYC == 0 {
+                            debug!("{} phase rejected: half cycle ambiguity", sv);
+                            false
+                        } else if opts.max_cp_stdev.is_some_and(|max| cp_std > max) {
+                            debug!("{} phase rejected: stdev {} cycles", sv, cp_std);
+                            false
+                        } else {
+                            true
+                        };
+
+                        let phase_range = if opts.use_phase && phase_valid {
+                            phase_range(carrier, cp_mes, tracked)
+                        } else {
+                            vec![]
+                        };
+
+                        if opts.combinations {
+                            combiner.push(sv, carrier, pr_mes, phase_valid.then_some(cp_mes));
+                        }
+
+                        // fast corrections apply to the L1 C/A pseudo range only
+                        if opts.sbas && gnss == Constellation::GPS && carrier == Carrier::L1 {
+                            pr_mes = fast_corrections.correct_pseudo_range(
+                                sv.prn,
+                                pr_mes,
+                                tow.epoch(TimeScale::GPST),
+                            );
+                        }
+
+                        let t = tow.epoch(TimeScale::GPST);
 
-                        candidates.push(Candidate::new(
+                        // SV is not corrected until it contributed to a fix. Out of the maps'
+                        // coverage, the broadcast model applies, once received.
+                        if let (Some(ionex), Some((lat, lon, sv_geometry))) =
+                            (opts.ionex.as_ref(), geometry.as_ref())
+                        {
+                            let delay = sv_geometry
+                                .iter()
+                                .find(|(used, _, _)| *used == sv)
+                                .and_then(|(_, elev, azim)| {
+                                    let frequency = carrier_frequency_hz(carrier);
+                                    ionex
+                                        .slant_delay(t, *lat, *lon, *elev, *azim, frequency)
+                                        .or_else(|| {
+                                            klobuchar.map(|model| {
+                                                model.slant_delay(
+                                                    t, *lat, *lon, *elev, *azim, frequency,
+                                                )
+                                            })
+                                        })
+                                });
+                            if let Some(delay) = delay {
+                                debug!("{} ionosphere delay: {:.3}m", sv, delay);
+                                pr_mes -= delay;
+                            }
+                        }
+                        let clock_corr = match opts.clocks.as_ref() {
+                            Some(clocks) => match clocks.clock_correction(t, sv) {
+                                Some(corr) => corr,
+                                None if opts.strict_clocks => {
+                                    debug!("{} withheld: no clock correction", sv);
+                                    continue;
+                                },
+                                None => Duration::default(),
+                            },
+                            None => Duration::default(),
+                        };
+
+                        observations.push(
                             sv,
-                            tow.epoch(TimeScale::GPST), //TODO
-                            Duration::default(),
-                            None,
-                            vec![PseudoRange {
+                            clock_corr,
+                            PseudoRange {
                                 carrier,
-                                value: pr_mes,
+                                value: opts.biases.correct_pseudo_range(carrier, pr_mes),
                                 snr: None, //TODO
-                            }],
-                            vec![PhaseRange {
-                                carrier,
-                                value: cp_mes,
-                                snr: None,       //TODO
-                                ambiguity: None, //TODO ?
-                            }],
-                        ));
+                            },
+                            phase_range,
+                        );
+
+                        // approaching SV: positive doppler, range decreasing.
+                        // One value per SV: its first signal
+                        if !range_rates.iter().any(|(measured, _)| *measured == sv) {
+                            range_rates.push((sv, -do_mes * wavelength(carrier)));
+                        }
+                    }
+                    for comb in combiner.combine() {
+                        let (c1, c2) = comb.carriers;
+                        info!(
+                            "{} {:?}/{:?} wl={} nl={:.3}m mw={} if={:.3}m mp={}",
+                            comb.sv,
+                            c1,
+                            c2,
+                            comb.widelane_phase
+                                .map(|wl| format!("{:.3}m", wl))
+                                .unwrap_or_else(|| "-".to_string()),
+                            comb.narrowlane_code,
+                            comb.melbourne_wubbena
+                                .map(|mw| format!("{:.3}cyc", mw))
+                                .unwrap_or_else(|| "-".to_string()),
+                            comb.iono_free_code,
+                            comb.multipath
+                                .map(|(mp1, mp2)| format!("{:.3}m/{:.3}m", mp1, mp2))
+                                .unwrap_or_else(|| "-".to_string()),
+                        );
+                    }
+                },
+                UbxPacketRef::RxmSfrbx(sfrbx) => {
+                    if opts.sbas && sfrbx.gnss_id() == 1 {
+                        match SbasMessage::decode(sfrbx.dwrd()) {
+                            // received since the latest measurements
+                            Some(msg) => fast_corrections.update(
+                                sfrbx.sv_id(),
+                                &msg,
+                                tow.epoch(TimeScale::GPST),
+                            ),
+                            None => {
+                                debug!("S{} invalid SBAS message", sfrbx.sv_id());
+                            },
+                        }
+                    }
+                    // GPS LNAV subframes: broadcast ionosphere model, out of the maps' coverage
+                    if opts.ionex.is_some() && sfrbx.gnss_id() == 0 {
+                        if let Some(model) = Klobuchar::decode(sfrbx.dwrd()) {
+                            if klobuchar != Some(model) {
+                                info!("broadcast ionosphere model: {:?}", model);
+                                klobuchar = Some(model);
+                            }
+                        }
+                    }
+                    // E1-B (sigId 1, named reserved1 by the ublox crate) I/NAV pages
+                    #[cfg(feature = "osnma")]
+                    if opts.osnma && sfrbx.gnss_id() == 2 && sfrbx.reserved1() == 1 {
+                        if let Ok(sv) = sv_rtk_id(sfrbx.gnss_id(), sfrbx.sv_id()) {
+                            if let Some(status) = osnma.on_page(sv, sfrbx.dwrd()) {
+                                if let Err(e) = tx.blocking_send(Message::Osnma((sv, status))) {
+                                    error!("failed to report osnma status: {}", e);
+                                }
+                            }
+                        }
                     }
                 },
                 UbxPacketRef::NavPvt(sol) => {
-                    let has_time = sol.fix_type() == GpsFix::Fix3D
-                        || sol.fix_type() == GpsFix::GPSPlusDeadReckoning
-                        || sol.fix_type() == GpsFix::TimeOnlyFix;
                     let has_posvel = sol.fix_type() == GpsFix::Fix3D
                         || sol.fix_type() == GpsFix::GPSPlusDeadReckoning;
 
@@ -271,11 +1612,25 @@ impl Ublox {
                         );
                     }
 
-                    if has_time {
-                        let time: DateTime<Utc> = (&sol)
-                            .try_into()
-                            .expect("Could not parse NAV-PVT time field to UTC");
-                        println!("Time: {:?}", time);
+                    // accuracies are given in mm
+                    let fix = has_posvel.then(|| ReceiverPosition {
+                        lat: sol.lat_degrees(),
+                        lon: sol.lon_degrees(),
+                        alt: sol.height_meters(),
+                        h_acc: sol.horiz_accuracy() as f64 * 1.0E-3,
+                        v_acc: sol.vert_accuracy() as f64 * 1.0E-3,
+                    });
+                    if let Err(e) = tx.blocking_send(Message::ReceiverFix(fix)) {
+                        error!("failed to report receiver fix: {}", e);
+                    }
+
+                    // falls back to the latest RAWX time, if any
+                    if let Some((time, source)) = receiver_time(pvt_time(&sol), tow) {
+                        debug!("Time: {} ({:?})", time, source);
+                        if time_source != Some(source) {
+                            info!("time source: {:?}", source);
+                            time_source = Some(source);
+                        }
                     }
                 },
                 // Others
@@ -296,8 +1651,963 @@ impl Ublox {
                 },
             }) {
                 Ok(_) => {},
-                Err(e) => {},
+                Err(e) => {
+                    if e.kind() == IoErrorKind::UnexpectedEof {
+                        info!("{}", e);
+                        return;
+                    }
+                },
             }
         }
     }
 }
+
+impl Source for Ublox {
+    fn run(&mut self) {
+        self.tasklet();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::VecDeque, sync::Mutex};
+    use tokio::sync::mpsc;
+
+    /// Scripted [Port]: each read returns the next chunk. Past the script, reads
+    /// time out like an idle serial port, or end like a replayed stream.
+    /// Each write is answered with the next reply, and captured.
+    struct MockPort {
+        reads: VecDeque<Vec<u8>>,
+        replies: VecDeque<Vec<u8>>,
+        eof: bool,
+        written: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Read for MockPort {
+        fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+            match self.reads.pop_front() {
+                Some(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                },
+                None if self.eof => Err(IoError::new(IoErrorKind::UnexpectedEof, "end of script")),
+                None => Err(IoError::new(IoErrorKind::TimedOut, "idle")),
+            }
+        }
+    }
+
+    impl Write for MockPort {
+        fn write(&mut self, data: &[u8]) -> IoResult<usize> {
+            self.written.lock().unwrap().extend_from_slice(data);
+            if let Some(reply) = self.replies.pop_front() {
+                self.reads.push_back(reply);
+            }
+            Ok(data.len())
+        }
+        fn flush(&mut self) -> IoResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Builds a device exchanging with a [MockPort], returns it with
+    /// the written bytes and the produced [Message]s
+    fn device(
+        reads: Vec<Vec<u8>>,
+        replies: Vec<Vec<u8>>,
+        eof: bool,
+        opts: Opts,
+    ) -> (Ublox, Arc<Mutex<Vec<u8>>>, mpsc::Receiver<Message>) {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let (_, rx) = mpsc::channel(16);
+        let (tx, messages) = mpsc::channel(64);
+        let ublox = Ublox {
+            rx,
+            tx,
+            port: Box::new(MockPort {
+                reads: reads.into(),
+                replies: replies.into(),
+                eof,
+                written: written.clone(),
+            }),
+            parser: Default::default(),
+            opts,
+            recorder: None,
+            parser_monitor: ParserMonitor::default(),
+        };
+        (ublox, written, messages)
+    }
+
+    /// Builds a device reading `reads` from a [MockPort], returns it with the written bytes
+    fn mock(reads: Vec<Vec<u8>>, ack_retries: u8) -> (Ublox, Arc<Mutex<Vec<u8>>>) {
+        let opts = Opts {
+            ack_timeout: StdDuration::from_millis(50),
+            ack_retries,
+            ..Default::default()
+        };
+        let (ublox, written, _) = device(reads, vec![], false, opts);
+        (ublox, written)
+    }
+
+    /// Runs the tasklet over a replayed stream of `frames`,
+    /// returns the proposed candidates: epoch and SV
+    fn replay(frames: Vec<Vec<u8>>, opts: Opts) -> Vec<(Epoch, Vec<SV>)> {
+        let (mut ublox, _, mut messages) = device(frames, vec![], true, opts);
+        ublox.tasklet();
+        let mut proposals = Vec::new();
+        while let Ok(msg) = messages.try_recv() {
+            if let Message::Candidates((t, candidates)) = msg {
+                proposals.push((t, candidates.iter().map(|cd| cd.sv).collect()));
+            }
+        }
+        proposals
+    }
+
+    /// Processing options accepting all measurements
+    fn processing_opts() -> Opts {
+        Opts {
+            pr_window: (1.0E6, 1.0E8),
+            ..Default::default()
+        }
+    }
+
+    /// ACK-ACK (or ACK-NAK) of message `M`
+    fn ack<M: UbxPacketMeta>(acked: bool) -> Vec<u8> {
+        ubx_frame(0x05, acked as u8, &[M::CLASS, M::ID])
+    }
+
+    /// MON-VER frame, reporting protocol version `protver`
+    fn mon_ver(protver: &str) -> Vec<u8> {
+        let mut payload = vec![0; 70];
+        payload[..21].copy_from_slice(b"ROM SPG 5.10 (7b202e)");
+        payload[30..38].copy_from_slice(b"00190000");
+        let extension = format!("PROTVER={}", protver);
+        payload[40..40 + extension.len()].copy_from_slice(extension.as_bytes());
+        ubx_frame(0x0A, 0x04, &payload)
+    }
+
+    /// RXM-RAWX frame: one measurement per (gnssId, svId, sigId, pseudo range)
+    fn rawx(week: u16, tow_ms: u32, measurements: &[(u8, u8, u8, f64)]) -> Vec<u8> {
+        let measurements = measurements
+            .iter()
+            .map(|&(gnss_id, sv_id, sig_id, pr)| (gnss_id, sv_id, sig_id, pr, 0.0))
+            .collect::<Vec<_>>();
+        rawx_doppler(week, tow_ms, &measurements)
+    }
+
+    /// RXM-RAWX frame: one measurement per (gnssId, svId, sigId, pseudo range, doppler)
+    fn rawx_doppler(week: u16, tow_ms: u32, measurements: &[(u8, u8, u8, f64, f32)]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&(tow_ms as f64 * 1.0E-3).to_le_bytes());
+        payload.extend_from_slice(&week.to_le_bytes());
+        payload.extend_from_slice(&[18, measurements.len() as u8, 0, 1, 0, 0]);
+        for (gnss_id, sv_id, sig_id, pr, doppler) in measurements {
+            payload.extend_from_slice(&pr.to_le_bytes());
+            payload.extend_from_slice(&0.0_f64.to_le_bytes());
+            payload.extend_from_slice(&doppler.to_le_bytes());
+            payload.extend_from_slice(&[*gnss_id, *sv_id, *sig_id, 0]);
+            // lock time (ms), C/N0, stdevs, trkStat: pseudo range valid
+            payload.extend_from_slice(&5000_u16.to_le_bytes());
+            payload.extend_from_slice(&[40, 2, 1, 1, 0x01, 0]);
+        }
+        ubx_frame(0x02, 0x15, &payload)
+    }
+
+    /// NAV-EOE frame
+    fn eoe(tow_ms: u32) -> Vec<u8> {
+        ubx_frame(0x01, 0x61, &tow_ms.to_le_bytes())
+    }
+
+    #[test]
+    fn phase_cycles_to_meters() {
+        let tracked = Duration::from_seconds(5.0);
+        for (carrier, cycles, meters) in [
+            (Carrier::L1, 1.0E8, 19_029_367.279836),
+            (Carrier::L2, 1.0E8, 24_421_021.342457),
+            (Carrier::E5A, 1.0E8, 25_482_804.879085),
+        ] {
+            let phase = phase_range(carrier, cycles, tracked);
+            assert_eq!(phase.len(), 1);
+            assert_eq!(phase[0].carrier, carrier);
+            assert!(
+                (phase[0].value - meters).abs() < 1.0E-3,
+                "{:?}: {}",
+                carrier,
+                phase[0].value
+            );
+        }
+
+        // freshly acquired: withheld
+        let tracked = Duration::from_milliseconds(500.0);
+        assert!(phase_range(Carrier::L1, 1.0E8, tracked).is_empty());
+    }
+
+    #[test]
+    fn wait_for_ack_accepts_ack() {
+        let (mut ublox, _) = mock(vec![ack::<CfgNav5>(true)], 0);
+        assert!(ublox.wait_for_ack::<CfgNav5>().is_ok());
+    }
+
+    #[test]
+    fn wait_for_ack_rejects_nak() {
+        let (mut ublox, _) = mock(vec![ack::<CfgNav5>(false)], 0);
+        let e = ublox.wait_for_ack::<CfgNav5>().unwrap_err();
+        assert_eq!(e.kind(), IoErrorKind::Other);
+    }
+
+    #[test]
+    fn wait_for_ack_times_out() {
+        // nothing received
+        let (mut ublox, _) = mock(vec![], 0);
+        let e = ublox.wait_for_ack::<CfgNav5>().unwrap_err();
+        assert_eq!(e.kind(), IoErrorKind::TimedOut);
+
+        // the ACK of another message does not count
+        let (mut ublox, _) = mock(vec![ack::<CfgTp5>(true)], 0);
+        let e = ublox.wait_for_ack::<CfgNav5>().unwrap_err();
+        assert_eq!(e.kind(), IoErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn write_acked_retries_on_timeout() {
+        let data = dyn_model_frame(DynModel::Portable);
+
+        let (mut ublox, written) = mock(vec![], 1);
+        let e = ublox.write_acked(CfgNav5, &data).unwrap_err();
+        assert_eq!(e.kind(), IoErrorKind::TimedOut);
+        assert_eq!(*written.lock().unwrap(), [data, data].concat());
+
+        let (mut ublox, written) = mock(vec![ack::<CfgNav5>(true)], 1);
+        assert!(ublox.write_acked(CfgNav5, &data).is_ok());
+        assert_eq!(*written.lock().unwrap(), data);
+    }
+
+    #[test]
+    fn delayed_ack_succeeds_on_retry() {
+        let data = dyn_model_frame(DynModel::Stationary);
+        let opts = Opts {
+            ack_timeout: StdDuration::from_millis(50),
+            ack_retries: 2,
+            ..Default::default()
+        };
+        // first write not acknowledged in time, the second one is
+        let replies = vec![vec![], ack::<CfgNav5>(true)];
+        let (mut ublox, written, _) = device(vec![], replies, false, opts);
+        assert!(ublox.write_acked(CfgNav5, &data).is_ok());
+        assert_eq!(*written.lock().unwrap(), [data, data].concat());
+    }
+
+    #[test]
+    fn update_reassembles_split_frames() {
+        // unknown MON frame: payload handed over as is
+        let payload = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        let frame = ubx_frame(0x0A, 0x99, &payload);
+        // split within the header, then within the payload
+        let reads = vec![
+            frame[..3].to_vec(),
+            frame[3..500].to_vec(),
+            frame[500..].to_vec(),
+        ];
+        let (mut ublox, _) = mock(reads, 0);
+        let mut parsed = Vec::new();
+        ublox
+            .update(|packet| {
+                if let UbxPacketRef::Unknown(packet) = packet {
+                    parsed.push((packet.class, packet.msg_id, packet.payload.to_vec()));
+                }
+            })
+            .unwrap();
+        assert_eq!(parsed, vec![(0x0A, 0x99, payload)]);
+        assert_eq!(ublox.parser_monitor.stats.malformed, 0);
+        assert_eq!(ublox.parser.buffer_len(), 0);
+    }
+
+    #[test]
+    fn malformed_packet_counted() {
+        let mut corrupt = ubx_frame(0x0A, 0x99, &[1, 2, 3, 4]);
+        *corrupt.last_mut().unwrap() ^= 0xFF;
+        let valid = ubx_frame(0x0A, 0x99, &[5, 6, 7, 8]);
+        let (mut ublox, _) = mock(vec![corrupt, valid], 0);
+        let mut parsed = Vec::new();
+        ublox
+            .update(|packet| {
+                if let UbxPacketRef::Unknown(packet) = packet {
+                    parsed.push(packet.payload.to_vec());
+                }
+            })
+            .unwrap();
+        assert_eq!(parsed, vec![vec![5, 6, 7, 8]]);
+        assert_eq!(ublox.parser_monitor.stats.malformed, 1);
+
+        // reported once the interval elapsed, and only on change
+        let mut monitor = ublox.parser_monitor;
+        assert!(monitor.report().is_none());
+        monitor.reported.1 = Instant::now().checked_sub(PARSER_REPORT_INTERVAL).unwrap();
+        let stats = monitor.report().unwrap();
+        assert_eq!(stats.malformed, 1);
+        assert_eq!(stats.dropped_bytes, 0);
+        monitor.reported.1 = Instant::now().checked_sub(PARSER_REPORT_INTERVAL).unwrap();
+        assert!(monitor.report().is_none());
+    }
+
+    #[test]
+    fn one_candidate_per_sv() {
+        let gps = |prn| SV::new(Constellation::GPS, prn);
+        // G03 on L1 C/A, L2CL and L5Q, G07 on L1 C/A
+        let frames = vec![
+            rawx(
+                2304,
+                1000,
+                &[
+                    (0, 3, 0, 21.0E6),
+                    (0, 7, 0, 22.0E6),
+                    (0, 3, 3, 21.0E6 + 2.5),
+                    (0, 3, 7, 21.0E6 + 3.1),
+                ],
+            ),
+            eoe(1000),
+        ];
+        let proposals = replay(frames, processing_opts());
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].1, vec![gps(3), gps(7)]);
+
+        let t = Epoch::from_time_of_week(2304, 1_000_000_000, TimeScale::GPST);
+        let pr = |carrier, value| PseudoRange {
+            carrier,
+            value,
+            snr: None,
+        };
+        let mut observations = Observations::default();
+        observations.push(gps(3), Duration::default(), pr(Carrier::L1, 21.0E6), vec![]);
+        observations.push(gps(7), Duration::default(), pr(Carrier::L1, 22.0E6), vec![]);
+        observations.push(gps(3), Duration::default(), pr(Carrier::L2, 21.0E6), vec![]);
+        // same carrier, tracked twice
+        observations.push(gps(3), Duration::default(), pr(Carrier::L2, 21.5E6), vec![]);
+        assert_eq!(observations.len(), 2);
+        let (sv, _, codes, _) = &observations.sv[0];
+        assert_eq!(*sv, gps(3));
+        assert_eq!(
+            codes
+                .iter()
+                .map(|pr| (pr.carrier, pr.value))
+                .collect::<Vec<_>>(),
+            vec![(Carrier::L1, 21.0E6), (Carrier::L2, 21.0E6)]
+        );
+        let candidates = observations.candidates(t);
+        assert_eq!(candidates.len(), 2);
+        assert!(observations.is_empty());
+
+        // QZSS L1S would collide with L1 C/A
+        assert_eq!(signal_rtk_id(5, 0).unwrap(), Carrier::L1);
+        assert!(signal_rtk_id(5, 1).is_err());
+    }
+
+    #[test]
+    fn candidates_proposed_on_end_of_epoch() {
+        let gps = |prn| SV::new(Constellation::GPS, prn);
+        let frames = vec![
+            rawx(2304, 1000, &[(0, 3, 0, 21.0E6), (0, 7, 0, 22.0E6)]),
+            eoe(1000),
+            rawx(2304, 2000, &[(0, 3, 0, 21.0E6)]),
+            rawx(2304, 3000, &[(0, 12, 0, 23.0E6), (2, 5, 1, 24.0E6)]),
+            eoe(3000),
+            // epoch not ended
+            rawx(2304, 4000, &[(0, 3, 0, 21.0E6)]),
+        ];
+        let proposals = replay(frames, processing_opts());
+
+        let t = |tow_ms: u64| Epoch::from_time_of_week(2304, tow_ms * 1_000_000, TimeScale::GPST);
+        assert_eq!(
+            proposals,
+            vec![
+                (t(1000), vec![gps(3), gps(7)]),
+                // both measurement bursts, on end of epoch
+                (
+                    t(3000),
+                    vec![gps(3), gps(12), SV::new(Constellation::Galileo, 5)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn stale_rawx_skipped() {
+        let frames = vec![
+            rawx(2304, 1000, &[(0, 3, 0, 21.0E6)]),
+            // sent again
+            rawx(2304, 1000, &[(0, 3, 0, 21.0E6)]),
+            eoe(1000),
+            // late
+            rawx(2304, 500, &[(0, 7, 0, 22.0E6)]),
+            rawx(2304, 2000, &[(0, 5, 0, 23.0E6)]),
+            eoe(2000),
+            // week rollover
+            rawx(2304, 604_799_000, &[(0, 3, 0, 21.0E6)]),
+            eoe(604_799_000),
+            rawx(2305, 0, &[(0, 7, 0, 22.0E6)]),
+            eoe(0),
+        ];
+        let proposals = replay(frames, processing_opts());
+
+        let t = |week: u32, tow_ms: u64| {
+            Epoch::from_time_of_week(week, tow_ms * 1_000_000, TimeScale::GPST)
+        };
+        let gps = |prn| SV::new(Constellation::GPS, prn);
+        assert_eq!(
+            proposals,
+            vec![
+                (t(2304, 1000), vec![gps(3)]),
+                (t(2304, 2000), vec![gps(5)]),
+                (t(2304, 604_799_000), vec![gps(3)]),
+                (t(2305, 0), vec![gps(7)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn antenna_state_from_mon_hw() {
+        // aStatus: 0 init, 1 unknown, 2 ok, 3 short, 4 open. aPower: 0 off, 1 on, 2 unknown
+        let mon_hw = |status: u8, power: u8| {
+            let mut payload = vec![0; 60];
+            payload[20] = status;
+            payload[21] = power;
+            ubx_frame(0x0A, 0x09, &payload)
+        };
+        let frames = vec![
+            mon_hw(2, 1),
+            mon_hw(2, 1),
+            mon_hw(3, 1),
+            mon_hw(4, 0),
+            mon_hw(2, 0),
+            mon_hw(0, 1),
+            mon_hw(1, 2),
+        ];
+        let (mut ublox, _, mut messages) = device(frames, vec![], true, processing_opts());
+        ublox.tasklet();
+
+        // reported on change only
+        let states = std::iter::from_fn(|| messages.try_recv().ok())
+            .filter_map(|msg| match msg {
+                Message::Antenna(state) => Some(state),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            states,
+            [
+                AntennaState::Ok,
+                AntennaState::Short,
+                AntennaState::Open,
+                AntennaState::Off,
+                AntennaState::Unknown,
+            ]
+        );
+        assert!(states[1].is_fault() && states[2].is_fault());
+        assert!(!states[3].is_fault());
+    }
+
+    #[test]
+    fn time_falls_back_to_rawx() {
+        let tow = Tow {
+            week: 2304,
+            tow: 1000,
+        };
+        let rawx_time = Epoch::from_time_of_week(2304, 1_000_000_000, TimeScale::GPST);
+        assert_eq!(
+            receiver_time(None, tow),
+            Some((rawx_time, TimeSource::Rawx))
+        );
+        assert_eq!(receiver_time(None, Tow::default()), None);
+
+        let nav_time = Utc.with_ymd_and_hms(2024, 3, 3, 0, 0, 0).unwrap();
+        let (time, source) = receiver_time(Some(nav_time), tow).unwrap();
+        assert_eq!(source, TimeSource::NavPvt);
+        assert_eq!(time, Epoch::from_gregorian_utc_at_midnight(2024, 3, 3));
+
+        // 3D fix, without valid date nor time: RAWX time is selected
+        let mut payload = vec![0; 92];
+        payload[20] = 0x03;
+        let frame = ubx_frame(0x01, 0x07, &payload);
+        let mut parser = UbxParser::default();
+        let mut packets = parser.consume(&frame);
+        match packets.next() {
+            Some(Ok(UbxPacketRef::NavPvt(sol))) => {
+                assert_eq!(sol.fix_type(), GpsFix::Fix3D);
+                assert_eq!(pvt_time(&sol), None);
+                assert_eq!(
+                    receiver_time(pvt_time(&sol), tow),
+                    Some((rawx_time, TimeSource::Rawx))
+                );
+            },
+            packet => panic!("unexpected packet: {:?}", packet),
+        }
+    }
+
+    #[test]
+    fn replay_file() {
+        let path = std::env::temp_dir().join("rt-navi-replay-test.ubx");
+        let frames = [
+            rawx(2304, 1000, &[(0, 3, 0, 21.0E6), (5, 193, 0, 38.0E6)]),
+            eoe(1000),
+        ];
+        std::fs::write(&path, frames.concat()).unwrap();
+
+        let (_, rx) = mpsc::channel(16);
+        let (tx, mut messages) = mpsc::channel(64);
+        let mut ublox = Ublox::replay(&path, processing_opts(), rx, tx).unwrap();
+        // returns at the end of the stream
+        ublox.run();
+
+        let mut proposals = Vec::new();
+        while let Ok(msg) = messages.try_recv() {
+            if let Message::Candidates((_, candidates)) = msg {
+                proposals.push(candidates.iter().map(|cd| cd.sv).collect::<Vec<_>>());
+            }
+        }
+        assert_eq!(
+            proposals,
+            vec![vec![
+                SV::new(Constellation::GPS, 3),
+                SV::new(Constellation::QZSS, 1)
+            ]]
+        );
+    }
+
+    #[test]
+    fn recording_on_frame_boundaries() {
+        let path = std::env::temp_dir().join("rt-navi-record-test.ubx");
+        let first = rawx(2304, 1000, &[(0, 3, 0, 21.0E6)]);
+        let second = eoe(1000);
+        let mut corrupt = eoe(2000);
+        *corrupt.last_mut().unwrap() ^= 0xFF;
+        let partial = rawx(2304, 2000, &[(0, 3, 0, 21.0E6)]);
+
+        let mut recorder = Recorder::new(&path).unwrap();
+        let nmea = b"$GNGGA,,,,,,0,00,99.99,,,,,,*56\r\n";
+        let (head, tail) = first.split_at(9);
+        for chunk in [
+            [&nmea[..], head].concat(),
+            tail.to_vec(),
+            corrupt,
+            // sync chars split across reads
+            second[..1].to_vec(),
+            second[1..].to_vec(),
+            vec![0xB5, 0x62, 0xFF, 0xFF],
+            partial[..20].to_vec(),
+        ] {
+            recorder.consume(&chunk).unwrap();
+        }
+        drop(recorder);
+
+        // complete and valid frames only
+        let recorded = std::fs::read(&path).unwrap();
+        assert_eq!(recorded, [first, second].concat());
+    }
+
+    #[test]
+    fn unsupported_signals_skipped() {
+        let signals = vec![
+            ExtendedSignal::GpsL5,
+            ExtendedSignal::GalE5a,
+            ExtendedSignal::GalE6,
+        ];
+        let opts = Opts {
+            signals: signals.clone(),
+            ack_timeout: StdDuration::from_millis(50),
+            ..Default::default()
+        };
+        // L5 rejected, E5a not answered, E6 acknowledged
+        let replies = vec![
+            mon_ver("27.11"),
+            ack::<CfgValSet>(false),
+            vec![],
+            ack::<CfgValSet>(true),
+        ];
+        let (mut ublox, written, _) = device(vec![], replies, false, opts);
+        ublox.enable_signals();
+
+        // every signal was attempted, init went on
+        let mut expected = UbxPacketRequest::request_for::<MonVer>()
+            .into_packet_bytes()
+            .to_vec();
+        for signal in signals.iter() {
+            expected.extend_from_slice(&signal.valset_frame(LAYER_RAM));
+        }
+        assert_eq!(*written.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn valset_written_to_persistent_layers() {
+        let opts = Opts {
+            signals: vec![ExtendedSignal::GalE5a],
+            persist: true,
+            ack_timeout: StdDuration::from_millis(50),
+            ..Default::default()
+        };
+        let replies = vec![mon_ver("27.11"), ack::<CfgValSet>(true)];
+        let (mut ublox, written, _) = device(vec![], replies, false, opts);
+        ublox.enable_signals();
+
+        // RAM, BBR and flash: CFG-SIGNAL-GAL_E5A_ENA = 1
+        let written = written.lock().unwrap();
+        let poll_len = UbxPacketRequest::request_for::<MonVer>()
+            .into_packet_bytes()
+            .len();
+        let valset = &written[poll_len..];
+        assert_eq!(valset[2..6], [0x06, 0x8A, 9, 0]);
+        assert_eq!(
+            valset[6..15],
+            [0x00, 0x07, 0x00, 0x00, 0x09, 0x00, 0x31, 0x10, 0x01]
+        );
+        assert_eq!(valset.len(), 17);
+        assert_eq!(ubx_checksum(&valset[2..15]), (valset[15], valset[16]));
+    }
+
+    #[test]
+    fn signals_skipped_on_old_protocol() {
+        let opts = Opts {
+            signals: vec![ExtendedSignal::GpsL5],
+            ..Default::default()
+        };
+        let (mut ublox, written, _) = device(vec![], vec![mon_ver("18.00")], false, opts);
+        ublox.enable_signals();
+
+        // MON-VER poll only
+        let poll = UbxPacketRequest::request_for::<MonVer>().into_packet_bytes();
+        assert_eq!(*written.lock().unwrap(), poll);
+    }
+
+    #[test]
+    fn message_ports() {
+        // i2c, uart1, uart2, usb, spi, then the reserved port
+        let ports = MessagePorts::from_str("uart1, USB").unwrap();
+        assert_eq!(ports.rates(), [0, 1, 0, 1, 0, 0]);
+        assert_eq!(MessagePorts::default().rates(), [0, 1, 1, 1, 0, 0]);
+        assert!(MessagePorts::from_str("uart3").is_err());
+
+        // every message is enabled on the USB port only
+        let opts = Opts {
+            ports: MessagePorts::from_str("usb").unwrap(),
+            ack_timeout: StdDuration::from_millis(50),
+            ..Default::default()
+        };
+        let acks = vec![ack::<CfgMsgAllPorts>(true); 4];
+        let (mut ublox, written, _) = device(vec![], acks, false, opts);
+        ublox.init().unwrap();
+
+        let expected = [(0x01, 0x07), (0x01, 0x14), (0x01, 0x61), (0x02, 0x15)]
+            .iter()
+            .map(|(class, id)| ubx_frame(0x06, 0x01, &[*class, *id, 0, 0, 0, 1, 0, 0]))
+            .collect::<Vec<_>>();
+        assert_eq!(*written.lock().unwrap(), expected.concat());
+    }
+
+    #[test]
+    fn secondary_reports_fixes_only() {
+        let opts = Opts {
+            start: Some(StartType::Cold),
+            sbas: true,
+            dyn_model: Some(DynModel::Automotive),
+            ports: MessagePorts::from_str("usb").unwrap(),
+            ack_timeout: StdDuration::from_millis(50),
+            ..Default::default()
+        };
+        let opts = opts.secondary();
+        assert!(opts.start.is_none());
+
+        // no restart, nor other configuration than NAV-PVT
+        let acks = vec![ack::<CfgMsgAllPorts>(true)];
+        let (mut ublox, written, _) = device(vec![], acks, false, opts);
+        ublox.init().unwrap();
+        assert_eq!(
+            *written.lock().unwrap(),
+            ubx_frame(0x06, 0x01, &[0x01, 0x07, 0, 0, 0, 1, 0, 0])
+        );
+    }
+
+    #[test]
+    fn warmup_drains_boot_messages() {
+        let opts = |warmup| Opts {
+            warmup,
+            ack_timeout: StdDuration::from_millis(50),
+            ..Default::default()
+        };
+        // sent while booting, before any configuration
+        let boot = || vec![ack::<CfgMsgAllPorts>(false)];
+        let acks = || vec![ack::<CfgMsgAllPorts>(true); 4];
+
+        // mistaken for the answer to the first configuration frame
+        let (mut ublox, _, _) = device(boot(), acks(), false, opts(None));
+        assert!(ublox.init().is_err());
+
+        let warmup = StdDuration::from_millis(100);
+        let (mut ublox, written, _) = device(boot(), acks(), false, opts(Some(warmup)));
+        let t0 = Instant::now();
+        ublox.init().unwrap();
+        assert!(t0.elapsed() >= warmup);
+        // four CFG-MSG frames
+        assert_eq!(written.lock().unwrap().len(), 4 * 16);
+    }
+
+    #[test]
+    fn restart_reset_mask() {
+        // navBbrMask, then GNSS only controlled software reset
+        for (start, mask) in [
+            (StartType::Cold, [0xFF, 0xFF]),
+            (StartType::Warm, [0x01, 0x00]),
+            (StartType::Hot, [0x00, 0x00]),
+        ] {
+            let (mut ublox, written) = mock(vec![], 0);
+            ublox.restart(start);
+            let expected = ubx_frame(0x06, 0x04, &[mask[0], mask[1], 0x02, 0x00]);
+            assert_eq!(*written.lock().unwrap(), expected, "{:?}", start);
+        }
+    }
+
+    #[test]
+    fn timepulse_parameters() {
+        let frame = timepulse_frame(10, 25.4);
+        assert_eq!(frame[2..6], [0x06, 0x31, 32, 0]);
+        let payload = &frame[6..38];
+        let u32_at = |pos: usize| u32::from_le_bytes(payload[pos..pos + 4].try_into().unwrap());
+
+        // TIMEPULSE, message version 1
+        assert_eq!(payload[0..2], [0, 1]);
+        // cable delay (ns), rounded
+        assert_eq!(i16::from_le_bytes([payload[4], payload[5]]), 25);
+        // 10Hz, with or without GNSS lock
+        assert_eq!(u32_at(8), 10);
+        assert_eq!(u32_at(12), 10);
+        // 50% duty cycle: 50ms
+        assert_eq!(u32_at(16), 50_000);
+        assert_eq!(u32_at(20), 50_000);
+        // active, locked to GNSS, frequency and length, aligned to top of second, rising
+        assert_eq!(u32_at(28), 0x7F);
+    }
+
+    #[test]
+    fn high_precision_position() {
+        let mut payload = vec![0; 36];
+        // coarse components (1e-7 deg, mm) then high precision ones (1e-9 deg, 0.1 mm)
+        payload[8..12].copy_from_slice(&22_944_813_i32.to_le_bytes());
+        payload[12..16].copy_from_slice(&488_583_701_i32.to_le_bytes());
+        payload[16..20].copy_from_slice(&35_123_i32.to_le_bytes());
+        payload[24..27].copy_from_slice(&[(-45_i8) as u8, 23, 7]);
+        // accuracies (0.1 mm)
+        payload[28..32].copy_from_slice(&141_u32.to_le_bytes());
+        payload[32..36].copy_from_slice(&205_u32.to_le_bytes());
+
+        let frames = vec![ubx_frame(0x01, 0x14, &payload)];
+        let (mut ublox, _, mut messages) = device(frames, vec![], true, Opts::default());
+        ublox.tasklet();
+        let position = std::iter::from_fn(|| messages.try_recv().ok())
+            .find_map(|msg| match msg {
+                Message::HpPosition(position) => Some(position),
+                _ => None,
+            })
+            .unwrap();
+
+        assert!((position.lat - 48.858_370_123).abs() < 1.0E-12);
+        assert!((position.lon - 2.294_481_255).abs() < 1.0E-12);
+        assert!((position.alt - 35.1237).abs() < 1.0E-9);
+        assert!((position.h_acc - 0.0141).abs() < 1.0E-9);
+        assert!((position.v_acc - 0.0205).abs() < 1.0E-9);
+    }
+
+    #[test]
+    fn range_rate_from_doppler() {
+        // G05 L1 approaching, G12 L2 receding
+        let frames = vec![
+            rawx_doppler(
+                2304,
+                432_000_000,
+                &[(0, 5, 0, 2.1E7, 1000.0), (0, 12, 3, 2.3E7, -500.0)],
+            ),
+            eoe(432_000_000),
+        ];
+        let (mut ublox, _, mut messages) = device(frames, vec![], true, processing_opts());
+        ublox.tasklet();
+        let rates = std::iter::from_fn(|| messages.try_recv().ok())
+            .find_map(|msg| match msg {
+                Message::RangeRates((_, rates)) => Some(rates),
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].0, SV::new(Constellation::GPS, 5));
+        assert!((rates[0].1 + 190.293_672_798).abs() < 1.0E-6);
+        assert_eq!(rates[1].0, SV::new(Constellation::GPS, 12));
+        assert!((rates[1].1 - 122.105_106_712).abs() < 1.0E-6);
+    }
+
+    #[test]
+    fn missing_port_returns_error() {
+        let (_, rx) = mpsc::channel(1);
+        let (tx, _) = mpsc::channel(1);
+        let serial = SerialOpts {
+            port: "/dev/rt-navi-missing".to_string(),
+            baud: 115_200,
+        };
+        match Ublox::new(serial, Opts::default(), rx, tx) {
+            Err(Error::Port(name, _)) => assert_eq!(name, "/dev/rt-navi-missing"),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("missing port opened"),
+        }
+    }
+
+    #[test]
+    fn verify_reports_mismatches() {
+        // CFG-VALGET response: version 1, RAM, then key/value pairs
+        let valget = |pairs: &[(u32, u8)]| {
+            let mut payload = vec![0x01, 0x00, 0x00, 0x00];
+            for (key, value) in pairs {
+                payload.extend_from_slice(&key.to_le_bytes());
+                payload.push(*value);
+            }
+            ubx_frame(CFG_CLASS, CFG_VALGET_ID, &payload)
+        };
+        let signals = vec![
+            ExtendedSignal::GpsL5,
+            ExtendedSignal::GalE6,
+            ExtendedSignal::BdsB2a,
+        ];
+        let opts = Opts {
+            signals: signals.clone(),
+            ..Default::default()
+        };
+        // L5 applied, E6 read back disabled, B2a key missing
+        let replies = vec![
+            valget(&[(ExtendedSignal::GpsL5.key(), 1)]),
+            valget(&[(ExtendedSignal::GalE6.key(), 0)]),
+            valget(&[(ExtendedSignal::GalE5a.key(), 1)]),
+        ];
+        let (mut ublox, written, _) = device(vec![], replies, false, opts);
+        assert_eq!(
+            ublox.verify_signals(),
+            [ExtendedSignal::GalE6, ExtendedSignal::BdsB2a]
+        );
+        let polls = signals
+            .iter()
+            .flat_map(|signal| signal.valget_frame())
+            .collect::<Vec<_>>();
+        assert_eq!(*written.lock().unwrap(), polls);
+    }
+
+    #[test]
+    fn qzss_measurements_and_messages_associate() {
+        let j01 = SV::new(Constellation::QZSS, 1);
+        // RAWX may number QZSS by PRN, SFRBX by slot
+        assert_eq!(sv_rtk_id(5, 193).unwrap(), j01);
+        assert_eq!(sv_rtk_id(5, 1).unwrap(), j01);
+        assert_eq!(sv_rtk_id(5, 202).unwrap(), SV::new(Constellation::QZSS, 10));
+        // not GPS
+        assert_ne!(sv_rtk_id(0, 1).unwrap(), j01);
+        assert_eq!(sv_rtk_id(0, 1).unwrap(), SV::new(Constellation::GPS, 1));
+    }
+
+    #[test]
+    fn stdev_indexes() {
+        // 0.01 m * 2^n, 0.004 cycle * n: upper bits are reserved
+        assert_eq!(pr_stdev(0), 0.01);
+        assert_eq!(pr_stdev(2), 0.04);
+        assert_eq!(pr_stdev(0xF2), 0.04);
+        assert!((pr_stdev(15) - 327.68).abs() < 1.0E-9);
+        assert!((pr_stdev(5).powi(2) - 0.1024).abs() < 1.0E-12);
+        assert_eq!(cp_stdev(0), 0.0);
+        assert!((cp_stdev(15) - 0.06).abs() < 1.0E-12);
+    }
+
+    #[test]
+    fn noisy_pseudo_ranges_rejected() {
+        // reported stdev index 2: 4 cm
+        let frames = || vec![rawx(2304, 1000, &[(0, 3, 0, 21.0E6)]), eoe(1000)];
+        let opts = |max_pr_stdev| Opts {
+            max_pr_stdev: Some(max_pr_stdev),
+            ..processing_opts()
+        };
+        assert!(replay(frames(), opts(0.03)).is_empty());
+        assert_eq!(replay(frames(), opts(0.04)).len(), 1);
+    }
+
+    #[test]
+    fn pseudo_range_window() {
+        let frames = vec![
+            rawx(
+                2304,
+                1000,
+                &[
+                    (0, 3, 0, 21.0E6),
+                    (0, 7, 0, 12.0E6),
+                    (0, 12, 0, 50.0E6),
+                    (0, 15, 0, 42.0E6),
+                ],
+            ),
+            eoe(1000),
+        ];
+        let opts = Opts {
+            pr_window: (18.0E6, 42.0E6),
+            ..processing_opts()
+        };
+        let gps = |prn| SV::new(Constellation::GPS, prn);
+        let proposals = replay(frames, opts);
+        assert_eq!(proposals[0].1, vec![gps(3), gps(15)]);
+    }
+
+    #[test]
+    fn strict_clocks_withhold_uncorrected() {
+        // precise clocks of G03 only, around week 2304 tow 1s
+        let content = [
+            format!("{:<60}{}", "     3.04           C", "RINEX VERSION / TYPE"),
+            format!("{:<60}{}", "", "END OF HEADER"),
+            "AS G03  2024 03 03 00 00  0.000000  1   -1.234500000000E-04".to_string(),
+            "AS G03  2024 03 03 00 00 30.000000  1   -1.234200000000E-04".to_string(),
+        ]
+        .join("\n");
+        let path = std::env::temp_dir().join("rt-navi-strict-clocks.clk");
+        std::fs::write(&path, content).unwrap();
+        let clocks = Arc::new(PreciseClockSource::from_file(&path).unwrap());
+
+        let frames = || {
+            vec![
+                rawx(2304, 1000, &[(0, 3, 0, 21.0E6), (0, 7, 0, 22.0E6)]),
+                eoe(1000),
+            ]
+        };
+        let opts = |strict_clocks| Opts {
+            clocks: Some(clocks.clone()),
+            strict_clocks,
+            ..processing_opts()
+        };
+        let gps = |prn| SV::new(Constellation::GPS, prn);
+
+        // lenient: G07 proposed without clock correction
+        let proposals = replay(frames(), opts(false));
+        assert_eq!(proposals[0].1, vec![gps(3), gps(7)]);
+        // strict: G07 withheld
+        let proposals = replay(frames(), opts(true));
+        assert_eq!(proposals[0].1, vec![gps(3)]);
+    }
+
+    #[test]
+    fn dyn_model_encoding() {
+        for (flag, value) in [
+            ("portable", 0),
+            ("stationary", 2),
+            ("pedestrian", 3),
+            ("automotive", 4),
+            ("sea", 5),
+            ("airborne1g", 6),
+            ("airborne2g", 7),
+            ("airborne4g", 8),
+            ("wrist", 9),
+            ("bike", 10),
+        ] {
+            let frame = dyn_model_frame(DynModel::from_str(flag).unwrap());
+            assert_eq!(frame[2..6], [0x06, 0x24, 36, 0]);
+            // only the dynamic model is applied
+            assert_eq!(frame[6..9], [0x01, 0x00, value], "{}", flag);
+        }
+        assert!(DynModel::from_str("rocket").is_err());
+    }
+}